@@ -20,6 +20,7 @@ pub struct App<'cx> {
     button_reset: muilib::ButtonView<'cx, Self>,
     toolbar_rect: muilib::RectView,
     rects: Vec<muilib::RectView>,
+    main_body_cache: muilib::LayoutCache,
     event_router: Arc<muilib::EventRouter<'cx, Self>>,
 }
 
@@ -37,7 +38,7 @@ impl<'cx> App<'cx> {
         let window = Arc::new(window);
         let event_router = Arc::new(muilib::EventRouter::new());
         let (ui_context, window_canvas) =
-            muilib::UiContext::create_for_window(resources, window.clone())
+            muilib::UiContext::create_for_window(resources, window.clone(), None, 4)
                 .unwrap_or_else(|e| panic!("{e}"));
 
         // let image = resources.load_image("images/pfp.png").unwrap();
@@ -73,6 +74,7 @@ impl<'cx> App<'cx> {
                         .with_line_width(4.)
                 })
                 .collect(),
+            main_body_cache: muilib::LayoutCache::new(),
             ui_context,
             event_router,
         };
@@ -126,7 +128,7 @@ impl<'cx> App<'cx> {
             hstack.subview(layout.spacer(RectSize::new(f32::INFINITY, 0.)));
         });
 
-        let main_body = layout.vstack(|vstack| {
+        let main_body_vstack = layout.vstack(|vstack| {
             vstack.set_fixed_padding(4.);
             vstack.set_fixed_padding(4.);
             vstack.set_alignment_horizontal(muilib::StackAlignmentHorizontal::Center);
@@ -141,6 +143,8 @@ impl<'cx> App<'cx> {
             }
         });
 
+        let main_body = layout.cached(&mut self.main_body_cache, main_body_vstack);
+
         let root_view = layout.vstack(|vstack| {
             vstack.set_fixed_padding(4.);
             vstack.set_alignment_vertical(muilib::StackAlignmentVertical::Top);
@@ -176,6 +180,7 @@ impl<'cx> App<'cx> {
             .begin_render_pass(&canvas, Theme::DEFAULT.primary_background());
 
         self.ui_context.draw_view(&mut render_pass, root_view);
+        self.ui_context.draw_layout_debug_overlay(&mut render_pass);
     }
 
     fn window_resized(&mut self) {
@@ -183,7 +188,6 @@ impl<'cx> App<'cx> {
             self.ui_context.wgpu_device(),
             self.window.inner_size(),
             self.window.scale_factor(),
-            None,
         );
     }
 }
@@ -211,10 +215,17 @@ impl<'cx> ApplicationHandler for App<'cx> {
                 device_id: _,
                 event,
                 is_synthetic: _,
-            } if event.state.is_pressed() => {
-                if event.logical_key == Key::Named(NamedKey::F5) {
-                    self.window.request_redraw();
-                }
+            } if event.state.is_pressed() && event.logical_key == Key::Named(NamedKey::F5) => {
+                self.window.request_redraw();
+            }
+            WindowEvent::KeyboardInput {
+                device_id: _,
+                event,
+                is_synthetic: _,
+            } if event.state.is_pressed() && event.logical_key == Key::Named(NamedKey::F3) => {
+                let layout_debug_overlay = self.ui_context.layout_debug_overlay();
+                layout_debug_overlay.set_enabled(!layout_debug_overlay.is_enabled());
+                self.window.request_redraw();
             }
             _ => (),
         }
@@ -1,6 +1,15 @@
 #![allow(dead_code)]
 
-use muilib::{ButtonStateStyle, ButtonStyle, Srgb, LineWidth};
+use muilib::{
+    BoxShadow, ButtonStateStyle, ButtonStyle, CornerRadius, Fill, LineWidth, Rgba, Srgb, Srgba,
+    cgmath::vec2,
+};
+
+const HOVERED_BUTTON_SHADOW: Option<BoxShadow> = Some(
+    BoxShadow::new(Rgba::from_hex(0x00000080))
+        .with_offset(vec2(0., 2.))
+        .with_blur_radius(6.),
+);
 
 #[derive(Debug, Clone, Copy)]
 pub struct Theme {
@@ -56,61 +65,76 @@ impl Theme {
             // Normal.
             ButtonStyle {
                 line_width: LineWidth::Uniform(1.),
+                corner_radius: CornerRadius::Uniform(4.),
                 font_size: 12.,
+                nine_slice: None,
                 idle_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0x2A2A2A),
+                    fill_color: Fill::Solid(Srgba::from_hex(0x2A2A2AFF)),
                     line_color: Srgb::from_hex(0x494949),
+                    shadow: None,
                 },
                 hovered_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0x424242),
+                    fill_color: Fill::Solid(Srgba::from_hex(0x424242FF)),
                     line_color: Srgb::from_hex(0xA2A2A2),
+                    shadow: HOVERED_BUTTON_SHADOW,
                 },
                 pressed_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0xA2A2A2),
+                    fill_color: Fill::Solid(Srgba::from_hex(0xA2A2A2FF)),
                     line_color: Srgb::from_hex(0xA2A2A2),
+                    shadow: None,
                 },
             },
             // Primary.
             ButtonStyle {
                 line_width: LineWidth::Uniform(1.),
+                corner_radius: CornerRadius::Uniform(4.),
                 font_size: 12.,
+                nine_slice: None,
                 idle_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0x2C3F71),
+                    fill_color: Fill::Solid(Srgba::from_hex(0x2C3F71FF)),
                     line_color: Srgb::from_hex(0x3D5B9B),
+                    shadow: None,
                 },
                 hovered_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0x5771B2),
+                    fill_color: Fill::Solid(Srgba::from_hex(0x5771B2FF)),
                     line_color: Srgb::from_hex(0x95A0BD),
+                    shadow: HOVERED_BUTTON_SHADOW,
                 },
                 pressed_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0x95A0BD),
+                    fill_color: Fill::Solid(Srgba::from_hex(0x95A0BDFF)),
                     line_color: Srgb::from_hex(0x95A0BD),
+                    shadow: None,
                 },
             },
             // Toxic.
             ButtonStyle {
                 line_width: LineWidth::Uniform(1.),
+                corner_radius: CornerRadius::Uniform(4.),
                 font_size: 12.,
+                nine_slice: None,
                 idle_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0x952727),
+                    fill_color: Fill::Solid(Srgba::from_hex(0x952727FF)),
                     line_color: Srgb::from_hex(0xC83F3F),
+                    shadow: None,
                 },
                 hovered_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0xFF776C),
+                    fill_color: Fill::Solid(Srgba::from_hex(0xFF776CFF)),
                     line_color: Srgb::from_hex(0xFFD0CE),
+                    shadow: HOVERED_BUTTON_SHADOW,
                 },
                 pressed_style: ButtonStateStyle {
                     text_color: Srgb::from_hex(0xFFFFFF),
-                    fill_color: Srgb::from_hex(0xFFD0CE),
+                    fill_color: Fill::Solid(Srgba::from_hex(0xFFD0CEFF)),
                     line_color: Srgb::from_hex(0xFFD0CE),
+                    shadow: None,
                 },
             },
         ],
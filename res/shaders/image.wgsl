@@ -1,9 +1,30 @@
 @group(0) @binding(0) var<uniform> projection: mat4x4<f32>;
 @group(0) @binding(1) var<uniform> aaf: f32;
 
+/// Insets are in the order of left, top, right and bottom.
+struct NineSlice {
+    /// Insets of the corners in the texture, in UV.
+    source: vec4<f32>,
+    /// Insets of the corners in the image, normalized to the size of the image.
+    destination: vec4<f32>,
+};
+
+struct Content {
+    /// Origin and size of where the content is drawn, normalized to the size of the image.
+    bounds: vec4<f32>,
+    /// 1 for repeating the content over the whole image, 0 for transparency outside of `bounds`.
+    tile: u32,
+};
+
 @group(1) @binding(0) var<uniform> model_view: mat4x4<f32>;
 @group(1) @binding(1) var texture: texture_2d<f32>;
 @group(1) @binding(2) var sampler_: sampler;
+@group(1) @binding(3) var<uniform> nine_slice: NineSlice;
+/// Origin and size of the part of the texture that is drawn, in UV.
+@group(1) @binding(4) var<uniform> source_rect: vec4<f32>;
+@group(1) @binding(5) var<uniform> content: Content;
+/// Linear, not premultiplied.
+@group(1) @binding(6) var<uniform> tint: vec4<f32>;
 
 const vertices = array<vec2<f32>, 6>(
     vec2<f32>(0., 0.),
@@ -29,7 +50,48 @@ fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
     return result;
 }
 
+/// Map `p` on one axis of the image to the texture, keeping the insets at both ends unstretched.
+fn nine_slice_axis(
+    p: f32,
+    source_start: f32,
+    source_end: f32,
+    destination_start: f32,
+    destination_end: f32,
+) -> f32 {
+    if p < destination_start {
+        return p / destination_start * source_start;
+    }
+    if p > 1. - destination_end {
+        return 1. - (1. - p) / destination_end * source_end;
+    }
+    let middle = max(1. - destination_start - destination_end, 1e-6);
+    return source_start + (p - destination_start) / middle * (1. - source_start - source_end);
+}
+
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(texture, sampler_, vertex.uv);
+    var p = (vertex.uv - content.bounds.xy) / max(content.bounds.zw, vec2<f32>(1e-6));
+    let inside = all(p >= vec2<f32>(0.)) && all(p <= vec2<f32>(1.));
+    if content.tile != 0u {
+        p = fract(p);
+    }
+    let uv = vec2<f32>(
+            nine_slice_axis(
+                p.x,
+                nine_slice.source.x,
+                nine_slice.source.z,
+                nine_slice.destination.x,
+                nine_slice.destination.z,
+            ),
+            nine_slice_axis(
+                p.y,
+                nine_slice.source.y,
+                nine_slice.source.w,
+                nine_slice.destination.y,
+                nine_slice.destination.w,
+            ),
+        );
+    let color = textureSample(texture, sampler_, source_rect.xy + uv * source_rect.zw) * tint;
+    let visible = inside || content.tile != 0u;
+    return select(vec4<f32>(0.), color, visible);
 }
@@ -17,6 +17,7 @@ struct InstanceInput {
     @location(3) fill_color: vec4<f32>,
     @location(4) line_color: vec4<f32>,
     @location(5) line_width: vec4<f32>,
+    @location(6) corner_radius: vec4<f32>,
 };
 
 struct VertexOutput {
@@ -25,6 +26,8 @@ struct VertexOutput {
     @location(1) fill_color: vec4<f32>,
     @location(2) line_color: vec4<f32>,
     @location(3) line_width: vec4<f32>,
+    @location(4) corner_radius: vec4<f32>,
+    @location(5) size: vec2<f32>,
 };
 
 @vertex
@@ -42,15 +45,86 @@ fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> Vertex
     result.fill_color = instance.fill_color;
     result.line_color = instance.line_color;
     result.line_width = instance.line_width;
+    result.corner_radius = instance.corner_radius;
+    result.size = vec2<f32>(length(model_view[0].xy), length(model_view[1].xy));
     return result;
 }
 
+/// Signed distance from `p` to the edge of a rounded rect centered at the origin, negative inside.
+/// `radius` is in the order of top left, top right, bottom right and bottom left.
+fn rounded_rect_distance(p: vec2<f32>, half_size: vec2<f32>, radius: vec4<f32>) -> f32 {
+    let radius_x = select(radius.xw, radius.yz, p.x > 0.);
+    let r = min(select(radius_x.x, radius_x.y, p.y > 0.), min(half_size.x, half_size.y));
+    let q = abs(p) - half_size + vec2<f32>(r);
+    return min(max(q.x, q.y), 0.) + length(max(q, vec2<f32>(0.))) - r;
+}
+
+/// Fraction of the pixel covered by the shape `distance` is taken from.
+fn coverage(distance: f32) -> f32 {
+    let pixel_width = max(fwidth(distance), 1e-4);
+    return clamp(0.5 - distance / pixel_width, 0., 1.);
+}
+
+/// Color of a rounded rect of `size` with a border inside of its edges, at `uv`.
+fn shade_rect(
+    uv: vec2<f32>,
+    size: vec2<f32>,
+    fill_color: vec4<f32>,
+    line_color: vec4<f32>,
+    normalized_line_width: vec4<f32>,
+    corner_radius: vec4<f32>,
+) -> vec4<f32> {
+    let p = uv * size;
+    let outer_distance = rounded_rect_distance(p - 0.5 * size, 0.5 * size, corner_radius);
+    // Line width is in the order of left, top, right and bottom.
+    let line_width = normalized_line_width * size.xyxy;
+    let inner_min = line_width.xy;
+    let inner_max = max(size - line_width.zw, inner_min);
+    let inner_radius = max(
+            corner_radius - max(line_width.xzzx, line_width.yyww),
+            vec4<f32>(0.),
+        );
+    let inner_distance = rounded_rect_distance(
+            p - 0.5 * (inner_min + inner_max),
+            0.5 * (inner_max - inner_min),
+            inner_radius,
+        );
+    let outer_coverage = coverage(outer_distance);
+    let fill_coverage = min(coverage(inner_distance), outer_coverage);
+    let line_coverage = outer_coverage - fill_coverage;
+    let fill_alpha = fill_color.a * fill_coverage;
+    let line_alpha = line_color.a * line_coverage;
+    let alpha = fill_alpha + line_alpha;
+    if alpha <= 0. {
+        return vec4<f32>(0.);
+    }
+    let rgb = (fill_color.rgb * fill_alpha + line_color.rgb * line_alpha) / alpha;
+    return vec4<f32>(rgb, alpha);
+}
+
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-    let distance = vec4<f32>(vertex.uv, vec2<f32>(1.) - vertex.uv);
-    return select(
+    return shade_rect(
+            vertex.uv,
+            vertex.size,
             vertex.fill_color,
             vertex.line_color,
-            any(distance < vertex.line_width),
+            vertex.line_width,
+            vertex.corner_radius,
+        );
+}
+
+/// Only writes to the stencil buffer, inside of the rounded rect.
+@fragment
+fn fs_mask(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let p = vertex.uv * vertex.size;
+    let distance = rounded_rect_distance(
+            p - 0.5 * vertex.size,
+            0.5 * vertex.size,
+            vertex.corner_radius,
         );
+    if distance > 0. {
+        discard;
+    }
+    return vec4<f32>(0.);
 }
@@ -0,0 +1,48 @@
+@group(0) @binding(0) var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1) var<uniform> aaf: f32;
+
+@group(1) @binding(0) var<uniform> glyph_size_uv: vec2<f32>;
+@group(1) @binding(1) var texture: texture_2d<f32>;
+@group(1) @binding(2) var sampler_: sampler;
+
+const vertices = array<vec2<f32>, 6>(
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 1.),
+);
+
+struct InstanceInput {
+    @location(0) origin: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) uv_offset: vec2<f32>,
+    @location(3) fg_color: vec4<f32>,
+    @location(4) bg_color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) fg_color: vec4<f32>,
+    @location(2) bg_color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> VertexOutput {
+    var result: VertexOutput;
+    let position = vertices[index];
+    result.uv = instance.uv_offset + position * glyph_size_uv;
+    let position_world = instance.origin + position * instance.size;
+    result.position = projection * vec4<f32>(position_world, 0.0, 1.0);
+    result.fg_color = instance.fg_color;
+    result.bg_color = instance.bg_color;
+    return result;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let sample = textureSample(texture, sampler_, vertex.uv);
+    return mix(vertex.bg_color, vertex.fg_color, sample.a);
+}
@@ -0,0 +1,34 @@
+@group(0) @binding(0) var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1) var<uniform> aaf: f32;
+
+@group(1) @binding(0) var<uniform> model_view: mat4x4<f32>;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    /// Signed distance to the edge of the shape, negative inside.
+    @location(1) distance: f32,
+    /// Linear, not premultiplied.
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) distance: f32,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var result: VertexOutput;
+    result.position = projection * model_view * vec4<f32>(input.position, 0.0, 1.0);
+    result.distance = input.distance;
+    result.color = input.color;
+    return result;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel_width = max(fwidth(vertex.distance), 1e-4);
+    let coverage = clamp(0.5 - vertex.distance / pixel_width, 0., 1.);
+    return vec4<f32>(vertex.color.rgb, vertex.color.a * coverage);
+}
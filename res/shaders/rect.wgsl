@@ -1,10 +1,23 @@
 @group(0) @binding(0) var<uniform> projection: mat4x4<f32>;
 @group(0) @binding(1) var<uniform> aaf: f32;
 
+struct Fill {
+    /// 0 for solid, 1 for linear gradient, 2 for radial gradient.
+    kind: u32,
+    n_stops: u32,
+    /// `(cos(angle), sin(angle), _, _)` for linear gradients, `(center, radius, _)` for radial
+    /// gradients.
+    parameters: vec4<f32>,
+    /// Linear, not premultiplied.
+    colors: array<vec4<f32>, 8>,
+    offsets: array<vec4<f32>, 2>,
+};
+
 @group(1) @binding(0) var<uniform> model_view: mat4x4<f32>;
-@group(1) @binding(1) var<uniform> fill_color: vec4<f32>;
+@group(1) @binding(1) var<uniform> fill: Fill;
 @group(1) @binding(2) var<uniform> line_color: vec4<f32>;
 @group(1) @binding(3) var<uniform> line_width: vec4<f32>;
+@group(1) @binding(4) var<uniform> corner_radius: vec4<f32>;
 
 const vertices = array<vec2<f32>, 6>(
     vec2<f32>(0., 0.),
@@ -18,6 +31,7 @@ const vertices = array<vec2<f32>, 6>(
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
+    @location(1) size: vec2<f32>,
 };
 
 @vertex
@@ -25,16 +39,107 @@ fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
     var result: VertexOutput;
     let position = vertices[index];
     result.uv = position;
+    result.size = vec2<f32>(length(model_view[0].xy), length(model_view[1].xy));
     result.position = projection * model_view * vec4<f32>(position.xy, 0.0, 1.0);
     return result;
 }
 
+/// Signed distance from `p` to the edge of a rounded rect centered at the origin, negative inside.
+/// `radius` is in the order of top left, top right, bottom right and bottom left.
+fn rounded_rect_distance(p: vec2<f32>, half_size: vec2<f32>, radius: vec4<f32>) -> f32 {
+    let radius_x = select(radius.xw, radius.yz, p.x > 0.);
+    let r = min(select(radius_x.x, radius_x.y, p.y > 0.), min(half_size.x, half_size.y));
+    let q = abs(p) - half_size + vec2<f32>(r);
+    return min(max(q.x, q.y), 0.) + length(max(q, vec2<f32>(0.))) - r;
+}
+
+/// Fraction of the pixel covered by the shape `distance` is taken from.
+fn coverage(distance: f32) -> f32 {
+    let pixel_width = max(fwidth(distance), 1e-4);
+    return clamp(0.5 - distance / pixel_width, 0., 1.);
+}
+
+/// Color of a rounded rect of `size` with a border inside of its edges, at `uv`.
+fn shade_rect(
+    uv: vec2<f32>,
+    size: vec2<f32>,
+    fill_color: vec4<f32>,
+    line_color: vec4<f32>,
+    normalized_line_width: vec4<f32>,
+    corner_radius: vec4<f32>,
+) -> vec4<f32> {
+    let p = uv * size;
+    let outer_distance = rounded_rect_distance(p - 0.5 * size, 0.5 * size, corner_radius);
+    // Line width is in the order of left, top, right and bottom.
+    let line_width = normalized_line_width * size.xyxy;
+    let inner_min = line_width.xy;
+    let inner_max = max(size - line_width.zw, inner_min);
+    let inner_radius = max(
+            corner_radius - max(line_width.xzzx, line_width.yyww),
+            vec4<f32>(0.),
+        );
+    let inner_distance = rounded_rect_distance(
+            p - 0.5 * (inner_min + inner_max),
+            0.5 * (inner_max - inner_min),
+            inner_radius,
+        );
+    let outer_coverage = coverage(outer_distance);
+    let fill_coverage = min(coverage(inner_distance), outer_coverage);
+    let line_coverage = outer_coverage - fill_coverage;
+    let fill_alpha = fill_color.a * fill_coverage;
+    let line_alpha = line_color.a * line_coverage;
+    let alpha = fill_alpha + line_alpha;
+    if alpha <= 0. {
+        return vec4<f32>(0.);
+    }
+    let rgb = (fill_color.rgb * fill_alpha + line_color.rgb * line_alpha) / alpha;
+    return vec4<f32>(rgb, alpha);
+}
+
+/// Position along the gradient of `fill` at `uv`.
+fn gradient_offset(uv: vec2<f32>, size: vec2<f32>) -> f32 {
+    let p = uv * size;
+    switch fill.kind {
+        case 1u: {
+            let direction = fill.parameters.xy;
+            let half_length = 0.5 * dot(size, abs(direction));
+            return 0.5 + dot(p - 0.5 * size, direction) / max(2. * half_length, 1e-4);
+        }
+        case 2u: {
+            let center = fill.parameters.xy * size;
+            let farthest = length(max(center, size - center));
+            return length(p - center) / max(fill.parameters.z * farthest, 1e-4);
+        }
+        default: {
+            return 0.;
+        }
+    }
+}
+
+fn premultiplied(color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+/// Color of `fill` at `uv`, interpolated in linear color space.
+fn fill_color_at(uv: vec2<f32>, size: vec2<f32>) -> vec4<f32> {
+    let t = gradient_offset(uv, size);
+    var color = premultiplied(fill.colors[0]);
+    for (var i = 1u; i < fill.n_stops; i++) {
+        let start = fill.offsets[(i - 1u) / 4u][(i - 1u) % 4u];
+        let end = fill.offsets[i / 4u][i % 4u];
+        if t >= start {
+            let s = clamp((t - start) / max(end - start, 1e-6), 0., 1.);
+            color = mix(premultiplied(fill.colors[i - 1u]), premultiplied(fill.colors[i]), s);
+        }
+    }
+    if color.a <= 0. {
+        return vec4<f32>(0.);
+    }
+    return vec4<f32>(color.rgb / color.a, color.a);
+}
+
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-    let distance = vec4<f32>(vertex.uv, vec2<f32>(1.) - vertex.uv);
-    return select(
-            fill_color,
-            line_color,
-            any(distance < line_width),
-        );
+    let fill_color = fill_color_at(vertex.uv, vertex.size);
+    return shade_rect(vertex.uv, vertex.size, fill_color, line_color, line_width, corner_radius);
 }
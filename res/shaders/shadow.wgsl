@@ -0,0 +1,84 @@
+@group(0) @binding(0) var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1) var<uniform> aaf: f32;
+
+@group(1) @binding(0) var<uniform> model_view: mat4x4<f32>;
+@group(1) @binding(1) var<uniform> color: vec4<f32>;
+/// Bounds of the shadow before blurring, as `(x_min, y_min, x_max, y_max)`.
+@group(1) @binding(2) var<uniform> shadow_rect: vec4<f32>;
+@group(1) @binding(3) var<uniform> corner_radius: vec4<f32>;
+@group(1) @binding(4) var<uniform> sigma: f32;
+
+const vertices = array<vec2<f32>, 6>(
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 1.),
+);
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) logical_position: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var result: VertexOutput;
+    let position = vertices[index];
+    let logical_position = model_view * vec4<f32>(position.xy, 0.0, 1.0);
+    result.logical_position = logical_position.xy;
+    result.position = projection * logical_position;
+    return result;
+}
+
+const PI: f32 = 3.141592653589793;
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    return exp(-(x * x) / (2. * sigma * sigma)) / (sqrt(2. * PI) * sigma);
+}
+
+/// Approximation of the error function, with a max error of about 5e-4.
+fn erf(x: vec2<f32>) -> vec2<f32> {
+    let s = sign(x);
+    let a = abs(x);
+    var r = 1. + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    r = r * r;
+    return s - s / (r * r);
+}
+
+/// Blurred coverage along the x axis of the row at `y` of a rounded rect centered at the origin.
+fn shadow_row(x: f32, y: f32, sigma: f32, radius: f32, half_size: vec2<f32>) -> f32 {
+    let delta = min(half_size.y - radius - abs(y), 0.);
+    let curved = half_size.x - radius + sqrt(max(0., radius * radius - delta * delta));
+    let integral = 0.5 + 0.5 * erf((x + vec2<f32>(-curved, curved)) * (sqrt(0.5) / sigma));
+    return integral.y - integral.x;
+}
+
+/// Coverage of a rounded rect centered at the origin and blurred by a Gaussian of `sigma`, at `p`.
+///
+/// The blur is exact along the x axis, and integrated numerically along the y axis.
+fn rounded_rect_shadow(p: vec2<f32>, half_size: vec2<f32>, radius: vec4<f32>, sigma: f32) -> f32 {
+    let radius_x = select(radius.xw, radius.yz, p.x > 0.);
+    let r = min(select(radius_x.x, radius_x.y, p.y > 0.), min(half_size.x, half_size.y));
+    let low = p.y - half_size.y;
+    let high = p.y + half_size.y;
+    let start = clamp(-3. * sigma, low, high);
+    let end = clamp(3. * sigma, low, high);
+    let step = (end - start) / 4.;
+    var y = start + 0.5 * step;
+    var value = 0.;
+    for (var i = 0; i < 4; i++) {
+        value += shadow_row(p.x, p.y - y, sigma, r, half_size) * gaussian(y, sigma) * step;
+        y += step;
+    }
+    return value;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let center = 0.5 * (shadow_rect.xy + shadow_rect.zw);
+    let half_size = max(0.5 * (shadow_rect.zw - shadow_rect.xy), vec2<f32>(0.));
+    let coverage = rounded_rect_shadow(vertex.logical_position - center, half_size, corner_radius, sigma);
+    return vec4<f32>(color.rgb, color.a * clamp(coverage, 0., 1.));
+}
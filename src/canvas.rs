@@ -20,11 +20,165 @@ pub trait Canvas {
 pub struct CanvasFormat {
     pub color_format: wgpu::TextureFormat,
     pub depth_stencil_format: Option<wgpu::TextureFormat>,
+    /// Number of samples per pixel, 1 for no MSAA.
+    ///
+    /// With MSAA, canvases are drawn onto a multisampled color texture, which is then resolved into
+    /// the actual color texture at the end of each render pass.
+    pub sample_count: u32,
+    pub alpha_blend: AlphaBlend,
+}
+
+/// How the alpha of drawn content is combined with the alpha of a canvas.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlphaBlend {
+    /// The alpha of the canvas is replaced with the alpha of the drawn content, for window
+    /// surfaces, whose alpha is usually ignored.
+    #[default]
+    Replace,
+    /// The alpha of the drawn content is composited over the alpha of the canvas, so that
+    /// translucent content over opaque content stays opaque, for canvases that are read back (e.g.
+    /// by `UiContext::render_to_image`).
+    Over,
+}
+
+impl AlphaBlend {
+    pub fn blend_component(self) -> wgpu::BlendComponent {
+        match self {
+            AlphaBlend::Replace => wgpu::BlendComponent::REPLACE,
+            AlphaBlend::Over => wgpu::BlendComponent::OVER,
+        }
+    }
+}
+
+impl CanvasFormat {
+    /// Whether canvases of this format have a stencil buffer, i.e. stencil masks take effect.
+    pub fn has_stencil(&self) -> bool {
+        self.depth_stencil_format
+            .is_some_and(|format| format.has_stencil_aspect())
+    }
+
+    /// Create a depth stencil texture of `depth_stencil_format` for a canvas of `physical_size`,
+    /// `None` if `depth_stencil_format` is `None`.
+    pub fn create_depth_stencil_texture(
+        &self,
+        device: &wgpu::Device,
+        physical_size: RectSize<u32>,
+    ) -> Option<wgpu::Texture> {
+        let format = self.depth_stencil_format?;
+        Some(device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("depth stencil texture"),
+            size: wgpu::Extent3d {
+                width: physical_size.width.max(1),
+                height: physical_size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }))
+    }
+
+    /// Create a multisampled color texture for a canvas of `physical_size`, `None` if
+    /// `sample_count` is 1.
+    pub fn create_multisampled_color_texture(
+        &self,
+        device: &wgpu::Device,
+        physical_size: RectSize<u32>,
+    ) -> Option<wgpu::Texture> {
+        if self.sample_count <= 1 {
+            return None;
+        }
+        Some(device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("multisampled color texture"),
+            size: wgpu::Extent3d {
+                width: physical_size.width.max(1),
+                height: physical_size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.color_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }))
+    }
+
+    /// Multisample state of pipelines drawing onto canvases of this format.
+    pub(crate) fn multisample_state(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            ..the_default()
+        }
+    }
+
+    /// Depth stencil state of pipelines that draw UI elements.
+    ///
+    /// UI elements are drawn regardless of the depth buffer, and only where the stencil buffer
+    /// equals the stencil reference (see `RenderPass::push_stencil_mask`).
+    pub(crate) fn depth_stencil_state(&self) -> Option<wgpu::DepthStencilState> {
+        let face = wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::Equal,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op: wgpu::StencilOperation::Keep,
+        };
+        self.depth_stencil_state_with(face, 0)
+    }
+
+    /// Depth stencil state of pipelines that draw stencil masks with `pass_op`, without changing
+    /// the depth buffer.
+    pub(crate) fn stencil_mask_state(
+        &self,
+        pass_op: wgpu::StencilOperation,
+    ) -> Option<wgpu::DepthStencilState> {
+        let face = wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::Equal,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op,
+        };
+        self.depth_stencil_state_with(face, !0)
+            .map(|state| wgpu::DepthStencilState {
+                depth_write_enabled: false,
+                ..state
+            })
+    }
+
+    fn depth_stencil_state_with(
+        &self,
+        face: wgpu::StencilFaceState,
+        write_mask: u32,
+    ) -> Option<wgpu::DepthStencilState> {
+        let format = self.depth_stencil_format?;
+        let stencil = match format.has_stencil_aspect() {
+            true => wgpu::StencilState {
+                front: face,
+                back: face,
+                read_mask: !0,
+                write_mask,
+            },
+            false => the_default(),
+        };
+        Some(wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: format.has_depth_aspect(),
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil,
+            bias: the_default(),
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct CanvasRef {
+    /// The multisampled color texture if the canvas has MSAA.
     pub color_texture_view: wgpu::TextureView,
+    /// The texture `color_texture_view` is resolved into, if the canvas has MSAA.
+    pub resolve_texture_view: Option<wgpu::TextureView>,
     pub depth_stencil_texture_view: Option<wgpu::TextureView>,
     pub logical_size: RectSize<f32>,
     pub projection: Matrix4<f32>,
@@ -38,12 +192,22 @@ impl CanvasRef {
     ) -> Self {
         Self {
             color_texture_view,
+            resolve_texture_view: None,
             depth_stencil_texture_view,
             logical_size,
             projection: Self::projection(logical_size, -1.0, 1.0),
         }
     }
 
+    /// Draw onto `multisampled_color_texture_view` instead, resolving it into the color texture.
+    pub fn with_multisampling(self, multisampled_color_texture_view: wgpu::TextureView) -> Self {
+        Self {
+            resolve_texture_view: Some(self.color_texture_view),
+            color_texture_view: multisampled_color_texture_view,
+            ..self
+        }
+    }
+
     pub fn bounds(&self) -> Bounds<f32> {
         Bounds {
             origin: point2(0., 0.),
@@ -59,12 +223,15 @@ impl CanvasRef {
 #[derive(Debug, Clone)]
 pub struct TextureCanvas {
     color_texture: wgpu::Texture,
+    multisampled_color_texture: Option<wgpu::Texture>,
     depth_stencil_texture: Option<wgpu::Texture>,
     format: CanvasFormat,
     logical_size: RectSize<f32>,
 }
 
 impl TextureCanvas {
+    /// `color_texture` must be single-sampled. If `format` has MSAA, use
+    /// `with_multisampled_color_texture` to provide the multisampled color texture.
     pub fn new(
         color_texture: wgpu::Texture,
         depth_stencil_texture: Option<wgpu::Texture>,
@@ -73,11 +240,142 @@ impl TextureCanvas {
     ) -> Self {
         Self {
             color_texture,
+            multisampled_color_texture: None,
             depth_stencil_texture,
             format,
             logical_size,
         }
     }
+
+    /// Create a canvas of `physical_size` with all the textures `format` needs.
+    ///
+    /// The color texture can also be bound as a texture and copied from.
+    pub fn create(
+        device: &wgpu::Device,
+        format: CanvasFormat,
+        physical_size: RectSize<u32>,
+        logical_size: RectSize<f32>,
+    ) -> Self {
+        let color_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("canvas color texture"),
+            size: wgpu::Extent3d {
+                width: physical_size.width.max(1),
+                height: physical_size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: format.color_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        Self {
+            color_texture,
+            multisampled_color_texture: format
+                .create_multisampled_color_texture(device, physical_size),
+            depth_stencil_texture: format.create_depth_stencil_texture(device, physical_size),
+            format,
+            logical_size,
+        }
+    }
+
+    pub fn with_multisampled_color_texture(
+        self,
+        multisampled_color_texture: impl Into<Option<wgpu::Texture>>,
+    ) -> Self {
+        Self {
+            multisampled_color_texture: multisampled_color_texture.into(),
+            ..self
+        }
+    }
+
+    /// The single-sampled color texture, which has the drawn content after the render passes are
+    /// finished.
+    pub fn color_texture(&self) -> &wgpu::Texture {
+        &self.color_texture
+    }
+
+    /// Copy the content of the color texture back to the CPU, blocking until the render passes
+    /// drawing onto it are finished.
+    ///
+    /// The color texture must have `COPY_SRC` usage (as it does if created by `create`), and be
+    /// of one of the 8-bit RGBA or BGRA formats.
+    pub fn read_to_image(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<image::RgbaImage, ReadCanvasError> {
+        use wgpu::TextureFormat::*;
+        let color_format = self.color_texture.format();
+        let is_bgra = match color_format {
+            Rgba8Unorm | Rgba8UnormSrgb => false,
+            Bgra8Unorm | Bgra8UnormSrgb => true,
+            _ => return Err(ReadCanvasError::UnsupportedColorFormat(color_format)),
+        };
+        let size = self.color_texture.size();
+        // Rows in the buffer are padded to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+        let bytes_per_row = size.width * 4;
+        let padded_bytes_per_row =
+            bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("canvas readback buffer"),
+            size: padded_bytes_per_row as u64 * size.height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&the_default());
+        encoder.copy_texture_to_buffer(
+            self.color_texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        queue.submit([encoder.finish()]);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer.map_async(wgpu::MapMode::Read, .., move |result| {
+            _ = sender.send(result);
+        });
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(ReadCanvasError::Poll)?;
+        receiver
+            .recv()
+            .expect("buffer mapping callback is dropped without being called")
+            .map_err(ReadCanvasError::BufferMapping)?;
+        let mut data = Vec::with_capacity((bytes_per_row * size.height) as usize);
+        for row in buffer
+            .get_mapped_range(..)
+            .chunks_exact(padded_bytes_per_row as usize)
+        {
+            data.extend_from_slice(&row[..bytes_per_row as usize]);
+        }
+        buffer.unmap();
+        if is_bgra {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(image::RgbaImage::from_raw(size.width, size.height, data).unwrap())
+    }
+}
+
+#[derive(Debug, Display, Error)]
+pub enum ReadCanvasError {
+    #[display("cannot read canvas of color format {_0:?} into an RGBA image")]
+    UnsupportedColorFormat(#[error(not(source))] wgpu::TextureFormat),
+    #[display("{_0}")]
+    Poll(wgpu::PollError),
+    #[display("{_0}")]
+    BufferMapping(wgpu::BufferAsyncError),
 }
 
 impl Canvas for TextureCanvas {
@@ -90,13 +388,17 @@ impl Canvas for TextureCanvas {
     }
 
     fn create_ref(&self) -> Result<CanvasRef, Box<dyn Error>> {
-        Ok(CanvasRef::new(
+        let canvas_ref = CanvasRef::new(
             self.color_texture.create_view(&the_default()),
             self.depth_stencil_texture
                 .as_ref()
                 .map(|texture| texture.create_view(&the_default())),
             self.logical_size,
-        ))
+        );
+        Ok(match &self.multisampled_color_texture {
+            Some(texture) => canvas_ref.with_multisampling(texture.create_view(&the_default())),
+            None => canvas_ref,
+        })
     }
 
     fn finish_drawing(&self) -> Result<(), Box<dyn Error>> {
@@ -107,6 +409,7 @@ impl Canvas for TextureCanvas {
 #[derive(Debug)]
 pub struct WindowCanvas<'window> {
     window_surface: wgpu::Surface<'window>,
+    multisampled_color_texture: Option<wgpu::Texture>,
     depth_stencil_texture: Option<wgpu::Texture>,
     format: CanvasFormat,
     logical_size: RectSize<f32>,
@@ -140,6 +443,7 @@ impl<'window> WindowCanvas<'window> {
     ) -> Self {
         Self {
             window_surface,
+            multisampled_color_texture: None,
             depth_stencil_texture,
             format,
             logical_size,
@@ -148,11 +452,16 @@ impl<'window> WindowCanvas<'window> {
         }
     }
 
+    /// If `adapter` does not support `sample_count` for the color format of the surface or for
+    /// `depth_stencil_format`, the highest sample count below it that both support is used instead
+    /// (see `Canvas::format`).
     pub fn create_for_window(
         instance: &wgpu::Instance,
         adapter: &wgpu::Adapter,
         device: &wgpu::Device,
         window: Arc<Window>,
+        depth_stencil_format: Option<wgpu::TextureFormat>,
+        sample_count: u32,
     ) -> Self {
         let window_size = window.inner_size();
         let window_scale_factor = window.scale_factor();
@@ -169,12 +478,28 @@ impl<'window> WindowCanvas<'window> {
             .find(|&format| format.is_srgb())
             .unwrap_or(surface_capabilities.formats[0]);
         log::info!("output color format: {color_format:?}");
+        let requested_sample_count = sample_count;
+        let sample_count = supported_sample_count(
+            adapter,
+            color_format,
+            depth_stencil_format,
+            requested_sample_count,
+        );
+        if sample_count != requested_sample_count {
+            log::warn!(
+                "sample count {requested_sample_count} is not supported for output color format \
+                {color_format:?} and depth stencil format {depth_stencil_format:?}, using \
+                {sample_count} instead"
+            );
+        }
         let mut self_ = Self::new(
             window_surface,
             None,
             CanvasFormat {
                 color_format,
-                depth_stencil_format: None,
+                depth_stencil_format,
+                sample_count,
+                alpha_blend: AlphaBlend::Replace,
             },
             // reconfigure_for_size would initialise this field.
             RectSize::new(0., 0.),
@@ -189,38 +514,57 @@ impl<'window> WindowCanvas<'window> {
                 present_mode: wgpu::PresentMode::AutoVsync,
             },
         );
-        self_.reconfigure_for_size(device, window_size, window_scale_factor, None);
+        self_.reconfigure_for_size(device, window_size, window_scale_factor);
         self_
     }
 
+    /// Resize the surface, and recreate the multisampled color texture and the depth stencil
+    /// texture (if the canvas format has them) in the new size.
     pub fn reconfigure_for_size(
         &mut self,
         device: &wgpu::Device,
         size: PhysicalSize<u32>,
         scale_factor: f64,
-        new_depth_stencil_texture: Option<wgpu::Texture>,
     ) {
         let logical_size = size.to_logical::<f32>(scale_factor);
         self.logical_size = RectSize::new(logical_size.width, logical_size.height);
         self.surface_config.width = size.width;
         self.surface_config.height = size.height;
         self.window_surface.configure(device, &self.surface_config);
-        match (
-            self.depth_stencil_texture.as_mut(),
-            new_depth_stencil_texture,
-        ) {
-            (None, None) => (),
-            (Some(texture), Some(new_texture)) => *texture = new_texture,
-            (None, Some(_)) => panic!(
-                "`WindowCanvas::reconfigure_for_size` is provided with a depth stencil texture, but this `WindowCanvas` does not have a depth stencil texture"
-            ),
-            (Some(_), None) => panic!(
-                "`WindowCanvas::reconfigure_for_size` is provided with no depth stencil texture, but this `WindowCanvas` *does have* a depth stencil texture"
-            ),
-        }
+        let physical_size = RectSize::new(size.width, size.height);
+        self.multisampled_color_texture = self
+            .format
+            .create_multisampled_color_texture(device, physical_size);
+        self.depth_stencil_texture = self
+            .format
+            .create_depth_stencil_texture(device, physical_size);
     }
 }
 
+/// The highest sample count up to `sample_count` that `adapter` supports for both `color_format`
+/// and `depth_stencil_format`.
+fn supported_sample_count(
+    adapter: &wgpu::Adapter,
+    color_format: wgpu::TextureFormat,
+    depth_stencil_format: Option<wgpu::TextureFormat>,
+    sample_count: u32,
+) -> u32 {
+    let is_supported = |sample_count| {
+        std::iter::once(color_format)
+            .chain(depth_stencil_format)
+            .all(|format| {
+                adapter
+                    .get_texture_format_features(format)
+                    .flags
+                    .sample_count_supported(sample_count)
+            })
+    };
+    (1..=sample_count)
+        .rev()
+        .find(|&sample_count| is_supported(sample_count))
+        .unwrap_or(1)
+}
+
 impl<'a> Canvas for WindowCanvas<'a> {
     fn format(&self) -> CanvasFormat {
         self.format
@@ -248,11 +592,15 @@ impl<'a> Canvas for WindowCanvas<'a> {
             .depth_stencil_texture
             .as_ref()
             .map(|texture| texture.create_view(&the_default()));
-        Ok(CanvasRef::new(
+        let canvas_ref = CanvasRef::new(
             color_texture_view,
             depth_stencil_texture_view,
             self.logical_size,
-        ))
+        );
+        Ok(match &self.multisampled_color_texture {
+            Some(texture) => canvas_ref.with_multisampling(texture.create_view(&the_default())),
+            None => canvas_ref,
+        })
     }
 
     fn finish_drawing(&self) -> Result<(), Box<dyn Error>> {
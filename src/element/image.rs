@@ -1,7 +1,8 @@
+use bytemuck::{Pod, Zeroable};
 use cgmath::*;
 
 use crate::{
-    Bounds, CanvasFormat, ImageRef, Texture2d,
+    Bounds, CanvasFormat, ImageRef, NineSlice, RectSize, Rgba, Texture2d,
     element::CameraBindGroup,
     resources::{AppResources, LoadResourceError},
     utils::*,
@@ -14,6 +15,74 @@ impl<'a> From<&'a image::RgbaImage> for ImageRef<'a> {
     }
 }
 
+/// GPU-side representation of a `NineSlice`, matching `struct NineSlice` in `image.wgsl`.
+///
+/// Insets are in the order of left, top, right and bottom. All zeros stretches the whole texture.
+#[derive(Debug, Clone, Copy, Default, Zeroable, Pod)]
+#[repr(C)]
+struct NineSliceUniform {
+    /// Insets of the corners in the texture, in UV.
+    source: [f32; 4],
+    /// Insets of the corners in the image, normalized to the size of the image.
+    destination: [f32; 4],
+}
+
+impl NineSliceUniform {
+    fn new(nine_slice: NineSlice, texture_size: RectSize<f32>, size: RectSize<f32>) -> Self {
+        let [left, top, right, bottom] = nine_slice.to_array();
+        // Corners are shrunk to fit if the image is smaller than them.
+        let scale_x = (size.width / (left + right)).min(1.);
+        let scale_y = (size.height / (top + bottom)).min(1.);
+        let normalized = |inset: f32, length: f32| {
+            if length > 0. {
+                (inset / length).clamp(0., 1.)
+            } else {
+                0.
+            }
+        };
+        Self {
+            source: [
+                normalized(left, texture_size.width),
+                normalized(top, texture_size.height),
+                normalized(right, texture_size.width),
+                normalized(bottom, texture_size.height),
+            ],
+            destination: [
+                normalized(left * scale_x, size.width),
+                normalized(top * scale_y, size.height),
+                normalized(right * scale_x, size.width),
+                normalized(bottom * scale_y, size.height),
+            ],
+        }
+    }
+}
+
+/// Where the content is drawn inside of an image, matching `struct Content` in `image.wgsl`.
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+#[repr(C)]
+struct ContentUniform {
+    /// Origin and size, normalized to the size of the image.
+    bounds: [f32; 4],
+    /// 1 for repeating the content over the whole image, 0 for transparency outside of `bounds`.
+    tile: u32,
+    _padding: [u32; 3],
+}
+
+impl ContentUniform {
+    fn new(bounds: Bounds<f32>, tile: bool) -> Self {
+        Self {
+            bounds: [
+                bounds.origin.x,
+                bounds.origin.y,
+                bounds.size.width,
+                bounds.size.height,
+            ],
+            tile: tile as u32,
+            _padding: [0; 3],
+        }
+    }
+}
+
 #[derive(Debug, Clone, AsBindGroup)]
 struct ImageBindGroup {
     #[binding(0)]
@@ -27,6 +96,22 @@ struct ImageBindGroup {
     #[binding(2)]
     #[sampler(filtering)]
     sampler: wgpu::Sampler,
+
+    #[binding(3)]
+    #[uniform]
+    nine_slice: UniformBuffer<NineSliceUniform>,
+
+    #[binding(4)]
+    #[uniform]
+    source_rect: UniformBuffer<[f32; 4]>,
+
+    #[binding(5)]
+    #[uniform]
+    content: UniformBuffer<ContentUniform>,
+
+    #[binding(6)]
+    #[uniform]
+    tint: UniformBuffer<Rgba>,
 }
 
 #[derive(Debug, Clone)]
@@ -47,6 +132,50 @@ impl ImageElement {
             * Matrix4::from_nonuniform_scale(bounds.size.width, bounds.size.height, 1.);
         self.set_model_view(queue, model_view);
     }
+
+    /// Draw the source rect of `source_size` (in texture pixels) as a nine-slice image of `size`
+    /// (the size the content is drawn at), or stretch the whole source rect if `nine_slice` is
+    /// `None`.
+    pub fn set_nine_slice(
+        &self,
+        queue: &wgpu::Queue,
+        nine_slice: Option<NineSlice>,
+        source_size: RectSize<f32>,
+        size: RectSize<f32>,
+    ) {
+        let uniform = match nine_slice {
+            Some(nine_slice) => NineSliceUniform::new(nine_slice, source_size, size),
+            None => NineSliceUniform::default(),
+        };
+        self.bind_group.nine_slice.write(uniform, queue);
+    }
+
+    /// The part of the texture that is drawn, in UV.
+    pub fn set_source_rect(&self, queue: &wgpu::Queue, source_rect: Bounds<f32>) {
+        self.bind_group.source_rect.write(
+            [
+                source_rect.origin.x,
+                source_rect.origin.y,
+                source_rect.size.width,
+                source_rect.size.height,
+            ],
+            queue,
+        );
+    }
+
+    /// Where the source rect is drawn inside of the image, normalized to the size of the image.
+    ///
+    /// Outside of `content_bounds` is transparent, or repeats the content if `tile`.
+    pub fn set_content_bounds(&self, queue: &wgpu::Queue, content_bounds: Bounds<f32>, tile: bool) {
+        self.bind_group
+            .content
+            .write(ContentUniform::new(content_bounds, tile), queue);
+    }
+
+    /// Multiplied with the colors of the texture.
+    pub fn set_tint(&self, queue: &wgpu::Queue, tint: impl Into<Rgba>) {
+        self.bind_group.tint.write(tint.into(), queue);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -94,22 +223,14 @@ impl<'cx> ImageRenderer<'cx> {
                             src_factor: wgpu::BlendFactor::SrcAlpha,
                             dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
                         },
-                        alpha: wgpu::BlendComponent::REPLACE,
+                        alpha: canvas_format.alpha_blend.blend_component(),
                     }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             primitive: the_default(),
-            depth_stencil: canvas_format.depth_stencil_format.map(|format| {
-                wgpu::DepthStencilState {
-                    format,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: the_default(),
-                    bias: the_default(),
-                }
-            }),
-            multisample: the_default(),
+            depth_stencil: canvas_format.depth_stencil_state(),
+            multisample: canvas_format.multisample_state(),
             multiview: None,
             cache: None,
         });
@@ -135,6 +256,13 @@ impl<'cx> ImageRenderer<'cx> {
             model_view: UniformBuffer::create_init(device, Matrix4::identity().into()),
             texture_view: texture.wgpu_texture_view().clone(),
             sampler: self.sampler.clone(),
+            nine_slice: UniformBuffer::create_init(device, NineSliceUniform::default()),
+            source_rect: UniformBuffer::create_init(device, [0., 0., 1., 1.]),
+            content: UniformBuffer::create_init(
+                device,
+                ContentUniform::new(Bounds::from_scalars(0., 0., 1., 1.), false),
+            ),
+            tint: UniformBuffer::create_init(device, Rgba::from_hex(0xFFFFFFFF)),
         };
         let wgpu_bind_group = bind_group.create_bind_group(&self.bind_group_layout, device);
         ImageElement {
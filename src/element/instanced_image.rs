@@ -0,0 +1,215 @@
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+
+use crate::{
+    AppResources, Bounds, CanvasFormat, Texture2d,
+    element::CameraBindGroup,
+    resources::LoadResourceError,
+    utils::*,
+    wgpu_utils::{AsBindGroup, Vertex, VertexBuffer},
+};
+
+#[derive(Debug, Clone, AsBindGroup)]
+struct InstancedImageBindGroup {
+    #[binding(0)]
+    #[texture_view(sample_type = float, view_dimension = 2, multisampled = false)]
+    texture_view: wgpu::TextureView,
+
+    #[binding(1)]
+    #[sampler(filtering)]
+    sampler: wgpu::Sampler,
+}
+
+/// Draws any number of images of the same texture in one draw call.
+#[derive(Debug, Clone)]
+pub struct InstancedImageRenderer<'cx> {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    _shader: &'cx wgpu::ShaderModule,
+    sampler: wgpu::Sampler,
+}
+
+impl<'cx> InstancedImageRenderer<'cx> {
+    pub fn create(
+        device: &wgpu::Device,
+        resources: &'cx AppResources,
+        canvas_format: CanvasFormat,
+    ) -> Result<Self, LoadResourceError> {
+        let shader = resources.load_shader("shaders/instanced_image.wgsl", device)?;
+        let bind_group_layout = InstancedImageBindGroup::create_bind_group_layout(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[
+                &CameraBindGroup::create_bind_group_layout(device),
+                &bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: the_default(),
+                buffers: &[ImageInstance::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                compilation_options: the_default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: canvas_format.color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            operation: wgpu::BlendOperation::Add,
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        },
+                        alpha: canvas_format.alpha_blend.blend_component(),
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: the_default(),
+            depth_stencil: canvas_format.depth_stencil_state(),
+            multisample: canvas_format.multisample_state(),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(&wgpu::wgt::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..the_default()
+        });
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            _shader: shader,
+            sampler,
+        })
+    }
+
+    pub fn create_images(
+        &self,
+        device: &wgpu::Device,
+        instances: &[ImageInstance],
+    ) -> InstancedImagesElement {
+        let instance_buffer = VertexBuffer::create_init(device, instances);
+        InstancedImagesElement {
+            instance_buffer,
+            n_instances: instances.len() as u32,
+        }
+    }
+
+    pub fn create_texture_binding(
+        &self,
+        device: &wgpu::Device,
+        texture: &Texture2d,
+    ) -> ImageTextureBinding {
+        let bind_group = InstancedImageBindGroup {
+            texture_view: texture.wgpu_texture_view().clone(),
+            sampler: self.sampler.clone(),
+        };
+        ImageTextureBinding {
+            wgpu_bind_group: bind_group.create_bind_group(&self.bind_group_layout, device),
+        }
+    }
+
+    pub fn draw_images(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        images: &InstancedImagesElement,
+        texture: &ImageTextureBinding,
+    ) {
+        self.draw_image_range(render_pass, images, texture, 0..images.n_instances);
+    }
+
+    /// Draw the images of `instances` in `images` only.
+    pub fn draw_image_range(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        images: &InstancedImagesElement,
+        texture: &ImageTextureBinding,
+        instances: Range<u32>,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &texture.wgpu_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, images.instance_buffer.slice(..));
+        render_pass.draw(0..6, instances);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InstancedImagesElement {
+    instance_buffer: VertexBuffer<ImageInstance>,
+    n_instances: u32,
+}
+
+impl InstancedImagesElement {
+    /// The images of the first `n_instances` instances in `instance_buffer`, which may be written
+    /// to after creation (e.g. the buffer of a `GrowableVertexBuffer`).
+    pub fn from_raw_parts(instance_buffer: VertexBuffer<ImageInstance>, n_instances: u32) -> Self {
+        Self {
+            instance_buffer,
+            n_instances,
+        }
+    }
+}
+
+/// The texture that instances of an `InstancedImagesElement` are drawn with.
+#[derive(Debug, Clone)]
+pub struct ImageTextureBinding {
+    wgpu_bind_group: wgpu::BindGroup,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct ImageInstance {
+    model_view_col_0: [f32; 3],
+    model_view_col_1: [f32; 3],
+    model_view_col_2: [f32; 3],
+}
+
+impl ImageInstance {
+    pub fn new() -> Self {
+        Self::zeroed()
+    }
+
+    pub fn with_model_view(self, model_view: Matrix3<f32>) -> Self {
+        Self {
+            model_view_col_0: model_view.x.into(),
+            model_view_col_1: model_view.y.into(),
+            model_view_col_2: model_view.z.into(),
+        }
+    }
+
+    /// Convenience function over `with_model_view`.
+    /// Sets `model_view` according to the bounds provided.
+    pub fn from_bounds(bounds: Bounds<f32>) -> Self {
+        let model_view = Matrix3::from_translation(bounds.origin.to_vec())
+            * Matrix3::from_nonuniform_scale(bounds.size.width, bounds.size.height);
+        Self::new().with_model_view(model_view)
+    }
+}
+
+impl Vertex for ImageInstance {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: size_of::<Self>() as u64,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &wgpu::vertex_attr_array! [
+            0 => Float32x3, // model_view_col_0
+            1 => Float32x3, // model_view_col_1
+            2 => Float32x3, // model_view_col_2
+        ],
+    };
+}
@@ -1,8 +1,10 @@
+use std::ops::Range;
+
 use bytemuck::{Pod, Zeroable};
 use cgmath::*;
 
 use crate::{
-    AppResources, Bounds, CanvasFormat, LineWidth, Rgba,
+    AppResources, Bounds, CanvasFormat, CornerRadius, LineWidth, Rgba,
     element::CameraBindGroup,
     resources::LoadResourceError,
     utils::*,
@@ -12,6 +14,8 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct InstancedRectRenderer<'cx> {
     pipeline: wgpu::RenderPipeline,
+    push_mask_pipeline: wgpu::RenderPipeline,
+    pop_mask_pipeline: wgpu::RenderPipeline,
     _shader: &'cx wgpu::ShaderModule,
 }
 
@@ -27,48 +31,62 @@ impl<'cx> InstancedRectRenderer<'cx> {
             bind_group_layouts: &[&CameraBindGroup::create_bind_group_layout(device)],
             push_constant_ranges: &[],
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: shader,
-                entry_point: Some("vs_main"),
-                compilation_options: the_default(),
-                buffers: &[RectInstance::LAYOUT],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: shader,
-                entry_point: Some("fs_main"),
-                compilation_options: the_default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: canvas_format.color_format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            operation: wgpu::BlendOperation::Add,
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        },
-                        alpha: wgpu::BlendComponent::REPLACE,
+        let create_pipeline =
+            |fragment_entry_point: &str,
+             write_mask: wgpu::ColorWrites,
+             depth_stencil: Option<wgpu::DepthStencilState>| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: None,
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: the_default(),
+                        buffers: &[RectInstance::LAYOUT],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: shader,
+                        entry_point: Some(fragment_entry_point),
+                        compilation_options: the_default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: canvas_format.color_format,
+                            blend: Some(wgpu::BlendState {
+                                color: wgpu::BlendComponent {
+                                    operation: wgpu::BlendOperation::Add,
+                                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                                },
+                                alpha: canvas_format.alpha_blend.blend_component(),
+                            }),
+                            write_mask,
+                        })],
                     }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: the_default(),
-            depth_stencil: canvas_format.depth_stencil_format.map(|format| {
-                wgpu::DepthStencilState {
-                    format,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: the_default(),
-                    bias: the_default(),
-                }
-            }),
-            multisample: the_default(),
-            multiview: None,
-            cache: None,
-        });
+                    primitive: the_default(),
+                    depth_stencil,
+                    multisample: canvas_format.multisample_state(),
+                    multiview: None,
+                    cache: None,
+                })
+            };
+        let pipeline = create_pipeline(
+            "fs_main",
+            wgpu::ColorWrites::ALL,
+            canvas_format.depth_stencil_state(),
+        );
+        let push_mask_pipeline = create_pipeline(
+            "fs_mask",
+            wgpu::ColorWrites::empty(),
+            canvas_format.stencil_mask_state(wgpu::StencilOperation::IncrementClamp),
+        );
+        let pop_mask_pipeline = create_pipeline(
+            "fs_mask",
+            wgpu::ColorWrites::empty(),
+            canvas_format.stencil_mask_state(wgpu::StencilOperation::DecrementClamp),
+        );
         Ok(Self {
             pipeline,
+            push_mask_pipeline,
+            pop_mask_pipeline,
             _shader: shader,
         })
     }
@@ -86,18 +104,73 @@ impl<'cx> InstancedRectRenderer<'cx> {
     }
 
     pub fn draw_rects(&self, render_pass: &mut wgpu::RenderPass, rects: &InstancedRectsElement) {
+        self.draw_rect_range(render_pass, rects, 0..rects.n_instances);
+    }
+
+    /// Draw the rects of `instances` in `rects` only.
+    pub fn draw_rect_range(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        rects: &InstancedRectsElement,
+        instances: Range<u32>,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_vertex_buffer(0, rects.instance_buffer.slice(..));
-        render_pass.draw(0..6, 0..rects.n_instances);
+        render_pass.draw(0..6, instances);
+    }
+
+    /// Draw the rects of `instances` in `rects` as stencil masks, only changing the stencil
+    /// buffer inside of the rounded rects.
+    pub fn draw_stencil_mask_range(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        rects: &InstancedRectsElement,
+        instances: Range<u32>,
+        op: StencilMaskOp,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+        let pipeline = match op {
+            StencilMaskOp::Push => &self.push_mask_pipeline,
+            StencilMaskOp::Pop => &self.pop_mask_pipeline,
+        };
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, rects.instance_buffer.slice(..));
+        render_pass.draw(0..6, instances);
     }
 }
 
+/// How a stencil mask changes the stencil buffer inside of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilMaskOp {
+    /// Increment the stencil values, so that only the inside of the mask equals the next stencil
+    /// reference.
+    Push,
+    /// Decrement the stencil values, undoing a `Push` of the same mask.
+    Pop,
+}
+
 #[derive(Debug, Clone)]
 pub struct InstancedRectsElement {
     instance_buffer: VertexBuffer<RectInstance>,
     n_instances: u32,
 }
 
+impl InstancedRectsElement {
+    /// The rects of the first `n_instances` instances in `instance_buffer`, which may be written
+    /// to after creation (e.g. the buffer of a `GrowableVertexBuffer`).
+    pub fn from_raw_parts(instance_buffer: VertexBuffer<RectInstance>, n_instances: u32) -> Self {
+        Self {
+            instance_buffer,
+            n_instances,
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
 #[repr(C)]
 pub struct RectInstance {
@@ -107,6 +180,7 @@ pub struct RectInstance {
     fill_color: [f32; 4],
     line_color: [f32; 4],
     line_width: [f32; 4],
+    corner_radius: [f32; 4],
 }
 
 impl RectInstance {
@@ -130,6 +204,13 @@ impl RectInstance {
         }
     }
 
+    pub fn with_corner_radius(self, corner_radius: impl Into<CornerRadius>) -> Self {
+        Self {
+            corner_radius: corner_radius.into().to_array(),
+            ..self
+        }
+    }
+
     pub fn with_fill_color(self, fill_color: impl Into<Rgba>) -> Self {
         Self {
             fill_color: fill_color.into().to_array(),
@@ -168,6 +249,7 @@ impl Vertex for RectInstance {
             3 => Float32x4, // fill_color
             4 => Float32x4, // line_color
             5 => Float32x4, // line_width
+            6 => Float32x4, // corner_radius
         ],
     };
 }
@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+
+use crate::{
+    AppResources, CanvasFormat, Font, Rgba,
+    element::{CameraBindGroup, TextInstance, TextRenderer},
+    resources::LoadResourceError,
+    utils::*,
+    wgpu_utils::{AsBindGroup, UniformBuffer, Vertex, VertexBuffer},
+};
+
+#[derive(Debug, Clone, AsBindGroup)]
+struct InstancedTextBindGroup {
+    #[binding(0)]
+    #[uniform]
+    glyph_size_uv: UniformBuffer<[f32; 2]>,
+
+    #[binding(1)]
+    #[texture_view]
+    texture_view: wgpu::TextureView,
+
+    #[binding(2)]
+    #[sampler]
+    sampler: wgpu::Sampler,
+}
+
+/// Draws glyphs of any number of texts in one draw call, each glyph carrying its own position and
+/// colors.
+///
+/// Shares the font atlas with `TextRenderer`.
+#[derive(Debug, Clone)]
+pub struct InstancedTextRenderer<'cx> {
+    pipeline: wgpu::RenderPipeline,
+    wgpu_bind_group: wgpu::BindGroup,
+    font: Font<'cx>,
+    _shader: &'cx wgpu::ShaderModule,
+}
+
+impl<'cx> InstancedTextRenderer<'cx> {
+    pub fn create(
+        device: &wgpu::Device,
+        text_renderer: &TextRenderer<'cx>,
+        resources: &'cx AppResources,
+        canvas_format: CanvasFormat,
+    ) -> Result<Self, LoadResourceError> {
+        let shader = resources.load_shader("shaders/instanced_text.wgsl", device)?;
+        let font = text_renderer.font();
+        let bind_group_layout = InstancedTextBindGroup::create_bind_group_layout(device);
+        let bind_group = InstancedTextBindGroup {
+            glyph_size_uv: UniformBuffer::create_init(device, font.glyph_size_uv().as_vec().into()),
+            texture_view: text_renderer.atlas_texture_view().clone(),
+            sampler: text_renderer.sampler().clone(),
+        };
+        let wgpu_bind_group = bind_group.create_bind_group(&bind_group_layout, device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[
+                &CameraBindGroup::create_bind_group_layout(device),
+                &bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: the_default(),
+                buffers: &[GlyphInstance::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                compilation_options: the_default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: canvas_format.color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            operation: wgpu::BlendOperation::Add,
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        },
+                        alpha: canvas_format.alpha_blend.blend_component(),
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: the_default(),
+            depth_stencil: canvas_format.depth_stencil_state(),
+            multisample: canvas_format.multisample_state(),
+            multiview: None,
+            cache: None,
+        });
+        Ok(Self {
+            pipeline,
+            wgpu_bind_group,
+            font,
+            _shader: shader,
+        })
+    }
+
+    /// Lay out the glyphs of `str` from `origin`, with glyphs scaled by `scale` (i.e. font size on
+    /// both axes if the text is not squeezed).
+    pub fn layout_glyphs(
+        &self,
+        str: &str,
+        origin: Point2<f32>,
+        scale: Vector2<f32>,
+        fg_color: impl Into<Rgba>,
+        bg_color: impl Into<Rgba>,
+    ) -> Vec<GlyphInstance> {
+        let fg_color = fg_color.into();
+        let bg_color = bg_color.into();
+        let glyph_size = vec2(self.font.glyph_relative_width() * scale.x, scale.y);
+        TextInstance::for_text(self.font, str)
+            .into_iter()
+            .map(|instance| {
+                let [x, y] = instance.position_offset;
+                GlyphInstance {
+                    origin: [origin.x + x * scale.x, origin.y + y * scale.y],
+                    size: glyph_size.into(),
+                    uv_offset: instance.uv_offset,
+                    fg_color: fg_color.to_array(),
+                    bg_color: bg_color.to_array(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn create_glyphs(
+        &self,
+        device: &wgpu::Device,
+        instances: &[GlyphInstance],
+    ) -> InstancedGlyphsElement {
+        let instance_buffer = VertexBuffer::create_init(device, instances);
+        InstancedGlyphsElement {
+            instance_buffer,
+            n_instances: instances.len() as u32,
+        }
+    }
+
+    pub fn draw_glyphs(&self, render_pass: &mut wgpu::RenderPass, glyphs: &InstancedGlyphsElement) {
+        self.draw_glyph_range(render_pass, glyphs, 0..glyphs.n_instances);
+    }
+
+    /// Draw the glyphs of `instances` in `glyphs` only.
+    pub fn draw_glyph_range(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        glyphs: &InstancedGlyphsElement,
+        instances: Range<u32>,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.wgpu_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, glyphs.instance_buffer.slice(..));
+        render_pass.draw(0..6, instances);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InstancedGlyphsElement {
+    instance_buffer: VertexBuffer<GlyphInstance>,
+    n_instances: u32,
+}
+
+impl InstancedGlyphsElement {
+    /// The glyphs of the first `n_instances` instances in `instance_buffer`, which may be written
+    /// to after creation (e.g. the buffer of a `GrowableVertexBuffer`).
+    pub fn from_raw_parts(instance_buffer: VertexBuffer<GlyphInstance>, n_instances: u32) -> Self {
+        Self {
+            instance_buffer,
+            n_instances,
+        }
+    }
+}
+
+/// A glyph positioned on the canvas, in logical coordinates.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct GlyphInstance {
+    pub origin: [f32; 2],
+    pub size: [f32; 2],
+    pub uv_offset: [f32; 2],
+    pub fg_color: [f32; 4],
+    pub bg_color: [f32; 4],
+}
+
+impl Vertex for GlyphInstance {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: size_of::<Self>() as u64,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &wgpu::vertex_attr_array! [
+            0 => Float32x2, // origin
+            1 => Float32x2, // size
+            2 => Float32x2, // uv_offset
+            3 => Float32x4, // fg_color
+            4 => Float32x4, // bg_color
+        ],
+    };
+}
@@ -1,11 +1,19 @@
 mod image;
+mod instanced_image;
 mod instanced_rect;
+mod instanced_text;
+mod path;
 mod rect;
+mod shadow;
 mod text;
 mod camera_bind_group;
 
+pub use instanced_image::*;
 pub use instanced_rect::*;
+pub use instanced_text::*;
+pub use path::*;
 pub use rect::*;
+pub use shadow::*;
 pub use image::*;
 pub use text::*;
 pub use camera_bind_group::*;
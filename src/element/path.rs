@@ -0,0 +1,755 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+use derive_more::{Display, Error};
+
+use crate::{
+    Bounds, CanvasFormat, LineCap, LineJoin, Path, Rgba, Stroke,
+    element::CameraBindGroup,
+    path::Polyline,
+    resources::{AppResources, LoadResourceError},
+    utils::*,
+    wgpu_utils::{AsBindGroup, IndexBuffer, UniformBuffer, Vertex, VertexBuffer},
+};
+
+#[derive(Debug, Clone, AsBindGroup)]
+struct PathBindGroup {
+    #[binding(0)]
+    #[uniform]
+    model_view: UniformBuffer<[[f32; 4]; 4]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct PathVertex {
+    pub position: [f32; 2],
+    /// Signed distance to the edge of the shape, negative inside.
+    ///
+    /// Interpolated across triangles for anti-aliasing, where pixels at distances of `0` are half
+    /// covered.
+    pub distance: f32,
+    /// Linear, not premultiplied.
+    pub color: [f32; 4],
+}
+
+impl Vertex for PathVertex {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: size_of::<Self>() as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x2,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32,
+                offset: size_of::<[f32; 2]>() as u64,
+                shader_location: 1,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x4,
+                offset: size_of::<[f32; 3]>() as u64,
+                shader_location: 2,
+            },
+        ],
+    };
+}
+
+/// Distance of vertices far enough inside a shape for it to be fully covered.
+const INSIDE: f32 = -1e6;
+
+/// Max ratio of the distance from a concave corner of a filled shape to the outer corner of its
+/// anti-aliasing fringe, to the width of the fringe.
+const FRINGE_MITER_LIMIT: f32 = 4.;
+
+/// Why `PathMesh::fill` did not fill a path.
+///
+/// These paths need a fill rule (even-odd or nonzero) to decide which parts are inside, which
+/// `PathMesh` does not support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Error)]
+pub enum PathFillError {
+    #[display("a subpath intersects itself")]
+    SelfIntersectingSubpath,
+    #[display("subpaths intersect or contain each other")]
+    OverlappingSubpaths,
+}
+
+/// Triangles of filled and stroked paths, tessellated on the CPU.
+///
+/// Edges are anti-aliased with a fringe of one pixel outside of them, so tessellation depends on
+/// `pixel_size`, the size of a physical pixel in the unit of the path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathMesh {
+    pub vertices: Vec<PathVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl PathMesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Bounding box of the vertices, `None` if there are none.
+    pub fn bounds(&self) -> Option<Bounds<f32>> {
+        let (first, rest) = self.vertices.split_first()?;
+        let [mut x_min, mut y_min] = first.position;
+        let [mut x_max, mut y_max] = first.position;
+        for vertex in rest {
+            let [x, y] = vertex.position;
+            x_min = x_min.min(x);
+            y_min = y_min.min(y);
+            x_max = x_max.max(x);
+            y_max = y_max.max(y);
+        }
+        Some(Bounds::from_scalars(
+            x_min,
+            y_min,
+            x_max - x_min,
+            y_max - y_min,
+        ))
+    }
+
+    /// Fill the subpaths of `path`, with open subpaths implicitly closed.
+    ///
+    /// Nothing is filled if a subpath intersects itself, or subpaths intersect or contain each
+    /// other (e.g. holes), see `PathFillError`.
+    pub fn fill(
+        &mut self,
+        path: &Path,
+        color: impl Into<Rgba>,
+        pixel_size: f32,
+    ) -> Result<(), PathFillError> {
+        let color = color.into().to_array();
+        let polygons: Vec<Vec<Point2<f32>>> = path
+            .flatten(0.25 * pixel_size)
+            .into_iter()
+            .map(|polyline| without_near_duplicates(polyline.points, 0.01 * pixel_size))
+            .filter(|points| points.len() >= 3 && signed_area(points) != 0.)
+            .collect();
+        for (i, polygon) in polygons.iter().enumerate() {
+            if is_self_intersecting(polygon) {
+                return Err(PathFillError::SelfIntersectingSubpath);
+            }
+            for other in &polygons[i + 1..] {
+                let intersecting = edges(polygon).any(|(a_0, a_1)| {
+                    edges(other).any(|(b_0, b_1)| do_segments_cross(a_0, a_1, b_0, b_1))
+                });
+                // Without intersections, either polygon is inside of the other if any of its
+                // points is.
+                if intersecting
+                    || is_in_polygon(polygon[0], other)
+                    || is_in_polygon(other[0], polygon)
+                {
+                    return Err(PathFillError::OverlappingSubpaths);
+                }
+            }
+        }
+        for polygon in &polygons {
+            self.fill_polygon(polygon, color, pixel_size);
+        }
+        Ok(())
+    }
+
+    /// Stroke the subpaths of `path`.
+    ///
+    /// Overlapping parts of the stroke are drawn over each other, which shows for translucent
+    /// colors.
+    pub fn stroke(&mut self, path: &Path, stroke: &Stroke, pixel_size: f32) {
+        let mut tessellator = StrokeTessellator {
+            mesh: self,
+            stroke,
+            color: stroke.color.to_array(),
+            half_width: 0.5 * stroke.width,
+            pixel_size,
+        };
+        for polyline in path.flatten(0.25 * pixel_size) {
+            if stroke.dashes.is_empty() {
+                tessellator.polyline(&polyline);
+            } else {
+                for dash in polyline.dashed(&stroke.dashes, stroke.dash_offset) {
+                    tessellator.polyline(&dash);
+                }
+            }
+        }
+    }
+
+    fn vertex(&mut self, position: Point2<f32>, distance: f32, color: [f32; 4]) -> u32 {
+        self.vertices.push(PathVertex {
+            position: position.into(),
+            distance,
+            color,
+        });
+        self.vertices.len() as u32 - 1
+    }
+
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend([a, b, c]);
+    }
+
+    fn quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.indices.extend([a, b, c, c, d, a]);
+    }
+
+    fn fill_polygon(&mut self, points: &[Point2<f32>], color: [f32; 4], pixel_size: f32) {
+        if points.len() < 3 {
+            return;
+        }
+        let n = points.len();
+        let signed_area = signed_area(points);
+        if signed_area == 0. {
+            return;
+        }
+        let orientation = signed_area.signum();
+        let first = self.vertices.len() as u32;
+        for &point in points {
+            self.vertex(point, INSIDE, color);
+        }
+
+        // Interior, by ear clipping.
+        let mut remaining: Vec<usize> = (0..n).collect();
+        while remaining.len() > 3 {
+            let m = remaining.len();
+            let ear = (0..m).find(|&i| {
+                let a = points[remaining[(i + m - 1) % m]];
+                let b = points[remaining[i]];
+                let c = points[remaining[(i + 1) % m]];
+                cross(b - a, c - b) * orientation > 0.
+                    && (remaining.iter())
+                        .map(|&j| points[j])
+                        .filter(|&p| p != a && p != b && p != c)
+                        .all(|p| !is_in_triangle(p, a, b, c))
+            });
+            // Degenerate polygons have no ears left, so fan out the rest.
+            let Some(i) = ear else {
+                break;
+            };
+            self.triangle(
+                first + remaining[(i + m - 1) % m] as u32,
+                first + remaining[i] as u32,
+                first + remaining[(i + 1) % m] as u32,
+            );
+            remaining.remove(i);
+        }
+        for i in 1..remaining.len() - 1 {
+            self.triangle(
+                first + remaining[0] as u32,
+                first + remaining[i] as u32,
+                first + remaining[i + 1] as u32,
+            );
+        }
+
+        // Anti-aliasing fringe outside of the edges, and wedges around the convex corners. At
+        // concave corners, the fringes of the two edges would overlap (which shows for translucent
+        // colors), so they are cut where their outer edges meet instead, unless the corners are too
+        // sharp for that.
+        let outward_normals: Vec<Vector2<f32>> = (0..n)
+            .map(|i| {
+                let d = (points[(i + 1) % n] - points[i]).normalize();
+                orientation * vec2(d.y, -d.x)
+            })
+            .collect();
+        // The outer corners of the fringes of the edges ending and starting at each point.
+        let outer_corners: Vec<(Point2<f32>, Point2<f32>)> = (0..n)
+            .map(|i| {
+                let normal_0 = outward_normals[(i + n - 1) % n];
+                let normal_1 = outward_normals[i];
+                let is_concave = cross(normal_0, normal_1) * orientation <= 0.;
+                // The ratio of the miter length to the fringe width is `sqrt(2 / (1 + cos))`.
+                let cos_angle = normal_0.dot(normal_1);
+                if is_concave && 1. + cos_angle >= 2. / FRINGE_MITER_LIMIT.powi(2) {
+                    let miter = points[i] + (normal_0 + normal_1) * (pixel_size / (1. + cos_angle));
+                    (miter, miter)
+                } else {
+                    (
+                        points[i] + normal_0 * pixel_size,
+                        points[i] + normal_1 * pixel_size,
+                    )
+                }
+            })
+            .collect();
+        for i in 0..n {
+            let j = (i + 1) % n;
+            let a = self.vertex(points[i], 0., color);
+            let b = self.vertex(points[j], 0., color);
+            let c = self.vertex(outer_corners[j].0, pixel_size, color);
+            let d = self.vertex(outer_corners[i].1, pixel_size, color);
+            self.quad(a, b, c, d);
+            if cross(outward_normals[i], outward_normals[j]) * orientation > 0. {
+                let a = self.vertex(points[j], 0., color);
+                let b = self.vertex(outer_corners[j].0, pixel_size, color);
+                let c = self.vertex(outer_corners[j].1, pixel_size, color);
+                self.triangle(a, b, c);
+            }
+        }
+    }
+}
+
+fn cross(a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Twice the signed area of the polygon, positive if clockwise (with y pointing down).
+fn signed_area(points: &[Point2<f32>]) -> f32 {
+    edges(points)
+        .map(|(a, b)| cross(a.to_vec(), b.to_vec()))
+        .sum()
+}
+
+/// `points` without the ones closer than `min_distance` to the previous one, or to the first one at
+/// the end (e.g. where a full circle arc ends up after rounding errors), since the edges between
+/// them could cross their neighbours.
+fn without_near_duplicates(points: Vec<Point2<f32>>, min_distance: f32) -> Vec<Point2<f32>> {
+    let mut result: Vec<Point2<f32>> = Vec::with_capacity(points.len());
+    for point in points {
+        if result
+            .last()
+            .is_none_or(|&last| last.distance(point) >= min_distance)
+        {
+            result.push(point);
+        }
+    }
+    while result.len() > 1 && result[0].distance(result[result.len() - 1]) < min_distance {
+        result.pop();
+    }
+    result
+}
+
+/// The edges of the polygon, including the one closing it.
+fn edges(points: &[Point2<f32>]) -> impl Iterator<Item = (Point2<f32>, Point2<f32>)> {
+    let n = points.len();
+    (0..n).map(move |i| (points[i], points[(i + 1) % n]))
+}
+
+/// Whether the segments cross each other, not counting segments that only touch.
+fn do_segments_cross(
+    a_0: Point2<f32>,
+    a_1: Point2<f32>,
+    b_0: Point2<f32>,
+    b_1: Point2<f32>,
+) -> bool {
+    let sides_of_b = cross(a_1 - a_0, b_0 - a_0) * cross(a_1 - a_0, b_1 - a_0);
+    let sides_of_a = cross(b_1 - b_0, a_0 - b_0) * cross(b_1 - b_0, a_1 - b_0);
+    sides_of_b < 0. && sides_of_a < 0.
+}
+
+fn is_self_intersecting(points: &[Point2<f32>]) -> bool {
+    let n = points.len();
+    (0..n).any(|i| {
+        // Neighbouring edges share a point, so only the edges after the next one are checked (and
+        // not the last one for the first edge).
+        let end = if i == 0 { n - 1 } else { n };
+        (i + 2..end).any(|j| {
+            do_segments_cross(
+                points[i],
+                points[(i + 1) % n],
+                points[j],
+                points[(j + 1) % n],
+            )
+        })
+    })
+}
+
+/// Whether `point` is inside of the polygon, by the even-odd rule.
+fn is_in_polygon(point: Point2<f32>, polygon: &[Point2<f32>]) -> bool {
+    edges(polygon)
+        .filter(|&(a, b)| {
+            (a.y > point.y) != (b.y > point.y)
+                && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        })
+        .count()
+        % 2
+        == 1
+}
+
+fn is_in_triangle(p: Point2<f32>, a: Point2<f32>, b: Point2<f32>, c: Point2<f32>) -> bool {
+    let d0 = cross(b - a, p - a);
+    let d1 = cross(c - b, p - b);
+    let d2 = cross(a - c, p - c);
+    let has_negative = d0 < 0. || d1 < 0. || d2 < 0.;
+    let has_positive = d0 > 0. || d1 > 0. || d2 > 0.;
+    !(has_negative && has_positive)
+}
+
+/// Stroke geometry is split at the center line, which is `half_width` inside the edges, so that
+/// the distance is interpolated across the width.
+struct StrokeTessellator<'a> {
+    mesh: &'a mut PathMesh,
+    stroke: &'a Stroke,
+    color: [f32; 4],
+    half_width: f32,
+    pixel_size: f32,
+}
+
+impl StrokeTessellator<'_> {
+    /// Distance from the center line to the outer edge of the anti-aliasing fringe.
+    fn extent(&self) -> f32 {
+        self.half_width + self.pixel_size
+    }
+
+    fn center(&mut self, point: Point2<f32>) -> u32 {
+        self.mesh.vertex(point, -self.half_width, self.color)
+    }
+
+    fn outer(&mut self, point: Point2<f32>) -> u32 {
+        self.mesh.vertex(point, self.pixel_size, self.color)
+    }
+
+    fn polyline(&mut self, polyline: &Polyline) {
+        let mut points = polyline.points.clone();
+        if points.len() == 1 {
+            // A zero-length subpath only shows with round caps, as a dot.
+            if !polyline.closed && self.stroke.cap == LineCap::Round {
+                self.round_cap(points[0], vec2(1., 0.));
+                self.round_cap(points[0], vec2(-1., 0.));
+            }
+            return;
+        }
+        if polyline.closed {
+            points.push(points[0]);
+        } else if self.stroke.cap == LineCap::Square {
+            let n = points.len();
+            let start_direction = (points[1] - points[0]).normalize();
+            let end_direction = (points[n - 1] - points[n - 2]).normalize();
+            points[0] -= start_direction * self.half_width;
+            points[n - 1] += end_direction * self.half_width;
+        }
+        for segment in points.windows(2) {
+            self.segment(segment[0], segment[1]);
+        }
+        for joint in points.windows(3) {
+            self.join(joint[0], joint[1], joint[2]);
+        }
+        if polyline.closed {
+            let n = points.len();
+            self.join(points[n - 2], points[0], points[1]);
+        } else if self.stroke.cap == LineCap::Round {
+            let n = points.len();
+            self.round_cap(points[0], (points[0] - points[1]).normalize());
+            self.round_cap(points[n - 1], (points[n - 1] - points[n - 2]).normalize());
+        }
+    }
+
+    fn segment(&mut self, start: Point2<f32>, end: Point2<f32>) {
+        let direction = (end - start).normalize();
+        let normal = vec2(-direction.y, direction.x) * self.extent();
+        for side in [normal, -normal] {
+            let a = self.center(start);
+            let b = self.center(end);
+            let c = self.outer(end + side);
+            let d = self.outer(start + side);
+            self.mesh.quad(a, b, c, d);
+        }
+    }
+
+    /// Fill the gap on the outer side of the corner at `point`.
+    fn join(&mut self, previous: Point2<f32>, point: Point2<f32>, next: Point2<f32>) {
+        let direction_0 = (point - previous).normalize();
+        let direction_1 = (next - point).normalize();
+        let turn = cross(direction_0, direction_1);
+        if turn.abs() < 1e-6 && direction_0.dot(direction_1) > 0. {
+            return;
+        }
+        // The outer side is to the right of turns to the left, and vice versa.
+        let side = if turn > 0. { -1. } else { 1. };
+        let normal_0 = side * vec2(-direction_0.y, direction_0.x);
+        let normal_1 = side * vec2(-direction_1.y, direction_1.x);
+        let extent = self.extent();
+        match self.stroke.join {
+            LineJoin::Round => {
+                let start_angle = normal_0.y.atan2(normal_0.x);
+                let sweep = cross(normal_0, normal_1).atan2(normal_0.dot(normal_1));
+                self.fan(point, start_angle, sweep);
+            }
+            LineJoin::Miter | LineJoin::Bevel => {
+                let center = self.center(point);
+                let a = self.outer(point + normal_0 * extent);
+                let b = self.outer(point + normal_1 * extent);
+                let miter_direction = normal_0 + normal_1;
+                let cos_half_angle = miter_direction.magnitude() / 2.;
+                let miter_ratio = 1. / cos_half_angle.max(1e-6);
+                if self.stroke.join == LineJoin::Bevel
+                    || miter_ratio > self.stroke.miter_limit
+                    || miter_direction.magnitude2() < 1e-12
+                {
+                    self.mesh.triangle(center, a, b);
+                } else {
+                    let tip = point + miter_direction.normalize() * (extent * miter_ratio);
+                    let tip = self.outer(tip);
+                    self.mesh.quad(center, a, tip, b);
+                }
+            }
+        }
+    }
+
+    /// Half disc at the end `point` of a subpath, bulging in `direction`.
+    fn round_cap(&mut self, point: Point2<f32>, direction: Vector2<f32>) {
+        let start_angle = direction.y.atan2(direction.x) - std::f32::consts::FRAC_PI_2;
+        self.fan(point, start_angle, std::f32::consts::PI);
+    }
+
+    /// Circular sector around `center` with a radius of `extent`.
+    fn fan(&mut self, center: Point2<f32>, start_angle: f32, sweep: f32) {
+        let extent = self.extent();
+        // Segments short enough that the chords are within a quarter of a pixel of the arc.
+        let max_step = 2. * (1. - 0.25 * self.pixel_size / extent).clamp(-1., 1.).acos();
+        let n = ((sweep.abs() / max_step.max(1e-3)).ceil() as u32).clamp(1, 256);
+        let center_index = self.center(center);
+        let mut previous = None;
+        for i in 0..=n {
+            let angle = start_angle + sweep * (i as f32 / n as f32);
+            let rim = self.outer(center + extent * vec2(angle.cos(), angle.sin()));
+            if let Some(previous) = previous {
+                self.mesh.triangle(center_index, previous, rim);
+            }
+            previous = Some(rim);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PathRenderer<'cx> {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    _shader: &'cx wgpu::ShaderModule,
+}
+
+impl<'cx> PathRenderer<'cx> {
+    pub fn create(
+        device: &wgpu::Device,
+        resources: &'cx AppResources,
+        canvas_format: CanvasFormat,
+    ) -> Result<Self, LoadResourceError> {
+        let shader = resources.load_shader("shaders/path.wgsl", device)?;
+        let bind_group_layout = PathBindGroup::create_bind_group_layout(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[
+                &CameraBindGroup::create_bind_group_layout(device),
+                &bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: the_default(),
+                buffers: &[PathVertex::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                compilation_options: the_default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: canvas_format.color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            operation: wgpu::BlendOperation::Add,
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        },
+                        alpha: canvas_format.alpha_blend.blend_component(),
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: the_default(),
+            depth_stencil: canvas_format.depth_stencil_state(),
+            multisample: canvas_format.multisample_state(),
+            multiview: None,
+            cache: None,
+        });
+        Ok(Self {
+            bind_group_layout,
+            pipeline,
+            _shader: shader,
+        })
+    }
+
+    pub fn create_path(&self, device: &wgpu::Device, mesh: &PathMesh) -> PathElement {
+        let bind_group = PathBindGroup {
+            model_view: UniformBuffer::create_init(device, Matrix4::identity().into()),
+        };
+        let wgpu_bind_group = bind_group.create_bind_group(&self.bind_group_layout, device);
+        PathElement {
+            bind_group,
+            wgpu_bind_group,
+            vertex_buffer: VertexBuffer::create_init(device, &mesh.vertices),
+            index_buffer: IndexBuffer::create_init(device, &mesh.indices),
+        }
+    }
+
+    pub fn draw_path(&self, render_pass: &mut wgpu::RenderPass, path: &PathElement) {
+        if path.index_buffer.length() == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &path.wgpu_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, path.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(
+            path.index_buffer.slice(..),
+            path.index_buffer.index_format(),
+        );
+        render_pass.draw_indexed(0..path.index_buffer.length(), 0, 0..1);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PathElement {
+    bind_group: PathBindGroup,
+    wgpu_bind_group: wgpu::BindGroup,
+    vertex_buffer: VertexBuffer<PathVertex>,
+    index_buffer: IndexBuffer<u32>,
+}
+
+impl PathElement {
+    pub fn set_model_view(&self, queue: &wgpu::Queue, model_view: Matrix4<f32>) {
+        self.bind_group.model_view.write(model_view.into(), queue);
+    }
+
+    /// Convenience function over `set_model_view`.
+    /// Sets `model_view` to a translation to `origin`.
+    pub fn set_origin(&self, queue: &wgpu::Queue, origin: Point2<f32>) {
+        self.set_model_view(queue, Matrix4::from_translation(origin.to_vec().extend(0.)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon_path(points: &[(f32, f32)]) -> Path {
+        let mut path = Path::new();
+        for &(x, y) in points {
+            path.line_to(point2(x, y));
+        }
+        path.close();
+        path
+    }
+
+    /// Sum of the areas of the triangles, which is the area covered if none overlap.
+    fn triangles_area(mesh: &PathMesh) -> f32 {
+        let position = |i: u32| Point2::from(mesh.vertices[i as usize].position);
+        (mesh.indices.chunks(3))
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| position(triangle[i]));
+                0.5 * cross(b - a, c - a).abs()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn fringes_of_concave_fills_do_not_overlap() {
+        // An L shape, with a concave corner at (10, 10).
+        let path = polygon_path(&[
+            (0., 0.),
+            (20., 0.),
+            (20., 10.),
+            (10., 10.),
+            (10., 20.),
+            (0., 20.),
+        ]);
+        let mut mesh = PathMesh::new();
+        mesh.fill(&path, Rgba::from_hex(0xFFFFFF80), 1.).unwrap();
+        // The shape, the fringes along the perimeter of 80, and the wedges at the 5 convex
+        // corners, without the 1x1 square where the fringes would overlap at the concave corner.
+        let expected_area = 300. + 80. + 5. * 0.5 - 1.;
+        assert!((triangles_area(&mesh) - expected_area).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fills_disjoint_subpaths() {
+        let mut path = polygon_path(&[(0., 0.), (10., 0.), (10., 10.)]);
+        path.move_to(point2(20., 0.))
+            .line_to(point2(30., 0.))
+            .line_to(point2(30., 10.))
+            .close();
+        let mut mesh = PathMesh::new();
+        assert_eq!(mesh.fill(&path, Rgba::from_hex(0xFFFFFFFF), 1.), Ok(()));
+        assert!(!mesh.is_empty());
+    }
+
+    #[test]
+    fn fills_full_circle_arcs() {
+        let mut path = Path::new();
+        path.move_to(point2(20., 10.))
+            .arc(point2(10., 10.), 10., 0., 2. * std::f32::consts::PI)
+            .close();
+        let mut mesh = PathMesh::new();
+        assert_eq!(mesh.fill(&path, Rgba::from_hex(0xFFFFFFFF), 1.), Ok(()));
+        assert!(!mesh.is_empty());
+    }
+
+    #[test]
+    fn rejects_paths_that_need_a_fill_rule() {
+        let mut mesh = PathMesh::new();
+        let bow_tie = polygon_path(&[(0., 0.), (10., 10.), (10., 0.), (0., 20.)]);
+        assert_eq!(
+            mesh.fill(&bow_tie, Rgba::from_hex(0xFFFFFFFF), 1.),
+            Err(PathFillError::SelfIntersectingSubpath),
+        );
+        let mut with_hole = polygon_path(&[(0., 0.), (30., 0.), (30., 30.), (0., 30.)]);
+        with_hole
+            .move_to(point2(10., 10.))
+            .line_to(point2(20., 10.))
+            .line_to(point2(20., 20.))
+            .close();
+        assert_eq!(
+            mesh.fill(&with_hole, Rgba::from_hex(0xFFFFFFFF), 1.),
+            Err(PathFillError::OverlappingSubpaths),
+        );
+        let mut crossing = polygon_path(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        crossing
+            .move_to(point2(5., 5.))
+            .line_to(point2(15., 5.))
+            .line_to(point2(15., 15.))
+            .close();
+        assert_eq!(
+            mesh.fill(&crossing, Rgba::from_hex(0xFFFFFFFF), 1.),
+            Err(PathFillError::OverlappingSubpaths),
+        );
+        assert!(mesh.is_empty());
+    }
+
+    /// Stroke of a corner at (100, 0) between a line from the left and a line back to the left,
+    /// at an angle of about 11°.
+    fn sharp_corner_stroke(miter_limit: f32) -> PathMesh {
+        let mut path = Path::new();
+        path.move_to(point2(0., 0.))
+            .line_to(point2(100., 0.))
+            .line_to(point2(0., 20.));
+        let stroke = Stroke::new(4., Rgba::from_hex(0xFFFFFFFF)).with_miter_limit(miter_limit);
+        let mut mesh = PathMesh::new();
+        mesh.stroke(&path, &stroke, 1.);
+        mesh
+    }
+
+    #[test]
+    fn miter_joins_fall_back_to_bevel_joins_past_the_miter_limit() {
+        // Half the width and the fringe.
+        let extent = 3.;
+        let beveled = sharp_corner_stroke(4.).bounds().unwrap();
+        assert!(beveled.x_max() < 100. + extent);
+        let mitered = sharp_corner_stroke(100.).bounds().unwrap();
+        assert!(mitered.x_max() > 100. + 5. * extent);
+    }
+
+    #[test]
+    fn miter_joins_of_right_angles_reach_the_corner_of_the_extent() {
+        let mut path = Path::new();
+        path.move_to(point2(0., 0.))
+            .line_to(point2(10., 0.))
+            .line_to(point2(10., 10.));
+        let mut mesh = PathMesh::new();
+        mesh.stroke(&path, &Stroke::new(4., Rgba::from_hex(0xFFFFFFFF)), 1.);
+        let has_tip = (mesh.vertices.iter())
+            .any(|vertex| (Point2::from(vertex.position) - point2(13., -3.)).magnitude() < 1e-4);
+        assert!(has_tip);
+    }
+}
@@ -1,11 +1,54 @@
 use std::fmt::Debug;
 
+use bytemuck::{Pod, Zeroable};
 use cgmath::*;
 
 use crate::{
-    element::CameraBindGroup, resources::{AppResources, LoadResourceError}, utils::*, wgpu_utils::{AsBindGroup, UniformBuffer}, Bounds, CanvasFormat, LineWidth, Rgba
+    element::CameraBindGroup, resources::{AppResources, LoadResourceError}, utils::*, wgpu_utils::{AsBindGroup, UniformBuffer}, Bounds, CanvasFormat, CornerRadius, Fill, LineWidth, Rgba, MAX_GRADIENT_STOPS
 };
 
+/// GPU-side representation of a `Fill`, matching `struct Fill` in `rect.wgsl`.
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+#[repr(C)]
+struct FillUniform {
+    kind: u32,
+    n_stops: u32,
+    _padding: [u32; 2],
+    parameters: [f32; 4],
+    colors: [[f32; 4]; MAX_GRADIENT_STOPS],
+    offsets: [[f32; 4]; MAX_GRADIENT_STOPS / 4],
+}
+
+impl FillUniform {
+    fn new(fill: Fill) -> Self {
+        let mut uniform = Self::zeroed();
+        let stops = match fill {
+            Fill::Solid(color) => {
+                uniform.n_stops = 1;
+                uniform.colors[0] = Rgba::from(color).to_array();
+                return uniform;
+            }
+            Fill::LinearGradient(gradient) => {
+                uniform.kind = 1;
+                uniform.parameters = [gradient.angle.cos(), gradient.angle.sin(), 0., 0.];
+                gradient.stops
+            }
+            Fill::RadialGradient(gradient) => {
+                uniform.kind = 2;
+                uniform.parameters = [gradient.center.x, gradient.center.y, gradient.radius, 0.];
+                gradient.stops
+            }
+        };
+        let stops = stops.as_slice();
+        uniform.n_stops = stops.len() as u32;
+        for (i, stop) in stops.iter().enumerate() {
+            uniform.colors[i] = Rgba::from(stop.color).to_array();
+            uniform.offsets[i / 4][i % 4] = stop.offset;
+        }
+        uniform
+    }
+}
+
 #[derive(Debug, Clone, AsBindGroup)]
 struct RectBindGroup {
     #[binding(0)]
@@ -14,7 +57,7 @@ struct RectBindGroup {
 
     #[binding(1)]
     #[uniform]
-    fill_color: UniformBuffer<Rgba>,
+    fill: UniformBuffer<FillUniform>,
 
     #[binding(2)]
     #[uniform]
@@ -23,6 +66,10 @@ struct RectBindGroup {
     #[binding(3)]
     #[uniform]
     line_width: UniformBuffer<[f32; 4]>,
+
+    #[binding(4)]
+    #[uniform]
+    corner_radius: UniformBuffer<[f32; 4]>,
 }
 
 #[derive(Debug, Clone)]
@@ -69,22 +116,14 @@ impl<'cx> RectRenderer<'cx> {
                             src_factor: wgpu::BlendFactor::SrcAlpha,
                             dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
                         },
-                        alpha: wgpu::BlendComponent::REPLACE,
+                        alpha: canvas_format.alpha_blend.blend_component(),
                     }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             primitive: the_default(),
-            depth_stencil: canvas_format.depth_stencil_format.map(|format| {
-                wgpu::DepthStencilState {
-                    format,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: the_default(),
-                    bias: the_default(),
-                }
-            }),
-            multisample: the_default(),
+            depth_stencil: canvas_format.depth_stencil_state(),
+            multisample: canvas_format.multisample_state(),
             multiview: None,
             cache: None,
         });
@@ -98,9 +137,10 @@ impl<'cx> RectRenderer<'cx> {
     pub fn create_rect(&self, device: &wgpu::Device) -> RectElement {
         let bind_group = RectBindGroup {
             model_view: UniformBuffer::create_init(device, Matrix4::identity().into()),
-            fill_color: UniformBuffer::create_init(device, Rgba::from_hex(0xFFFFFFFF)),
+            fill: UniformBuffer::create_init(device, FillUniform::new(Fill::default())),
             line_color: UniformBuffer::create_init(device, Rgba::from_hex(0xFFFFFFFF)),
             line_width: UniformBuffer::create_init(device, [0., 0., 0., 0.]),
+            corner_radius: UniformBuffer::create_init(device, [0., 0., 0., 0.]),
         };
         let wgpu_bind_group = bind_group.create_bind_group(&self.bind_group_layout, device);
         RectElement {
@@ -142,8 +182,10 @@ impl RectElement {
         self.set_normalized_line_width(queue, line_width.into().normalized_in(bounds.size));
     }
 
-    pub fn set_fill_color(&self, queue: &wgpu::Queue, fill_color: impl Into<Rgba>) {
-        self.bind_group.fill_color.write(fill_color.into(), queue);
+    pub fn set_fill_color(&self, queue: &wgpu::Queue, fill_color: impl Into<Fill>) {
+        self.bind_group
+            .fill
+            .write(FillUniform::new(fill_color.into()), queue);
     }
 
     pub fn set_line_color(&self, queue: &wgpu::Queue, line_color: impl Into<Rgba>) {
@@ -155,4 +197,10 @@ impl RectElement {
             .line_width
             .write(line_width.into().to_array(), queue);
     }
+
+    pub fn set_corner_radius(&self, queue: &wgpu::Queue, corner_radius: impl Into<CornerRadius>) {
+        self.bind_group
+            .corner_radius
+            .write(corner_radius.into().to_array(), queue);
+    }
 }
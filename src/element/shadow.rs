@@ -0,0 +1,165 @@
+use cgmath::*;
+
+use crate::{
+    Bounds, BoxShadow, CanvasFormat, CornerRadius, Rgba,
+    element::CameraBindGroup,
+    resources::{AppResources, LoadResourceError},
+    utils::*,
+    wgpu_utils::{AsBindGroup, UniformBuffer},
+};
+
+#[derive(Debug, Clone, AsBindGroup)]
+struct ShadowBindGroup {
+    #[binding(0)]
+    #[uniform]
+    model_view: UniformBuffer<[[f32; 4]; 4]>,
+
+    #[binding(1)]
+    #[uniform]
+    color: UniformBuffer<Rgba>,
+
+    #[binding(2)]
+    #[uniform]
+    shadow_rect: UniformBuffer<[f32; 4]>,
+
+    #[binding(3)]
+    #[uniform]
+    corner_radius: UniformBuffer<[f32; 4]>,
+
+    #[binding(4)]
+    #[uniform]
+    sigma: UniformBuffer<f32>,
+}
+
+/// Draws box shadows with an analytic approximation of Gaussian-blurred rounded rects.
+#[derive(Debug, Clone)]
+pub struct ShadowRenderer<'cx> {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    _shader: &'cx wgpu::ShaderModule,
+}
+
+impl<'cx> ShadowRenderer<'cx> {
+    pub fn create(
+        device: &wgpu::Device,
+        resources: &'cx AppResources,
+        canvas_format: CanvasFormat,
+    ) -> Result<Self, LoadResourceError> {
+        let shader = resources.load_shader("shaders/shadow.wgsl", device)?;
+        let bind_group_layout = ShadowBindGroup::create_bind_group_layout(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[
+                &CameraBindGroup::create_bind_group_layout(device),
+                &bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: the_default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                compilation_options: the_default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: canvas_format.color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            operation: wgpu::BlendOperation::Add,
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        },
+                        alpha: canvas_format.alpha_blend.blend_component(),
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: the_default(),
+            depth_stencil: canvas_format.depth_stencil_state(),
+            multisample: canvas_format.multisample_state(),
+            multiview: None,
+            cache: None,
+        });
+        Ok(Self {
+            bind_group_layout,
+            pipeline,
+            _shader: shader,
+        })
+    }
+
+    pub fn create_shadow(&self, device: &wgpu::Device) -> ShadowElement {
+        let bind_group = ShadowBindGroup {
+            model_view: UniformBuffer::create_init(device, Matrix4::identity().into()),
+            color: UniformBuffer::create_init(device, Rgba::from_hex(0x00000000)),
+            shadow_rect: UniformBuffer::create_init(device, [0., 0., 0., 0.]),
+            corner_radius: UniformBuffer::create_init(device, [0., 0., 0., 0.]),
+            sigma: UniformBuffer::create_init(device, 1.),
+        };
+        let wgpu_bind_group = bind_group.create_bind_group(&self.bind_group_layout, device);
+        ShadowElement {
+            bind_group,
+            wgpu_bind_group,
+        }
+    }
+
+    pub fn draw_shadow(&self, render_pass: &mut wgpu::RenderPass, shadow: &ShadowElement) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &shadow.wgpu_bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShadowElement {
+    bind_group: ShadowBindGroup,
+    wgpu_bind_group: wgpu::BindGroup,
+}
+
+impl ShadowElement {
+    /// The smallest standard deviation of the blur, so that edges of unblurred shadows are still
+    /// anti-aliased.
+    const MIN_SIGMA: f32 = 0.5;
+
+    /// Sets the shadow cast by a rect of `bounds` and `corner_radius`.
+    pub fn set_parameters(
+        &self,
+        queue: &wgpu::Queue,
+        bounds: Bounds<f32>,
+        corner_radius: impl Into<CornerRadius>,
+        shadow: BoxShadow,
+    ) {
+        let visible_bounds = shadow.visible_bounds(bounds);
+        let model_view = Matrix4::from_translation(visible_bounds.origin.to_vec().extend(0.))
+            * Matrix4::from_nonuniform_scale(
+                visible_bounds.size.width,
+                visible_bounds.size.height,
+                1.,
+            );
+        self.bind_group.model_view.write(model_view.into(), queue);
+        self.bind_group.color.write(shadow.color, queue);
+        let shadow_bounds = shadow.shadow_bounds(bounds);
+        self.bind_group.shadow_rect.write(
+            [
+                shadow_bounds.x_min(),
+                shadow_bounds.y_min(),
+                shadow_bounds.x_max(),
+                shadow_bounds.y_max(),
+            ],
+            queue,
+        );
+        let corner_radius = corner_radius
+            .into()
+            .to_array()
+            .map(|radius| (radius + shadow.spread).max(0.));
+        self.bind_group.corner_radius.write(corner_radius, queue);
+        let sigma = (0.5 * shadow.blur_radius).max(Self::MIN_SIGMA);
+        self.bind_group.sigma.write(sigma, queue);
+    }
+}
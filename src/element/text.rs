@@ -68,6 +68,33 @@ impl TextInstance {
             uv_offset,
         }
     }
+
+    /// Lay out the glyphs of `str` in rows and columns, with position offsets in the unit of
+    /// font size.
+    pub fn for_text(font: Font, str: &str) -> Vec<Self> {
+        let mut instances: Vec<TextInstance> = Vec::new();
+        let mut row = 0u32;
+        let mut column = 0u32;
+        for char in str.chars() {
+            if char == '\n' {
+                column = 0;
+                row += 1;
+                continue;
+            } else if char == '\r' {
+                column = 0;
+                continue;
+            }
+            let Some(glyph_bounds) = font.uv_bounds_for_char(char) else {
+                continue;
+            };
+            instances.push(TextInstance {
+                position_offset: [column as f32 * font.glyph_relative_width(), row as f32],
+                uv_offset: glyph_bounds.origin.into(),
+            });
+            column += 1;
+        }
+        instances
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -156,22 +183,14 @@ impl<'cx> TextRenderer<'cx> {
                             src_factor: wgpu::BlendFactor::SrcAlpha,
                             dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
                         },
-                        alpha: wgpu::BlendComponent::REPLACE,
+                        alpha: canvas_format.alpha_blend.blend_component(),
                     }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             primitive: the_default(),
-            depth_stencil: canvas_format.depth_stencil_format.map(|format| {
-                wgpu::DepthStencilState {
-                    format,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: the_default(),
-                    bias: the_default(),
-                }
-            }),
-            multisample: the_default(),
+            depth_stencil: canvas_format.depth_stencil_state(),
+            multisample: canvas_format.multisample_state(),
             multiview: None,
             cache: None,
         });
@@ -260,27 +279,7 @@ impl<'cx> TextRenderer<'cx> {
         device: &wgpu::Device,
         str: &str,
     ) -> (u32, VertexBuffer<TextInstance>) {
-        let mut instances: Vec<TextInstance> = Vec::new();
-        let mut row = 0u32;
-        let mut column = 0u32;
-        for char in str.chars() {
-            if char == '\n' {
-                column = 0;
-                row += 1;
-                continue;
-            } else if char == '\r' {
-                column = 0;
-                continue;
-            }
-            let Some(glyph_bounds) = self.font.uv_bounds_for_char(char) else {
-                continue;
-            };
-            instances.push(TextInstance {
-                position_offset: [column as f32 * self.font.glyph_relative_width(), row as f32],
-                uv_offset: glyph_bounds.origin.into(),
-            });
-            column += 1;
-        }
+        let instances = TextInstance::for_text(self.font, str);
         let instance_buffer = VertexBuffer::create_init(device, &instances);
         (instances.len() as u32, instance_buffer)
     }
@@ -288,4 +287,12 @@ impl<'cx> TextRenderer<'cx> {
     pub fn font(&self) -> Font<'cx> {
         self.font
     }
+
+    pub(crate) fn atlas_texture_view(&self) -> &wgpu::TextureView {
+        &self.texture_view
+    }
+
+    pub(crate) fn sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
 }
@@ -8,11 +8,11 @@ use std::{
 
 use cgmath::*;
 
-use winit::event::{MouseButton, WindowEvent};
+use winit::event::{MouseButton, MouseScrollDelta, WindowEvent};
 
 use crate::Bounds;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MouseEventKind {
     HoveringStart,
     HoveringFinish,
@@ -27,6 +27,15 @@ pub enum MouseEventKind {
         button: MouseButton,
         inside: bool,
     },
+    /// The cursor moved while a button is held down, with the button having been pressed inside
+    /// the bounds.
+    Dragging { button: MouseButton },
+    /// Scrolling by a mouse wheel or a trackpad while the cursor is inside the bounds.
+    Scroll {
+        /// Scroll distance in logical pixels. Positive values mean scrolling towards the top/left
+        /// of the content.
+        delta: Vector2<f32>,
+    },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -46,8 +55,21 @@ impl MouseEvent {
 
 pub trait MouseEventListener<UiState>: Send + Sync {
     fn mouse_event(&self, event: MouseEvent, ui_state: &mut UiState);
+
+    /// Handle a `Scroll` event, returning if the event is consumed.
+    ///
+    /// Scroll events are offered to the innermost listener under the cursor first, and passed on
+    /// to the enclosing listeners until one consumes it, so that nested scroll views don't all
+    /// scroll at once. By default the event is given to `mouse_event` and not consumed.
+    fn scroll_event(&self, event: MouseEvent, ui_state: &mut UiState) -> bool {
+        self.mouse_event(event, ui_state);
+        false
+    }
 }
 
+/// Scroll distance for one line of a line-based scroll delta (typically from mouse wheels).
+const SCROLL_LINE_HEIGHT: f32 = 24.;
+
 pub struct EventRouter<'cx, UiState> {
     inner: Mutex<EventRouterInner<'cx, UiState>>,
     dispatch: Arc<EventRouterDispatch>,
@@ -64,7 +86,11 @@ struct EventRouterInner<'cx, UiState> {
 }
 
 struct EventRouterDispatch {
-    bounds_updates: Mutex<HashMap<usize, Bounds<f32>>>,
+    /// `None` for listeners that are completely clipped away.
+    bounds_updates: Mutex<HashMap<usize, Option<Bounds<f32>>>>,
+    /// Stack of clip bounds pushed by `ListenerHandle::push_clip_bounds`, each entry already
+    /// intersected with the entries below it.
+    clip_stack: Mutex<Vec<Option<Bounds<f32>>>>,
     /// List of objects to deregister.
     deregisters: Mutex<HashSet<usize>>,
 }
@@ -80,6 +106,7 @@ impl<'cx, UiState> EventRouter<'cx, UiState> {
             }),
             dispatch: Arc::new(EventRouterDispatch {
                 bounds_updates: Mutex::new(HashMap::new()),
+                clip_stack: Mutex::new(Vec::new()),
                 deregisters: Mutex::new(HashSet::new()),
             }),
         }
@@ -94,7 +121,7 @@ impl<'cx, UiState> EventRouter<'cx, UiState> {
         let listeners = &mut inner.listeners;
         let index = listeners.len();
         listeners.push(Some(Listener {
-            bounds,
+            bounds: Some(bounds),
             is_hovered: false,
             button_states: array::from_fn(|_| false),
             object: Box::new(listener),
@@ -138,7 +165,24 @@ impl<'cx, UiState> EventRouter<'cx, UiState> {
                 let position_logical = position.to_logical::<f32>(inner.scale_factor);
                 let cursor_position = point2(position_logical.x, position_logical.y);
                 inner.cursor_position = Some(cursor_position);
-                self.scan_events(ui_state, &mut inner)
+                let should_redraw = self.scan_events(ui_state, &mut inner);
+                self.scan_drags(ui_state, &mut inner) || should_redraw
+            }
+            &WindowEvent::MouseWheel {
+                device_id: _,
+                delta,
+                phase: _,
+            } => {
+                let mut inner = self.inner.lock().unwrap();
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => vec2(x, y) * SCROLL_LINE_HEIGHT,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let position_logical = position.to_logical::<f32>(inner.scale_factor);
+                        vec2(position_logical.x, position_logical.y)
+                    }
+                };
+                let should_redraw = self.scan_events(ui_state, &mut inner);
+                self.dispatch_scroll(ui_state, &mut inner, delta) || should_redraw
             }
             WindowEvent::CursorLeft { device_id: _ } => {
                 let mut inner = self.inner.lock().unwrap();
@@ -212,7 +256,7 @@ impl<'cx, UiState> EventRouter<'cx, UiState> {
         let button_states = &mut inner.button_states;
         // Scan for button hovering events.
         for listener in Self::listeners_iter_mut(listeners_locked) {
-            let inside = listener.bounds.contains(cursor_position);
+            let inside = listener.contains(cursor_position);
             let is_hovered_before = listener.is_hovered;
             // Scan for hovering changes.
             if inside && !listener.is_hovered {
@@ -273,6 +317,84 @@ impl<'cx, UiState> EventRouter<'cx, UiState> {
         }
         should_redraw
     }
+
+    /// Send `Dragging` events to listeners with buttons held down.
+    ///
+    /// Returns if should redraw.
+    fn scan_drags(
+        &self,
+        ui_state: &mut UiState,
+        inner: &mut EventRouterInner<'cx, UiState>,
+    ) -> bool {
+        let Some(cursor_position) = inner.cursor_position else {
+            return false;
+        };
+        let mut should_redraw = false;
+        for listener in Self::listeners_iter_mut(&mut inner.listeners) {
+            for (i, &pressed) in listener.button_states.iter().enumerate() {
+                if !pressed {
+                    continue;
+                }
+                let button = match i {
+                    0 => MouseButton::Left,
+                    1 => MouseButton::Right,
+                    2 => MouseButton::Middle,
+                    3 => MouseButton::Forward,
+                    4 => MouseButton::Back,
+                    _ => unreachable!(),
+                };
+                let event = MouseEvent::new(MouseEventKind::Dragging { button }, cursor_position);
+                listener.object.mouse_event(event, ui_state);
+                should_redraw = true;
+            }
+        }
+        should_redraw
+    }
+
+    /// Send a `Scroll` event to the listeners under the cursor, from the innermost one outwards,
+    /// until one of them consumes it.
+    ///
+    /// Listeners in a clipping view are clipped to its bounds, so the innermost listener is the
+    /// one with the smallest bounds. Of listeners with the same area, the one registered last goes
+    /// first.
+    ///
+    /// Returns if should redraw.
+    fn dispatch_scroll(
+        &self,
+        ui_state: &mut UiState,
+        inner: &mut EventRouterInner<'cx, UiState>,
+        delta: Vector2<f32>,
+    ) -> bool {
+        let Some(cursor_position) = inner.cursor_position else {
+            return false;
+        };
+        let mut candidates: Vec<(usize, f32)> = inner
+            .listeners
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(index, listener)| {
+                let listener = listener.as_ref()?;
+                let bounds = listener.bounds?;
+                bounds
+                    .contains(cursor_position)
+                    .then(|| (index, bounds.width() * bounds.height()))
+            })
+            .collect();
+        candidates.sort_by(|(_, area_0), (_, area_1)| area_0.total_cmp(area_1));
+        let mut should_redraw = false;
+        for (index, _) in candidates {
+            let Some(Some(listener)) = inner.listeners.get(index) else {
+                continue;
+            };
+            let event = MouseEvent::new(MouseEventKind::Scroll { delta }, cursor_position);
+            should_redraw = true;
+            if listener.object.scroll_event(event, ui_state) {
+                break;
+            }
+        }
+        should_redraw
+    }
 }
 
 impl<'cx, UiState> Default for EventRouter<'cx, UiState> {
@@ -282,8 +404,8 @@ impl<'cx, UiState> Default for EventRouter<'cx, UiState> {
 }
 
 struct Listener<'cx, UiState> {
-    /// The bounds of this listener.
-    bounds: Bounds<f32>,
+    /// The bounds of this listener, `None` if the listener is completely clipped away.
+    bounds: Option<Bounds<f32>>,
     /// Is the cursor currently hovering over this listener?
     is_hovered: bool,
     /// Records the buttons that the listener is currently being pressed by.
@@ -292,6 +414,12 @@ struct Listener<'cx, UiState> {
     object: Box<dyn MouseEventListener<UiState> + 'cx>,
 }
 
+impl<UiState> Listener<'_, UiState> {
+    fn contains(&self, point: Point2<f32>) -> bool {
+        self.bounds.is_some_and(|bounds| bounds.contains(point))
+    }
+}
+
 /// Unregisters the listener when dropped.
 #[derive(Clone)]
 pub struct ListenerHandle {
@@ -316,13 +444,44 @@ impl Drop for ListenerHandle {
 }
 
 impl ListenerHandle {
+    /// The bounds are clipped by the clip bounds pushed by `push_clip_bounds`, if any.
     pub fn update_bounds(&self, bounds: Bounds<f32>) {
         if let Some(router_dispatch) = self.router_dispatch.upgrade() {
+            let clipped_bounds = match router_dispatch.clip_stack.lock().unwrap().last() {
+                Some(&clip_bounds) => clip_bounds.and_then(|clip| clip.intersection(bounds)),
+                None => Some(bounds),
+            };
             router_dispatch
                 .bounds_updates
                 .lock()
                 .unwrap()
-                .insert(self.index, bounds);
+                .insert(self.index, clipped_bounds);
+        }
+    }
+
+    /// Until the matching `pop_clip_bounds`, bounds of all listeners of the same `EventRouter`
+    /// updated via `update_bounds` are clipped to `clip_bounds` (intersected with the clip bounds
+    /// pushed before).
+    ///
+    /// This is for views that only show part of their subviews (e.g. `ScrollView`), so that the
+    /// invisible parts of the subviews cannot receive events. Such views would push their visible
+    /// bounds before `apply_bounds` of their subviews, and pop them after.
+    pub fn push_clip_bounds(&self, clip_bounds: Bounds<f32>) {
+        if let Some(router_dispatch) = self.router_dispatch.upgrade() {
+            let mut clip_stack = router_dispatch.clip_stack.lock().unwrap();
+            let clipped = match clip_stack.last() {
+                Some(&last) => last.and_then(|last| last.intersection(clip_bounds)),
+                None => Some(clip_bounds),
+            };
+            clip_stack.push(clipped);
+        }
+    }
+
+    /// See `push_clip_bounds`.
+    pub fn pop_clip_bounds(&self) {
+        if let Some(router_dispatch) = self.router_dispatch.upgrade() {
+            let popped = router_dispatch.clip_stack.lock().unwrap().pop();
+            debug_assert!(popped.is_some(), "unbalanced `pop_clip_bounds`");
         }
     }
 }
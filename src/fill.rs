@@ -0,0 +1,156 @@
+use cgmath::*;
+
+use crate::{Rgba, Srgb, Srgba};
+
+/// Max number of stops in a gradient.
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+/// How the inside of a rect is painted.
+///
+/// Colors are given in sRGB, but gradients are interpolated in linear color space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    Solid(Srgba),
+    LinearGradient(LinearGradient),
+    RadialGradient(RadialGradient),
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Self::Solid(Srgba::from_hex(0xFFFFFFFF))
+    }
+}
+
+impl From<Srgba> for Fill {
+    fn from(color: Srgba) -> Self {
+        Self::Solid(color)
+    }
+}
+
+impl From<Srgb> for Fill {
+    fn from(color: Srgb) -> Self {
+        Self::Solid(color.into())
+    }
+}
+
+impl From<Rgba> for Fill {
+    fn from(color: Rgba) -> Self {
+        Self::Solid(color.into())
+    }
+}
+
+impl From<LinearGradient> for Fill {
+    fn from(gradient: LinearGradient) -> Self {
+        Self::LinearGradient(gradient)
+    }
+}
+
+impl From<RadialGradient> for Fill {
+    fn from(gradient: RadialGradient) -> Self {
+        Self::RadialGradient(gradient)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// Position of the stop along the gradient, from 0 to 1.
+    pub offset: f32,
+    pub color: Srgba,
+}
+
+impl GradientStop {
+    pub const fn new(offset: f32, color: Srgba) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// Up to `MAX_GRADIENT_STOPS` stops of a gradient, in the order of increasing offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStops {
+    stops: [GradientStop; MAX_GRADIENT_STOPS],
+    len: usize,
+}
+
+impl GradientStops {
+    /// # Panics
+    ///
+    /// - if `stops` is empty or has more than `MAX_GRADIENT_STOPS` stops
+    /// - if the offsets of `stops` are not in increasing order
+    pub const fn new(stops: &[GradientStop]) -> Self {
+        assert!(!stops.is_empty(), "gradient has no stops");
+        assert!(
+            stops.len() <= MAX_GRADIENT_STOPS,
+            "gradient has too many stops"
+        );
+        let mut stops_ = [stops[0]; MAX_GRADIENT_STOPS];
+        let mut i = 1;
+        while i < stops.len() {
+            assert!(
+                stops[i - 1].offset <= stops[i].offset,
+                "gradient stops are not in increasing order of offsets"
+            );
+            stops_[i] = stops[i];
+            i += 1;
+        }
+        Self {
+            stops: stops_,
+            len: stops.len(),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[GradientStop] {
+        &self.stops[..self.len]
+    }
+}
+
+/// A gradient along a line through the center of the rect.
+///
+/// Like in CSS, the line is long enough for the stops at 0 and 1 to be at the corners of the rect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearGradient {
+    /// Direction of the gradient, in radians, clockwise from left-to-right.
+    ///
+    /// E.g. an angle of pi/2 goes from top to bottom.
+    pub angle: f32,
+    pub stops: GradientStops,
+}
+
+impl LinearGradient {
+    pub const fn new(angle: f32, stops: &[GradientStop]) -> Self {
+        Self {
+            angle,
+            stops: GradientStops::new(stops),
+        }
+    }
+}
+
+/// A gradient in circles around a center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadialGradient {
+    /// Relative to the bounds of the rect, `(0, 0)` being the top left corner and `(1, 1)` being
+    /// the bottom right corner.
+    pub center: Point2<f32>,
+    /// Radius of the stop at 1, relative to the distance from the center to the farthest corner of
+    /// the rect.
+    pub radius: f32,
+    pub stops: GradientStops,
+}
+
+impl RadialGradient {
+    /// Gradient centered in the rect that ends at the farthest corners.
+    pub const fn new(stops: &[GradientStop]) -> Self {
+        Self {
+            center: point2(0.5, 0.5),
+            radius: 1.,
+            stops: GradientStops::new(stops),
+        }
+    }
+
+    pub const fn with_center(self, center: Point2<f32>) -> Self {
+        Self { center, ..self }
+    }
+
+    pub const fn with_radius(self, radius: f32) -> Self {
+        Self { radius, ..self }
+    }
+}
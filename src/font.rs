@@ -17,12 +17,64 @@ pub struct FontMetaJson {
     pub glyphs_per_line: u32,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Font<'cx> {
+/// The part of a font needed for measuring texts, without the glyph atlas.
+///
+/// Unlike `Font`, this can be obtained without loading the atlas image, so texts can be measured
+/// and laid out without a GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontMetrics {
     present_start: u8,
     present_end: u8,
-    glyphs_per_line: u32,
     glyph_size: RectSize<u32>,
+}
+
+impl FontMetrics {
+    pub const fn new(glyph_size: RectSize<u32>, present_range: Range<u8>) -> Self {
+        Self {
+            present_start: present_range.start,
+            present_end: present_range.end,
+            glyph_size,
+        }
+    }
+
+    pub fn from_meta_json(font_meta: &FontMetaJson) -> Self {
+        Self::new(
+            RectSize::new(font_meta.glyph_width, font_meta.glyph_height),
+            font_meta.present_start..font_meta.present_end,
+        )
+    }
+
+    /// Load only the font meta JSON, without the atlas image.
+    pub fn load_from_resources(
+        resources: &AppResources,
+        json_subpath: impl AsRef<Path>,
+    ) -> Result<Self, LoadResourceError> {
+        let font_meta = resources.load_json_object::<FontMetaJson>(json_subpath)?;
+        Ok(Self::from_meta_json(&font_meta))
+    }
+
+    pub fn present_range(&self) -> Range<u8> {
+        self.present_start..self.present_end
+    }
+
+    pub fn has_glyph(&self, char: char) -> bool {
+        self.present_range().contains(&(char as u8))
+    }
+
+    /// Glyph width if glyph height is 1.
+    pub fn glyph_relative_width(&self) -> f32 {
+        (self.glyph_size.width as f32) / (self.glyph_size.height as f32)
+    }
+
+    pub fn glyph_size(&self) -> RectSize<u32> {
+        self.glyph_size
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Font<'cx> {
+    metrics: FontMetrics,
+    glyphs_per_line: u32,
     glyph_size_uv: RectSize<f32>,
     atlas_image: ImageRef<'cx>,
 }
@@ -37,10 +89,8 @@ impl<'cx> Font<'cx> {
         let atlas_image_subpath = resources.solve_relative_subpath(json_subpath, &font_meta.path);
         let atlas_image = resources.load_image(&atlas_image_subpath)?;
         Ok(Self {
-            present_start: font_meta.present_start,
-            present_end: font_meta.present_end,
+            metrics: FontMetrics::from_meta_json(&font_meta),
             glyphs_per_line: font_meta.glyphs_per_line,
-            glyph_size: RectSize::new(font_meta.glyph_width, font_meta.glyph_height),
             glyph_size_uv: RectSize::new(
                 font_meta.glyph_width as f32 / atlas_image.width_f(),
                 font_meta.glyph_height as f32 / atlas_image.height_f(),
@@ -49,23 +99,27 @@ impl<'cx> Font<'cx> {
         })
     }
 
+    pub fn metrics(&self) -> FontMetrics {
+        self.metrics
+    }
+
     pub fn atlas_image(&self) -> ImageRef<'cx> {
         self.atlas_image
     }
 
     pub fn present_range(&self) -> Range<u8> {
-        self.present_start..self.present_end
+        self.metrics.present_range()
     }
 
     pub fn has_glyph(&self, char: char) -> bool {
-        self.present_range().contains(&(char as u8))
+        self.metrics.has_glyph(char)
     }
 
     fn uv_position_for_glyph(&self, char: char) -> Option<Point2<f32>> {
         if !self.has_glyph(char) {
             return None;
         }
-        let i_glyph = ((char as u8) - self.present_start) as u32;
+        let i_glyph = ((char as u8) - self.metrics.present_start) as u32;
         let glyph_coord = point2(
             (i_glyph % self.glyphs_per_line) as f32 * self.glyph_size_uv.width,
             (i_glyph / self.glyphs_per_line) as f32 * self.glyph_size_uv.height,
@@ -80,11 +134,11 @@ impl<'cx> Font<'cx> {
 
     /// Glyph width if glyph height is 1.
     pub fn glyph_relative_width(&self) -> f32 {
-        (self.glyph_size.width as f32) / (self.glyph_size.height as f32)
+        self.metrics.glyph_relative_width()
     }
 
     pub fn glyph_size(&self) -> RectSize<u32> {
-        self.glyph_size
+        self.metrics.glyph_size()
     }
 
     pub fn glyph_size_uv(&self) -> RectSize<f32> {
@@ -0,0 +1,190 @@
+use bumpalo::{Bump, collections::Vec as BumpVec};
+use cgmath::*;
+
+use crate::{
+    Bounds, CanvasRef, Constraints, LayoutDebugKind, LayoutDebugOverlay, RectSize, RenderPass,
+    UiContext, View,
+};
+
+/// A point on a view, as fractions of the view's size.
+///
+/// `(0.0, 0.0)` is the top-left corner, `(1.0, 1.0)` is the bottom-right corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Anchor {
+    pub const TOP_LEFT: Self = Self::new(0.0, 0.0);
+    pub const TOP: Self = Self::new(0.5, 0.0);
+    pub const TOP_RIGHT: Self = Self::new(1.0, 0.0);
+    pub const LEFT: Self = Self::new(0.0, 0.5);
+    pub const CENTER: Self = Self::new(0.5, 0.5);
+    pub const RIGHT: Self = Self::new(1.0, 0.5);
+    pub const BOTTOM_LEFT: Self = Self::new(0.0, 1.0);
+    pub const BOTTOM: Self = Self::new(0.5, 1.0);
+    pub const BOTTOM_RIGHT: Self = Self::new(1.0, 1.0);
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Offset of the anchor point from the origin of something of `size`.
+    pub fn offset_in(self, size: RectSize<f32>) -> Vector2<f32> {
+        vec2(self.x * size.width, self.y * size.height)
+    }
+}
+
+/// Size of a subview of `Anchored`.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum AnchoredSize {
+    /// The size the subview would take if it is given the size of the parent.
+    #[default]
+    Fit,
+    Fixed(RectSize<f32>),
+    /// As ratios of the size of the parent.
+    RelativeToParent { width: f32, height: f32 },
+}
+
+/// How a subview of `Anchored` is positioned and sized.
+///
+/// The subview is placed such that its `anchor` lands on the `parent_anchor` of the parent, and
+/// then moved by `offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnchoredPlacement {
+    pub parent_anchor: Anchor,
+    pub anchor: Anchor,
+    pub offset: Vector2<f32>,
+    pub size: AnchoredSize,
+}
+
+impl AnchoredPlacement {
+    pub const fn new(parent_anchor: Anchor, anchor: Anchor) -> Self {
+        Self {
+            parent_anchor,
+            anchor,
+            offset: vec2(0., 0.),
+            size: AnchoredSize::Fit,
+        }
+    }
+
+    /// Pin the subview to `anchor` of the parent with the same anchor of the subview itself, e.g.
+    /// `Anchor::BOTTOM_RIGHT` pins the subview to the bottom-right corner.
+    pub const fn pinned(anchor: Anchor) -> Self {
+        Self::new(anchor, anchor)
+    }
+
+    pub const fn with_offset(self, offset: Vector2<f32>) -> Self {
+        Self { offset, ..self }
+    }
+
+    pub const fn with_size(self, size: AnchoredSize) -> Self {
+        Self { size, ..self }
+    }
+
+    fn bounds_in(self, parent_bounds: Bounds<f32>, size: RectSize<f32>) -> Bounds<f32> {
+        let origin = parent_bounds.origin + self.parent_anchor.offset_in(parent_bounds.size)
+            - self.anchor.offset_in(size)
+            + self.offset;
+        Bounds::new(origin, size)
+    }
+}
+
+pub(crate) struct AnchoredSubview<'view, 'cx> {
+    placement: AnchoredPlacement,
+    view: &'view mut (dyn View<'cx> + 'view),
+}
+
+/// Positions each subview relative to anchor points of itself, regardless of the other subviews.
+/// Subviews may go outside of the bounds of the layout.
+///
+/// Like a spreading view, `Anchored` takes all the space it is given. Subviews added first are
+/// drawn at the back.
+pub struct Anchored<'pass, 'views, 'cx> {
+    subviews: BumpVec<'pass, AnchoredSubview<'views, 'cx>>,
+    bounds: Bounds<f32>,
+}
+
+impl<'pass, 'views, 'cx> Anchored<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump) -> Self {
+        Self {
+            subviews: BumpVec::new_in(bump),
+            bounds: Bounds::default(),
+        }
+    }
+
+    pub(crate) fn subview(
+        &mut self,
+        subview: &'views mut (dyn View<'cx> + 'views),
+        placement: AnchoredPlacement,
+    ) {
+        self.subviews.push(AnchoredSubview {
+            placement,
+            view: subview,
+        });
+    }
+}
+
+impl<'pass, 'views, 'cx> View<'cx> for Anchored<'pass, 'views, 'cx> {
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        RectSize::new(f32::INFINITY, f32::INFINITY)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subviews.iter().any(|subview| subview.view.is_dirty())
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        for subview in &mut self.subviews {
+            let size = match subview.placement.size {
+                AnchoredSize::Fit => subview.view.size_that_fits(Constraints::loose(bounds.size)),
+                AnchoredSize::Fixed(size) => size,
+                AnchoredSize::RelativeToParent { width, height } => {
+                    bounds.size.scaled(width, height)
+                }
+            };
+            let subview_bounds = subview.placement.bounds_in(bounds, size);
+            LayoutDebugOverlay::nested(|| subview.view.apply_bounds(subview_bounds));
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        for subview in &mut self.subviews {
+            subview.view.prepare_for_drawing(ui_context, canvas);
+        }
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        for subview in &self.subviews {
+            subview.view.draw(ui_context, render_pass);
+        }
+    }
+}
+
+pub struct AnchoredBuilder<'pass, 'views, 'cx> {
+    anchored: Anchored<'pass, 'views, 'cx>,
+}
+
+impl<'pass, 'views, 'cx> AnchoredBuilder<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump) -> Self {
+        Self {
+            anchored: Anchored::new(bump),
+        }
+    }
+
+    /// Add a subview positioned and sized by `placement`, on top of the subviews added before.
+    pub fn subview(
+        &mut self,
+        subview: &'views mut (dyn View<'cx> + 'views),
+        placement: AnchoredPlacement,
+    ) {
+        self.anchored.subview(subview, placement);
+    }
+
+    pub(crate) fn finish(self) -> Anchored<'pass, 'views, 'cx> {
+        self.anchored
+    }
+}
@@ -0,0 +1,128 @@
+use cgmath::*;
+
+use crate::{
+    Bounds, CanvasRef, Constraints, LayoutDebugKind, LayoutDebugOverlay, RectSize, RenderPass,
+    UiContext, View,
+};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AspectRatioMode {
+    /// The subview is as large as possible while staying inside the bounds.
+    #[default]
+    Fit,
+    /// The subview is as small as possible while covering the whole bounds.
+    ///
+    /// The parts of the subview that go outside of the bounds are not clipped.
+    Fill,
+}
+
+/// Keeps the subview at a fixed width/height ratio under whatever bounds it is given.
+///
+/// The subview is positioned inside the bounds by `spread_ratio_{horizontal|vertical}`, in the
+/// same way as `Container` with `ContainerPadding::Spread` on both edges.
+pub struct AspectRatio<'view, Subview> {
+    ratio: f32,
+    mode: AspectRatioMode,
+    spread_ratio_horizontal: f32,
+    spread_ratio_vertical: f32,
+    subview: &'view mut Subview,
+    bounds: Bounds<f32>,
+}
+
+impl<'view, Subview> AspectRatio<'view, Subview> {
+    pub(crate) fn new(ratio: f32, subview: &'view mut Subview) -> Self {
+        assert!(ratio > 0., "aspect ratio must be positive, got {ratio}");
+        Self {
+            ratio,
+            mode: AspectRatioMode::Fit,
+            spread_ratio_horizontal: 0.5,
+            spread_ratio_vertical: 0.5,
+            subview,
+            bounds: Bounds::default(),
+        }
+    }
+
+    /// Width divided by height.
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    pub fn set_mode(&mut self, mode: AspectRatioMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn set_spread_ratio_horizontal(&mut self, spread_ratio_horizontal: f32) -> &mut Self {
+        self.spread_ratio_horizontal = spread_ratio_horizontal;
+        self
+    }
+
+    pub fn set_spread_ratio_vertical(&mut self, spread_ratio_vertical: f32) -> &mut Self {
+        self.spread_ratio_vertical = spread_ratio_vertical;
+        self
+    }
+
+    /// The largest size of the ratio inside `size`.
+    ///
+    /// Infinite if `size` is infinite on both axes.
+    fn fit_in(&self, size: RectSize<f32>) -> RectSize<f32> {
+        let width = size.width.min(size.height * self.ratio);
+        RectSize::new(width, width / self.ratio)
+    }
+
+    /// The smallest size of the ratio that covers `size`.
+    ///
+    /// Falls back to `fit_in` if `size` is infinite on either axis.
+    fn fill_in(&self, size: RectSize<f32>) -> RectSize<f32> {
+        if !(size.width.is_finite() && size.height.is_finite()) {
+            return self.fit_in(size);
+        }
+        let width = size.width.max(size.height * self.ratio);
+        RectSize::new(width, width / self.ratio)
+    }
+}
+
+impl<'view, 'cx, Subview> View<'cx> for AspectRatio<'view, Subview>
+where
+    Subview: View<'cx>,
+{
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        let subview_size = self.subview.preferred_size();
+        self.fit_in(subview_size)
+    }
+
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        let subview_size = self.subview.preferred_size();
+        constraints.constrain(self.fit_in(subview_size.min(constraints.max)))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subview.is_dirty()
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        let subview_size = match self.mode {
+            AspectRatioMode::Fit => self.fit_in(bounds.size),
+            AspectRatioMode::Fill => self.fill_in(bounds.size),
+        };
+        // Negative in `Fill` mode, so the overflowing parts are also distributed by the spread
+        // ratios.
+        let leftover = bounds.size.as_vec() - subview_size.as_vec();
+        let offset = vec2(
+            self.spread_ratio_horizontal * leftover.x,
+            self.spread_ratio_vertical * leftover.y,
+        );
+        let subview_bounds = Bounds::new(bounds.origin + offset, subview_size);
+        LayoutDebugOverlay::nested(|| self.subview.apply_bounds(subview_bounds));
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        self.subview.prepare_for_drawing(ui_context, canvas);
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        self.subview.draw(ui_context, render_pass);
+    }
+}
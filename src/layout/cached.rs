@@ -0,0 +1,155 @@
+use crate::{Bounds, CanvasRef, Constraints, RectSize, RenderPass, UiContext, View};
+
+/// The persistent part of a `Cached`, which keeps the measurements and the bounds of a subtree
+/// across frames.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutCache {
+    invalidated: bool,
+    preferred_size: Option<RectSize<f32>>,
+    /// Results of `size_that_fits` since the subtree was last dirty.
+    measurements: Vec<(Constraints, RectSize<f32>)>,
+    bounds: Option<Bounds<f32>>,
+    /// Physical and logical size of the canvas the subtree was last prepared for, since views
+    /// may prepare differently for another scale factor.
+    canvas_size: Option<(RectSize<u32>, RectSize<f32>)>,
+}
+
+impl LayoutCache {
+    /// Max number of `size_that_fits` results kept.
+    const MAX_MEASUREMENTS: usize = 8;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Force the subtree to be re-measured, re-laid out and re-prepared in the next frame.
+    ///
+    /// This is needed when the structure or parameters of the layouts in the subtree (e.g.
+    /// paddings of a `Stack`) change, which views cannot detect themselves.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    fn clear(&mut self) {
+        self.invalidated = false;
+        self.preferred_size = None;
+        self.measurements.clear();
+        self.bounds = None;
+        self.canvas_size = None;
+    }
+
+    fn measurement(&self, constraints: Constraints) -> Option<RectSize<f32>> {
+        self.measurements
+            .iter()
+            .find(|&&(constraints_, _)| constraints_ == constraints)
+            .map(|&(_, size)| size)
+    }
+
+    fn insert_measurement(&mut self, constraints: Constraints, size: RectSize<f32>) {
+        if self.measurements.len() >= Self::MAX_MEASUREMENTS {
+            self.measurements.clear();
+        }
+        self.measurements.push((constraints, size));
+    }
+}
+
+/// Retains the measurements of its subview across frames.
+///
+/// If the subtree is not dirty (see `View::is_dirty`), measuring reuses the sizes from previous
+/// frames, and if it is also given the same bounds as last time, stays clean after they are
+/// applied, and is drawn onto a canvas of the same size and scale, preparing for drawing is
+/// skipped. Otherwise the subtree is re-measured and re-prepared as normal.
+///
+/// The bounds are still applied to the subtree in every frame, because the layouts in it are
+/// rebuilt in every `LayoutPass` and only know their bounds from `apply_bounds`. Views only mark
+/// themselves as needing an update in `apply_bounds` when their bounds change, so this leaves an
+/// unchanged subtree clean.
+///
+/// Layouts themselves are never dirty, so `LayoutCache::invalidate` has to be called when the
+/// layouts in the subtree are changed.
+pub struct Cached<'view, Subview> {
+    cache: &'view mut LayoutCache,
+    subview: &'view mut Subview,
+    dirty: bool,
+    /// Whether `prepare_for_drawing` needs to be called on the subview in this frame.
+    needs_prepare: bool,
+}
+
+impl<'view, Subview> Cached<'view, Subview> {
+    pub(crate) fn new<'cx>(cache: &'view mut LayoutCache, subview: &'view mut Subview) -> Self
+    where
+        Subview: View<'cx>,
+    {
+        let dirty = cache.invalidated || subview.is_dirty();
+        if dirty {
+            cache.clear();
+        }
+        Self {
+            cache,
+            subview,
+            dirty,
+            needs_prepare: dirty,
+        }
+    }
+}
+
+impl<'view, 'cx, Subview> View<'cx> for Cached<'view, Subview>
+where
+    Subview: View<'cx>,
+{
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        match self.cache.preferred_size {
+            Some(preferred_size) => preferred_size,
+            None => {
+                let preferred_size = self.subview.preferred_size();
+                self.cache.preferred_size = Some(preferred_size);
+                preferred_size
+            }
+        }
+    }
+
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        match self.cache.measurement(constraints) {
+            Some(size) => size,
+            None => {
+                let size = self.subview.size_that_fits(constraints);
+                self.cache.insert_measurement(constraints, size);
+                size
+            }
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        if self.cache.bounds != Some(bounds) {
+            self.cache.bounds = Some(bounds);
+            self.needs_prepare = true;
+        }
+        self.subview.apply_bounds(bounds);
+        if self.subview.is_dirty() {
+            self.needs_prepare = true;
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        let physical_size = canvas.color_texture_view.texture().size();
+        let canvas_size = (
+            RectSize::new(physical_size.width, physical_size.height),
+            canvas.logical_size,
+        );
+        if self.cache.canvas_size != Some(canvas_size) {
+            self.cache.canvas_size = Some(canvas_size);
+            self.needs_prepare = true;
+        }
+        if self.needs_prepare {
+            self.subview.prepare_for_drawing(ui_context, canvas);
+        }
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        self.subview.draw(ui_context, render_pass);
+    }
+}
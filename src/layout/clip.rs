@@ -0,0 +1,79 @@
+use crate::{
+    Bounds, CanvasRef, Constraints, CornerRadius, LayoutDebugKind, LayoutDebugOverlay, RectSize,
+    RenderPass, UiContext, View,
+};
+
+/// Restricts the drawing of its subview to its own bounds.
+///
+/// The subview is laid out inside the same bounds as the `ClipView`, but anything it draws outside
+/// of them (e.g. overflowing text) is cut off.
+///
+/// With a corner radius, the subview is also masked to the rounded corners, which needs the
+/// canvas to have a stencil buffer (see `RenderPass::push_stencil_mask`).
+pub struct ClipView<'view, Subview> {
+    subview: &'view mut Subview,
+    corner_radius: Option<CornerRadius>,
+    bounds: Bounds<f32>,
+}
+
+impl<'view, Subview> ClipView<'view, Subview> {
+    pub(crate) fn new<'cx>(subview: &'view mut Subview) -> Self
+    where
+        Subview: View<'cx>,
+    {
+        Self {
+            subview,
+            corner_radius: None,
+            bounds: Bounds::default(),
+        }
+    }
+
+    pub fn set_corner_radius(
+        &mut self,
+        corner_radius: impl Into<Option<CornerRadius>>,
+    ) -> &mut Self {
+        self.corner_radius = corner_radius.into();
+        self
+    }
+}
+
+impl<'view, 'cx, Subview> View<'cx> for ClipView<'view, Subview>
+where
+    Subview: View<'cx>,
+{
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        self.subview.preferred_size()
+    }
+
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        self.subview.size_that_fits(constraints)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subview.is_dirty()
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        LayoutDebugOverlay::nested(|| {
+            LayoutDebugOverlay::clipped(bounds, || self.subview.apply_bounds(bounds));
+        });
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        self.subview.prepare_for_drawing(ui_context, canvas);
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        render_pass.push_clip_rect(self.bounds);
+        if let Some(corner_radius) = self.corner_radius {
+            render_pass.push_stencil_mask(self.bounds, corner_radius);
+        }
+        self.subview.draw(ui_context, render_pass);
+        if self.corner_radius.is_some() {
+            render_pass.pop_stencil_mask();
+        }
+        render_pass.pop_clip_rect();
+    }
+}
@@ -1,6 +1,9 @@
 use cgmath::*;
 
-use crate::{Bounds, CanvasRef, RectSize, RectView, RenderPass, UiContext, View};
+use crate::{
+    Bounds, CanvasRef, Constraints, ImageView, LayoutDebugKind, LayoutDebugOverlay, RectSize,
+    RectView, RenderPass, UiContext, View,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ContainerPadding {
@@ -51,7 +54,10 @@ pub struct Container<'view, Subview> {
     spread_ratio_vertical: f32,
     subview: &'view mut Subview,
     subview_size: RectSize<f32>,
+    bounds: Bounds<f32>,
+    subview_bounds: Bounds<f32>,
     background_rect_view: Option<&'view mut RectView>,
+    background_image_view: Option<&'view mut ImageView>,
 }
 
 impl<'view, Subview> Container<'view, Subview> {
@@ -68,7 +74,10 @@ impl<'view, Subview> Container<'view, Subview> {
             spread_ratio_vertical: 0.5,
             subview_size: subview.preferred_size(),
             subview,
+            bounds: Bounds::default(),
+            subview_bounds: Bounds::default(),
             background_rect_view: None,
+            background_image_view: None,
         }
     }
 
@@ -119,11 +128,57 @@ impl<'view, Subview> Container<'view, Subview> {
         self
     }
 
+    /// Drawn over the background rect view, e.g. a nine-slice image (see
+    /// `ImageView::set_nine_slice`) for a skinned panel.
+    pub fn set_background_image_view(
+        &mut self,
+        background_image_view: impl Into<Option<&'view mut ImageView>>,
+    ) -> &mut Self {
+        self.background_image_view = background_image_view.into();
+        self
+    }
+
     /// The preferred size of the subview.
     pub fn subview_size(&self) -> RectSize<f32> {
         self.subview_size
     }
 
+    /// The space left for the subview inside `size` after the fixed paddings.
+    fn space_for_subview(&self, size: RectSize<f32>) -> RectSize<f32> {
+        RectSize {
+            width: (size.width
+                - self.padding_left.as_fixed().unwrap_or(0.)
+                - self.padding_right.as_fixed().unwrap_or(0.)),
+            height: (size.height
+                - self.padding_top.as_fixed().unwrap_or(0.)
+                - self.padding_bottom.as_fixed().unwrap_or(0.)),
+        }
+        .max(RectSize::new(0., 0.))
+    }
+
+    /// The size of the container with the paddings around a subview of `subview_size`, if it is
+    /// given unlimited space.
+    fn outer_size(&self, subview_size: RectSize<f32>) -> RectSize<f32> {
+        let (padding_left, padding_right) = Self::padding_length(
+            self.padding_left,
+            self.padding_right,
+            self.spread_ratio_horizontal,
+            subview_size.width,
+            f32::INFINITY,
+        );
+        let (padding_top, padding_bottom) = Self::padding_length(
+            self.padding_top,
+            self.padding_bottom,
+            self.spread_ratio_vertical,
+            subview_size.height,
+            f32::INFINITY,
+        );
+        RectSize {
+            width: padding_left + subview_size.width + padding_right,
+            height: padding_top + subview_size.height + padding_bottom,
+        }
+    }
+
     fn padding_length(
         padding_leading: ContainerPadding,
         padding_trailing: ContainerPadding,
@@ -160,37 +215,30 @@ where
     Subview: View<'cx>,
 {
     fn preferred_size(&mut self) -> RectSize<f32> {
-        let (padding_left, padding_right) = Self::padding_length(
-            self.padding_left,
-            self.padding_right,
-            self.spread_ratio_horizontal,
-            self.subview_size.width,
-            f32::INFINITY,
-        );
-        let (padding_top, padding_bottom) = Self::padding_length(
-            self.padding_top,
-            self.padding_bottom,
-            self.spread_ratio_vertical,
-            self.subview_size.height,
-            f32::INFINITY,
-        );
-        RectSize {
-            width: padding_left + self.subview_size.width + padding_right,
-            height: padding_top + self.subview_size.height + padding_bottom,
-        }
+        self.outer_size(self.subview_size)
+    }
+
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        let max_size = self.space_for_subview(constraints.max);
+        let subview_size = self.subview.size_that_fits(Constraints::loose(max_size));
+        constraints.constrain(self.outer_size(subview_size))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subview.is_dirty()
+            || self
+                .background_rect_view
+                .as_ref()
+                .is_some_and(|background_rect_view| background_rect_view.is_dirty())
+            || self
+                .background_image_view
+                .as_ref()
+                .is_some_and(|background_image_view| background_image_view.is_dirty())
     }
 
     fn apply_bounds(&mut self, bounds: Bounds<f32>) {
-        let max_size = RectSize {
-            width: (bounds.width()
-                - self.padding_left.as_fixed().unwrap_or(0.)
-                - self.padding_right.as_fixed().unwrap_or(0.)),
-            height: (bounds.height()
-                - self.padding_top.as_fixed().unwrap_or(0.)
-                - self.padding_bottom.as_fixed().unwrap_or(0.)),
-        }
-        .max(RectSize::new(0., 0.));
-        let subview_size = self.subview_size.min(max_size);
+        let max_size = self.space_for_subview(bounds.size);
+        let subview_size = self.subview.size_that_fits(Constraints::loose(max_size));
         let (padding_left, padding_right) = Self::padding_length(
             self.padding_left,
             self.padding_right,
@@ -223,16 +271,28 @@ where
         if subview_bounds.y_max() > bounds.y_max() {
             subview_bounds.size.height = (bounds.y_max() - subview_bounds.y_min()).max(0.);
         }
-        if let Some(background_rect_view) = &mut self.background_rect_view {
-            background_rect_view.apply_bounds(subview_bounds);
-        }
-        self.subview.apply_bounds(subview_bounds);
+        self.bounds = bounds;
+        self.subview_bounds = subview_bounds;
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        LayoutDebugOverlay::record_padding(bounds, subview_bounds);
+        LayoutDebugOverlay::nested(|| {
+            if let Some(background_rect_view) = &mut self.background_rect_view {
+                background_rect_view.apply_bounds(subview_bounds);
+            }
+            if let Some(background_image_view) = &mut self.background_image_view {
+                background_image_view.apply_bounds(subview_bounds);
+            }
+            self.subview.apply_bounds(subview_bounds);
+        });
     }
 
     fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
         if let Some(background_rect_view) = &mut self.background_rect_view {
             background_rect_view.prepare_for_drawing(ui_context, canvas);
         }
+        if let Some(background_image_view) = &mut self.background_image_view {
+            background_image_view.prepare_for_drawing(ui_context, canvas);
+        }
         self.subview.prepare_for_drawing(ui_context, canvas);
     }
 
@@ -240,6 +300,9 @@ where
         if let Some(background_rect_view) = &self.background_rect_view {
             background_rect_view.draw(ui_context, render_pass);
         }
+        if let Some(background_image_view) = &self.background_image_view {
+            background_image_view.draw(ui_context, render_pass);
+        }
         self.subview.draw(ui_context, render_pass);
     }
 }
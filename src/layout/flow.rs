@@ -0,0 +1,277 @@
+use std::{mem, ops::Range};
+
+use bumpalo::{Bump, collections::Vec as BumpVec};
+use cgmath::*;
+
+use crate::{
+    Axis, Bounds, CanvasRef, Constraints, LayoutDebugKind, LayoutDebugOverlay, RectSize,
+    RenderPass, StackAlignment, StackAlignmentHorizontal, StackAlignmentVertical, StackPaddingType,
+    UiContext, View, axis_utils::*,
+};
+
+pub(crate) struct FlowSubview<'view, 'cx> {
+    preferred_size: RectSize<f32>,
+    /// Size of the subview under the constraints of the current layout.
+    measured_size: RectSize<f32>,
+    view: &'view mut (dyn View<'cx> + 'view),
+}
+
+/// A line of subviews in a `Flow`.
+struct FlowLine {
+    subviews: Range<usize>,
+    alpha: f32,
+    beta: f32,
+}
+
+/// Lays out subviews along an axis like a `Stack`, but starts a new line whenever the space on
+/// that axis runs out.
+pub struct Flow<'pass, 'views, 'cx> {
+    // For the lingo "a", "b", "alpha", "beta", see `axis_utils`.
+    //
+    axis: Axis,
+    alignment_alpha: StackAlignment,
+    alignment_beta: StackAlignment,
+    padding_type: StackPaddingType,
+    item_spacing: f32,
+    line_spacing: f32,
+    subviews: BumpVec<'pass, FlowSubview<'views, 'cx>>,
+    bounds: Bounds<f32>,
+}
+
+impl<'pass, 'views, 'cx> Flow<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump, axis: Axis) -> Self {
+        Self {
+            axis,
+            alignment_alpha: StackAlignment::Leading,
+            alignment_beta: StackAlignment::Leading,
+            padding_type: StackPaddingType::Interpadded,
+            item_spacing: 0.,
+            line_spacing: 0.,
+            subviews: BumpVec::new_in(bump),
+            bounds: Bounds::default(),
+        }
+    }
+
+    pub(crate) fn subview(&mut self, subview: &'views mut (dyn View<'cx> + 'views)) {
+        let preferred_size = subview.preferred_size();
+        self.subviews.push(FlowSubview {
+            preferred_size,
+            measured_size: preferred_size,
+            view: subview,
+        });
+    }
+
+    /// The padding before the first item/line and after the last item/line.
+    fn outer_padding(padding_type: StackPaddingType, spacing: f32) -> f32 {
+        match padding_type {
+            StackPaddingType::Interpadded => 0.,
+            StackPaddingType::Omnipadded => spacing,
+        }
+    }
+
+    /// Size of a subview in a line, with spreading on the other axis not counted.
+    fn subview_size(axis: Axis, measured_size: RectSize<f32>) -> RectSize<f32> {
+        let mut size = measured_size;
+        let beta = size.beta_mut(axis);
+        if !beta.is_finite() {
+            *beta = 0.;
+        }
+        size
+    }
+
+    /// Measure the subviews with the length of a line, and break them into lines that fit in
+    /// `available_alpha`. Subviews that are longer than a line (including spreading subviews)
+    /// are limited to the length of a line.
+    fn break_lines(&mut self, available_alpha: f32) -> BumpVec<'pass, FlowLine> {
+        let outer_padding = Self::outer_padding(self.padding_type, self.item_spacing);
+        let line_alpha = (available_alpha - 2. * outer_padding).max(0.);
+        let constraints =
+            Constraints::loose(RectSize::new_on_axis(self.axis, line_alpha, f32::INFINITY));
+        let mut lines = BumpVec::new_in(self.subviews.bump());
+        let mut current = FlowLine {
+            subviews: 0..0,
+            alpha: 0.,
+            beta: 0.,
+        };
+        for (i, subview) in self.subviews.iter_mut().enumerate() {
+            subview.measured_size = subview.view.size_that_fits(constraints);
+            let size = Self::subview_size(self.axis, subview.measured_size);
+            if !current.subviews.is_empty()
+                && current.alpha + self.item_spacing + size.alpha(self.axis) > line_alpha
+            {
+                let next = FlowLine {
+                    subviews: i..i,
+                    alpha: 0.,
+                    beta: 0.,
+                };
+                lines.push(mem::replace(&mut current, next));
+            }
+            let spacing = match current.subviews.is_empty() {
+                true => 0.,
+                false => self.item_spacing,
+            };
+            current.subviews.end = i + 1;
+            current.alpha += spacing + size.alpha(self.axis);
+            current.beta = current.beta.max(size.beta(self.axis));
+        }
+        if !current.subviews.is_empty() {
+            lines.push(current);
+        }
+        lines
+    }
+}
+
+impl<'pass, 'views, 'cx> View<'cx> for Flow<'pass, 'views, 'cx> {
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        // Preferably everything is in one line.
+        let n_subviews = self.subviews.len();
+        let item_padding = 2. * Self::outer_padding(self.padding_type, self.item_spacing);
+        let line_padding = 2. * Self::outer_padding(self.padding_type, self.line_spacing);
+        let alpha = self
+            .subviews
+            .iter()
+            .map(|subview| subview.preferred_size.alpha(self.axis))
+            .sum::<f32>()
+            + self.item_spacing * n_subviews.saturating_sub(1) as f32;
+        let beta = self
+            .subviews
+            .iter()
+            .map(|subview| subview.preferred_size.beta(self.axis))
+            .fold(0.0f32, f32::max);
+        RectSize::new_on_axis(self.axis, alpha + item_padding, beta + line_padding)
+    }
+
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        let lines = self.break_lines(constraints.max.alpha(self.axis));
+        let item_padding = 2. * Self::outer_padding(self.padding_type, self.item_spacing);
+        let line_padding = 2. * Self::outer_padding(self.padding_type, self.line_spacing);
+        let alpha = lines.iter().map(|line| line.alpha).fold(0.0f32, f32::max);
+        let beta = lines.iter().map(|line| line.beta).sum::<f32>()
+            + self.line_spacing * lines.len().saturating_sub(1) as f32;
+        constraints.constrain(RectSize::new_on_axis(
+            self.axis,
+            alpha + item_padding,
+            beta + line_padding,
+        ))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subviews.iter().any(|subview| subview.view.is_dirty())
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        // For the lingo "a", "b", "alpha", "beta", see `axis_utils`.
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        let lines = self.break_lines(bounds.alpha(self.axis));
+        let item_padding = Self::outer_padding(self.padding_type, self.item_spacing);
+        let line_padding = Self::outer_padding(self.padding_type, self.line_spacing);
+        let line_alpha = (bounds.alpha(self.axis) - 2. * item_padding).max(0.);
+
+        // Lines are shrunk together if there are too many of them.
+        let total_beta = lines.iter().map(|line| line.beta).sum::<f32>()
+            + self.line_spacing * lines.len().saturating_sub(1) as f32
+            + 2. * line_padding;
+        let shrink_b = (bounds.beta(self.axis) / total_beta).min(1.);
+        let leftover_beta = (bounds.beta(self.axis) - total_beta).max(0.);
+
+        let mut offset_b = self.alignment_beta.ratio() * leftover_beta + line_padding * shrink_b;
+        for line in &lines {
+            let line_beta = line.beta * shrink_b;
+            let leftover_alpha = (line_alpha - line.alpha).max(0.);
+            let mut offset_a = item_padding + self.alignment_alpha.ratio() * leftover_alpha;
+            for subview in &mut self.subviews[line.subviews.clone()] {
+                let mut subview_size = Self::subview_size(self.axis, subview.measured_size);
+                // Spreading subviews fill the line.
+                *subview_size.beta_mut(self.axis) =
+                    subview.measured_size.beta(self.axis).min(line.beta) * shrink_b;
+                let subview_leftover_beta = line_beta - subview_size.beta(self.axis);
+                let subview_offset_b =
+                    offset_b + self.alignment_beta.ratio() * subview_leftover_beta;
+                let subview_bounds = Bounds::new(
+                    bounds.origin + Vector2::new_on_axis(self.axis, offset_a, subview_offset_b),
+                    subview_size,
+                );
+                LayoutDebugOverlay::nested(|| subview.view.apply_bounds(subview_bounds));
+                offset_a += subview_size.alpha(self.axis) + self.item_spacing;
+            }
+            offset_b += line_beta + self.line_spacing * shrink_b;
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        for subview in &mut self.subviews {
+            subview.view.prepare_for_drawing(ui_context, canvas);
+        }
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        for subview in &self.subviews {
+            subview.view.draw(ui_context, render_pass);
+        }
+    }
+}
+
+pub struct FlowBuilder<'pass, 'views, 'cx> {
+    flow: Flow<'pass, 'views, 'cx>,
+}
+
+impl<'pass, 'views, 'cx> FlowBuilder<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump, axis: Axis) -> Self {
+        Self {
+            flow: Flow::new(bump, axis),
+        }
+    }
+
+    pub fn subview(&mut self, subview: &'views mut (dyn View<'cx> + 'views)) {
+        self.flow.subview(subview);
+    }
+
+    /// For a horizontal flow, this aligns the lines as a whole vertically, and aligns the subviews
+    /// inside a line if they are shorter than the line. For a vertical flow, this aligns the
+    /// subviews in each line. Vice-versa for `set_alignment_horizontal`.
+    ///
+    /// Default value: `StackAlignmentVertical::Top`.
+    pub fn set_alignment_vertical(&mut self, alignment: StackAlignmentVertical) {
+        match self.flow.axis {
+            Axis::Horizontal => self.flow.alignment_beta = alignment.into(),
+            Axis::Vertical => self.flow.alignment_alpha = alignment.into(),
+        }
+    }
+
+    /// See `set_alignment_vertical`.
+    ///
+    /// Default value: `StackAlignmentHorizontal::Left`.
+    pub fn set_alignment_horizontal(&mut self, alignment: StackAlignmentHorizontal) {
+        match self.flow.axis {
+            Axis::Horizontal => self.flow.alignment_alpha = alignment.into(),
+            Axis::Vertical => self.flow.alignment_beta = alignment.into(),
+        }
+    }
+
+    /// See `StackPaddingType`. Applies to both the spacing between subviews and the spacing
+    /// between lines.
+    ///
+    /// Default value: `StackPaddingType::Interpadded`.
+    pub fn set_padding_type(&mut self, padding_type: StackPaddingType) {
+        self.flow.padding_type = padding_type;
+    }
+
+    /// Spacing between subviews in the same line.
+    ///
+    /// Default value: `0.0`.
+    pub fn set_item_spacing(&mut self, item_spacing: f32) {
+        self.flow.item_spacing = item_spacing;
+    }
+
+    /// Spacing between lines.
+    ///
+    /// Default value: `0.0`.
+    pub fn set_line_spacing(&mut self, line_spacing: f32) {
+        self.flow.line_spacing = line_spacing;
+    }
+
+    pub(crate) fn finish(self) -> Flow<'pass, 'views, 'cx> {
+        self.flow
+    }
+}
@@ -0,0 +1,430 @@
+use std::ops::Range;
+
+use bumpalo::{Bump, collections::Vec as BumpVec};
+use cgmath::*;
+
+use crate::{
+    Axis, Bounds, CanvasRef, Constraints, LayoutDebugKind, LayoutDebugOverlay, RectSize,
+    RenderPass, StackAlignment, StackAlignmentHorizontal, StackAlignmentVertical, UiContext, View,
+    axis_utils::*,
+};
+
+/// Size definition of a row or a column in a `Grid`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridTrack {
+    /// Fixed length.
+    Fixed(f32),
+    /// Share of the space left over after the fixed and fit-to-content tracks are laid out,
+    /// proportional to the provided weight.
+    ///
+    /// A fraction track never gets shorter than its content unless the whole grid is shrunk, and
+    /// fits its content if the space is unbounded (e.g. in a `ScrollView`). Like spreading
+    /// subviews of `Stack`, a grid with fraction tracks reports an infinite preferred length on
+    /// that axis.
+    Fraction(f32),
+    /// Fit to the preferred lengths of the subviews in this track.
+    Fit,
+}
+
+pub(crate) struct GridSubview<'view, 'cx> {
+    row: usize,
+    column: usize,
+    row_span: usize,
+    column_span: usize,
+    preferred_size: RectSize<f32>,
+    /// Size of the subview under the constraints of the current layout.
+    measured_size: RectSize<f32>,
+    view: &'view mut (dyn View<'cx> + 'view),
+}
+
+impl GridSubview<'_, '_> {
+    /// The range of tracks this subview occupies on the axis.
+    fn span(&self, axis: Axis) -> Range<usize> {
+        match axis {
+            Axis::Horizontal => self.column..self.column + self.column_span,
+            Axis::Vertical => self.row..self.row + self.row_span,
+        }
+    }
+}
+
+/// Offsets (relative to the grid) and lengths of the tracks on an axis.
+struct ResolvedTracks<'pass> {
+    offsets: BumpVec<'pass, f32>,
+    lengths: BumpVec<'pass, f32>,
+}
+
+impl ResolvedTracks<'_> {
+    /// Offset and length of a span of tracks.
+    fn span(&self, span: Range<usize>) -> (f32, f32) {
+        let offset = self.offsets[span.start];
+        let end = self.offsets[span.end - 1] + self.lengths[span.end - 1];
+        (offset, end - offset)
+    }
+
+    fn total_length(&self) -> f32 {
+        match self.lengths.len() {
+            0 => 0.,
+            n => self.span(0..n).1,
+        }
+    }
+}
+
+pub struct Grid<'pass, 'views, 'cx> {
+    bump: &'pass Bump,
+    rows: BumpVec<'pass, GridTrack>,
+    columns: BumpVec<'pass, GridTrack>,
+    row_spacing: f32,
+    column_spacing: f32,
+    alignment_horizontal: StackAlignment,
+    alignment_vertical: StackAlignment,
+    subviews: BumpVec<'pass, GridSubview<'views, 'cx>>,
+    bounds: Bounds<f32>,
+}
+
+impl<'pass, 'views, 'cx> Grid<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump) -> Self {
+        Self {
+            bump,
+            rows: BumpVec::new_in(bump),
+            columns: BumpVec::new_in(bump),
+            row_spacing: 0.,
+            column_spacing: 0.,
+            alignment_horizontal: StackAlignment::Center,
+            alignment_vertical: StackAlignment::Center,
+            subviews: BumpVec::new_in(bump),
+            bounds: Bounds::default(),
+        }
+    }
+
+    pub(crate) fn subview(
+        &mut self,
+        row: usize,
+        column: usize,
+        row_span: usize,
+        column_span: usize,
+        subview: &'views mut (dyn View<'cx> + 'views),
+    ) {
+        let preferred_size = subview.preferred_size();
+        self.subviews.push(GridSubview {
+            row,
+            column,
+            row_span: row_span.max(1),
+            column_span: column_span.max(1),
+            preferred_size,
+            measured_size: preferred_size,
+            view: subview,
+        });
+    }
+
+    /// Tracks that are referenced by subviews but not defined are added as `GridTrack::Fit`.
+    fn fill_implicit_tracks(&mut self) {
+        for subview in &self.subviews {
+            let n_rows = subview.row + subview.row_span;
+            let n_columns = subview.column + subview.column_span;
+            if self.rows.len() < n_rows {
+                self.rows.resize(n_rows, GridTrack::Fit);
+            }
+            if self.columns.len() < n_columns {
+                self.columns.resize(n_columns, GridTrack::Fit);
+            }
+        }
+    }
+
+    fn tracks(&self, axis: Axis) -> &[GridTrack] {
+        match axis {
+            Axis::Horizontal => &self.columns,
+            Axis::Vertical => &self.rows,
+        }
+    }
+
+    fn spacing(&self, axis: Axis) -> f32 {
+        match axis {
+            Axis::Horizontal => self.column_spacing,
+            Axis::Vertical => self.row_spacing,
+        }
+    }
+
+    fn total_spacing(&self, axis: Axis) -> f32 {
+        self.spacing(axis) * self.tracks(axis).len().saturating_sub(1) as f32
+    }
+
+    /// Lengths of the tracks on an axis if every track is given just enough space for its
+    /// content.
+    fn content_lengths(&self, axis: Axis) -> BumpVec<'pass, f32> {
+        // For the lingo "a", "b", "alpha", "beta", see `axis_utils`.
+        let tracks = self.tracks(axis);
+        let mut lengths = BumpVec::from_iter_in(
+            tracks.iter().map(|&track| match track {
+                GridTrack::Fixed(length) => length,
+                GridTrack::Fraction(_) | GridTrack::Fit => 0.,
+            }),
+            self.bump,
+        );
+        let finite_alpha = |subview: &GridSubview| {
+            let alpha = subview.measured_size.alpha(axis);
+            if alpha.is_finite() { alpha } else { 0. }
+        };
+        // Subviews that occupy one track decide the length of that track directly.
+        for subview in self.subviews.iter().filter(|s| s.span(axis).len() == 1) {
+            let i = subview.span(axis).start;
+            if !matches!(tracks[i], GridTrack::Fixed(_)) {
+                lengths[i] = lengths[i].max(finite_alpha(subview));
+            }
+        }
+        // Subviews spanning multiple tracks distribute whatever they still lack equally among
+        // the non-fixed tracks they span.
+        for subview in self.subviews.iter().filter(|s| s.span(axis).len() > 1) {
+            let span = subview.span(axis);
+            let occupied = lengths[span.clone()].iter().sum::<f32>()
+                + self.spacing(axis) * (span.len() - 1) as f32;
+            let lacking = finite_alpha(subview) - occupied;
+            let n_flexible = tracks[span.clone()]
+                .iter()
+                .filter(|track| !matches!(track, GridTrack::Fixed(_)))
+                .count();
+            if lacking <= 0. || n_flexible == 0 {
+                continue;
+            }
+            for i in span {
+                if !matches!(tracks[i], GridTrack::Fixed(_)) {
+                    lengths[i] += lacking / n_flexible as f32;
+                }
+            }
+        }
+        lengths
+    }
+
+    fn resolve_tracks(&self, axis: Axis, available: f32) -> ResolvedTracks<'pass> {
+        let tracks = self.tracks(axis);
+        let mut lengths = self.content_lengths(axis);
+        let min_length = lengths.iter().sum::<f32>() + self.total_spacing(axis);
+        let shrink = if min_length > 0. {
+            (available / min_length).min(1.)
+        } else {
+            1.
+        };
+        if shrink < 1. {
+            for length in &mut lengths {
+                *length *= shrink;
+            }
+        } else if available.is_finite() {
+            // Distribute the leftover space among fraction tracks by their weights, without
+            // letting any fraction track go below its content length.
+            let mut frozen = BumpVec::from_iter_in(
+                tracks.iter().map(|&track| match track {
+                    GridTrack::Fraction(weight) => weight <= 0.,
+                    GridTrack::Fixed(_) | GridTrack::Fit => true,
+                }),
+                self.bump,
+            );
+            loop {
+                let mut space = available - self.total_spacing(axis);
+                let mut weight_sum = 0.0f32;
+                for (i, &track) in tracks.iter().enumerate() {
+                    match track {
+                        GridTrack::Fraction(weight) if !frozen[i] => weight_sum += weight,
+                        _ => space -= lengths[i],
+                    }
+                }
+                if weight_sum <= 0. {
+                    break;
+                }
+                let mut froze_any = false;
+                for (i, &track) in tracks.iter().enumerate() {
+                    if let GridTrack::Fraction(weight) = track
+                        && !frozen[i]
+                        && space * weight / weight_sum < lengths[i]
+                    {
+                        frozen[i] = true;
+                        froze_any = true;
+                    }
+                }
+                if froze_any {
+                    continue;
+                }
+                for (i, &track) in tracks.iter().enumerate() {
+                    if let GridTrack::Fraction(weight) = track
+                        && !frozen[i]
+                    {
+                        lengths[i] = space * weight / weight_sum;
+                    }
+                }
+                break;
+            }
+        }
+        let spacing = self.spacing(axis) * shrink;
+        let mut offsets = BumpVec::with_capacity_in(lengths.len(), self.bump);
+        let mut offset = 0.0f32;
+        for &length in &lengths {
+            offsets.push(offset);
+            offset += length + spacing;
+        }
+        ResolvedTracks { offsets, lengths }
+    }
+
+    /// Measure the subviews for `available` space and resolve the columns and the rows.
+    ///
+    /// Subviews are measured again with the widths of the columns they span before the rows are
+    /// resolved, since their heights may depend on it (e.g. wrapping texts).
+    fn resolve(
+        &mut self,
+        available: RectSize<f32>,
+    ) -> (ResolvedTracks<'pass>, ResolvedTracks<'pass>) {
+        for subview in &mut self.subviews {
+            subview.measured_size = subview.view.size_that_fits(Constraints::loose(available));
+        }
+        let columns = self.resolve_tracks(Axis::Horizontal, available.width);
+        for subview in &mut self.subviews {
+            let (_, width) = columns.span(subview.span(Axis::Horizontal));
+            let constraints = Constraints::loose(RectSize::new(width, available.height));
+            subview.measured_size = subview.view.size_that_fits(constraints);
+        }
+        let rows = self.resolve_tracks(Axis::Vertical, available.height);
+        (columns, rows)
+    }
+
+    fn preferred_length(&self, axis: Axis) -> f32 {
+        if self
+            .tracks(axis)
+            .iter()
+            .any(|track| matches!(track, GridTrack::Fraction(_)))
+        {
+            return f32::INFINITY;
+        }
+        self.content_lengths(axis).iter().sum::<f32>() + self.total_spacing(axis)
+    }
+}
+
+impl<'pass, 'views, 'cx> View<'cx> for Grid<'pass, 'views, 'cx> {
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        for subview in &mut self.subviews {
+            subview.measured_size = subview.preferred_size;
+        }
+        RectSize::new(
+            self.preferred_length(Axis::Horizontal),
+            self.preferred_length(Axis::Vertical),
+        )
+    }
+
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        let (columns, rows) = self.resolve(constraints.max);
+        constraints.constrain(RectSize::new(columns.total_length(), rows.total_length()))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subviews.iter().any(|subview| subview.view.is_dirty())
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        let (columns, rows) = self.resolve(bounds.size);
+        let alignment_ratios = vec2(
+            self.alignment_horizontal.ratio(),
+            self.alignment_vertical.ratio(),
+        );
+        for subview in &mut self.subviews {
+            let (x_offset, width) = columns.span(subview.span(Axis::Horizontal));
+            let (y_offset, height) = rows.span(subview.span(Axis::Vertical));
+            let cell_origin = bounds.origin + vec2(x_offset, y_offset);
+            let cell_size = RectSize::new(width, height);
+            // Spreading subviews fill the cell.
+            let subview_size = subview.view.size_that_fits(Constraints::loose(cell_size));
+            let leftover = cell_size.as_vec() - subview_size.as_vec();
+            let subview_bounds = Bounds::new(
+                cell_origin + leftover.mul_element_wise(alignment_ratios),
+                subview_size,
+            );
+            LayoutDebugOverlay::nested(|| subview.view.apply_bounds(subview_bounds));
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        for subview in &mut self.subviews {
+            subview.view.prepare_for_drawing(ui_context, canvas);
+        }
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        for subview in &self.subviews {
+            subview.view.draw(ui_context, render_pass);
+        }
+    }
+}
+
+pub struct GridBuilder<'pass, 'views, 'cx> {
+    grid: Grid<'pass, 'views, 'cx>,
+}
+
+impl<'pass, 'views, 'cx> GridBuilder<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump) -> Self {
+        Self {
+            grid: Grid::new(bump),
+        }
+    }
+
+    /// Place a subview in the cell at `row` and `column`.
+    pub fn subview(
+        &mut self,
+        row: usize,
+        column: usize,
+        subview: &'views mut (dyn View<'cx> + 'views),
+    ) {
+        self.grid.subview(row, column, 1, 1, subview);
+    }
+
+    /// Place a subview that spans `row_span` rows and `column_span` columns, starting from the
+    /// cell at `row` and `column`.
+    pub fn subview_spanning(
+        &mut self,
+        row: usize,
+        column: usize,
+        row_span: usize,
+        column_span: usize,
+        subview: &'views mut (dyn View<'cx> + 'views),
+    ) {
+        self.grid
+            .subview(row, column, row_span, column_span, subview);
+    }
+
+    /// Rows that are not defined but are occupied by subviews would be `GridTrack::Fit`.
+    pub fn set_rows(&mut self, rows: impl IntoIterator<Item = GridTrack>) {
+        self.grid.rows.clear();
+        self.grid.rows.extend(rows);
+    }
+
+    /// Columns that are not defined but are occupied by subviews would be `GridTrack::Fit`.
+    pub fn set_columns(&mut self, columns: impl IntoIterator<Item = GridTrack>) {
+        self.grid.columns.clear();
+        self.grid.columns.extend(columns);
+    }
+
+    /// Default value: `0.0`.
+    pub fn set_row_spacing(&mut self, row_spacing: f32) {
+        self.grid.row_spacing = row_spacing;
+    }
+
+    /// Default value: `0.0`.
+    pub fn set_column_spacing(&mut self, column_spacing: f32) {
+        self.grid.column_spacing = column_spacing;
+    }
+
+    /// Alignment of subviews inside their cells, if they are smaller than the cells.
+    ///
+    /// Default value: `StackAlignmentHorizontal::Center`.
+    pub fn set_alignment_horizontal(&mut self, alignment: StackAlignmentHorizontal) {
+        self.grid.alignment_horizontal = alignment.into();
+    }
+
+    /// Alignment of subviews inside their cells, if they are smaller than the cells.
+    ///
+    /// Default value: `StackAlignmentVertical::Center`.
+    pub fn set_alignment_vertical(&mut self, alignment: StackAlignmentVertical) {
+        self.grid.alignment_vertical = alignment.into();
+    }
+
+    pub(crate) fn finish(mut self) -> Grid<'pass, 'views, 'cx> {
+        self.grid.fill_implicit_tracks();
+        self.grid
+    }
+}
@@ -0,0 +1,342 @@
+use std::cell::Cell;
+
+use cgmath::*;
+
+use crate::{
+    Bounds, CanvasRef, EventRouter, LayoutDebugKind, LayoutDebugOverlay, RectSize, RenderPass,
+    ScrollState, ScrollView, UiContext, View,
+};
+
+/// Provides the rows of a `ListView`.
+pub trait ListDataSource {
+    type RowView;
+
+    fn row_count(&self) -> usize;
+
+    /// Height of every row.
+    fn row_height(&self) -> f32;
+
+    /// Create a new row view, which is then bound to a row by `bind_row_view`.
+    fn create_row_view(&mut self) -> Self::RowView;
+
+    /// Update `row_view` to show the row at `index`.
+    ///
+    /// `row_view` may have shown another row before, as row views are recycled when they scroll
+    /// out of view.
+    fn bind_row_view(&mut self, row_view: &mut Self::RowView, index: usize);
+}
+
+/// The persistent part of a `ListView`, which keeps the scroll offset and the row views across
+/// frames.
+pub struct ListState<RowView> {
+    scroll_state: ScrollState,
+    /// Row views bound to the rows that were visible in the last layout, sorted by row index.
+    bound_rows: Vec<(usize, RowView)>,
+    /// Row views ready to be bound to other rows.
+    recycled_rows: Vec<RowView>,
+    /// The row count and the row height used in the last layout, `None` before the first layout
+    /// and after `reload_rows`.
+    laid_out_rows: Option<(usize, f32)>,
+}
+
+impl<RowView> ListState<RowView> {
+    pub fn new<UiState>(event_router: &EventRouter<'_, UiState>) -> Self {
+        Self {
+            scroll_state: ScrollState::new(event_router),
+            bound_rows: Vec::new(),
+            recycled_rows: Vec::new(),
+            laid_out_rows: None,
+        }
+    }
+
+    pub fn scroll_state(&self) -> &ScrollState {
+        &self.scroll_state
+    }
+
+    pub fn scroll_state_mut(&mut self) -> &mut ScrollState {
+        &mut self.scroll_state
+    }
+
+    /// Rebind all the visible rows in the next layout, e.g. after the data of the rows changed.
+    pub fn reload_rows(&mut self) {
+        self.recycled_rows
+            .extend(self.bound_rows.drain(..).map(|(_, row_view)| row_view));
+        self.laid_out_rows = None;
+    }
+}
+
+/// The content of the scroll view of a `ListView`.
+pub(crate) struct ListRows<'pass, Source: ListDataSource> {
+    source: &'pass mut Source,
+    bound_rows: &'pass mut Vec<(usize, Source::RowView)>,
+    recycled_rows: &'pass mut Vec<Source::RowView>,
+    laid_out_rows: &'pass mut Option<(usize, f32)>,
+    /// Bounds of the `ListView`, set before the rows are laid out.
+    viewport: &'pass Cell<Bounds<f32>>,
+    row_count: usize,
+    row_height: f32,
+}
+
+impl<'pass, Source: ListDataSource> ListRows<'pass, Source> {
+    /// Returns the scroll state of `state` along with the rows, as they are borrowed separately
+    /// by the scroll view.
+    pub(crate) fn new(
+        viewport: &'pass Cell<Bounds<f32>>,
+        state: &'pass mut ListState<Source::RowView>,
+        source: &'pass mut Source,
+    ) -> (&'pass mut ScrollState, Self) {
+        let rows = Self {
+            row_count: source.row_count(),
+            row_height: source.row_height(),
+            source,
+            bound_rows: &mut state.bound_rows,
+            recycled_rows: &mut state.recycled_rows,
+            laid_out_rows: &mut state.laid_out_rows,
+            viewport,
+        };
+        (&mut state.scroll_state, rows)
+    }
+
+    /// Range of the indices of the rows inside the viewport, if the rows start at `content_y`.
+    fn visible_range(&self, content_y: f32) -> (usize, usize) {
+        if self.row_height <= 0. || self.row_height.is_nan() {
+            return (0, 0);
+        }
+        let viewport = self.viewport.get();
+        let start = ((viewport.y_min() - content_y) / self.row_height).floor();
+        let end = ((viewport.y_max_() - content_y) / self.row_height).ceil();
+        let clamp = |x: f32| (x.max(0.) as usize).min(self.row_count);
+        (clamp(start), clamp(end))
+    }
+
+    /// Recycle the row views outside of `start..end`, and bind row views to the rows without
+    /// one inside.
+    fn bind_rows(&mut self, start: usize, end: usize) {
+        let mut kept_rows = Vec::with_capacity(end - start);
+        for (index, row_view) in self.bound_rows.drain(..) {
+            match (start..end).contains(&index) {
+                true => kept_rows.push((index, row_view)),
+                false => self.recycled_rows.push(row_view),
+            }
+        }
+        let mut kept_rows = kept_rows.into_iter().peekable();
+        for index in start..end {
+            let bound_row = match kept_rows.next_if(|&(kept_index, _)| kept_index == index) {
+                Some(bound_row) => bound_row,
+                None => {
+                    let mut row_view = self
+                        .recycled_rows
+                        .pop()
+                        .unwrap_or_else(|| self.source.create_row_view());
+                    self.source.bind_row_view(&mut row_view, index);
+                    (index, row_view)
+                }
+            };
+            self.bound_rows.push(bound_row);
+        }
+    }
+}
+
+impl<'pass, 'cx, Source> View<'cx> for ListRows<'pass, Source>
+where
+    Source: ListDataSource,
+    Source::RowView: View<'cx>,
+{
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        RectSize::new(f32::INFINITY, self.row_count as f32 * self.row_height)
+    }
+
+    fn is_dirty(&self) -> bool {
+        *self.laid_out_rows != Some((self.row_count, self.row_height))
+            || self
+                .bound_rows
+                .iter()
+                .any(|(_, row_view)| row_view.is_dirty())
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        *self.laid_out_rows = Some((self.row_count, self.row_height));
+        let (start, end) = self.visible_range(bounds.y_min());
+        self.bind_rows(start, end);
+        // The scroll view clips the bounds of event listeners to its viewport, so bounds with no
+        // height leave the event listeners in recycled rows without bounds, instead of the bounds
+        // of the rows they were bound to before.
+        let recycled_bounds = Bounds::new(bounds.origin, RectSize::new(bounds.width(), 0.));
+        for row_view in self.recycled_rows.iter_mut() {
+            row_view.apply_bounds(recycled_bounds);
+        }
+        for (index, row_view) in self.bound_rows.iter_mut() {
+            let row_bounds = Bounds::new(
+                bounds.origin + vec2(0., *index as f32 * self.row_height),
+                RectSize::new(bounds.width(), self.row_height),
+            );
+            row_view.apply_bounds(row_bounds);
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        for (_, row_view) in self.bound_rows.iter_mut() {
+            row_view.prepare_for_drawing(ui_context, canvas);
+        }
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        for (_, row_view) in self.bound_rows.iter() {
+            row_view.draw(ui_context, render_pass);
+        }
+    }
+}
+
+/// A vertical list of rows of the same height, which only creates, lays out and draws the rows
+/// that are visible.
+///
+/// Rows are scrolled in the same way as a `ScrollView`. Row views that scroll out of view are
+/// recycled for the rows that scroll into view, along with their GPU resources.
+///
+/// Like a spreading view, `ListView` takes all the space it is given.
+pub struct ListView<'pass, Source: ListDataSource> {
+    viewport: &'pass Cell<Bounds<f32>>,
+    scroll_view: ScrollView<'pass, ListRows<'pass, Source>>,
+}
+
+impl<'pass, Source: ListDataSource> ListView<'pass, Source> {
+    pub(crate) fn new<'cx>(
+        viewport: &'pass Cell<Bounds<f32>>,
+        scroll_state: &'pass mut ScrollState,
+        rows: &'pass mut ListRows<'pass, Source>,
+    ) -> Self
+    where
+        Source::RowView: View<'cx>,
+    {
+        Self {
+            viewport,
+            scroll_view: ScrollView::new(scroll_state, rows),
+        }
+    }
+}
+
+impl<'pass, 'cx, Source> View<'cx> for ListView<'pass, Source>
+where
+    Source: ListDataSource,
+    Source::RowView: View<'cx>,
+{
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        RectSize::new(f32::INFINITY, f32::INFINITY)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.scroll_view.is_dirty()
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.viewport.set(bounds);
+        LayoutDebugOverlay::nested(|| self.scroll_view.apply_bounds(bounds));
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        self.scroll_view.prepare_for_drawing(ui_context, canvas);
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        self.scroll_view.draw(ui_context, render_pass);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::*;
+
+    use crate::LayoutPass;
+
+    use super::*;
+
+    /// Keeps the bounds from the last layout, and is never dirty itself.
+    #[derive(Default)]
+    struct Row {
+        bounds: Bounds<f32>,
+    }
+
+    impl<'cx> View<'cx> for Row {
+        fn preferred_size(&mut self) -> RectSize<f32> {
+            RectSize::new(0., 0.)
+        }
+
+        fn is_dirty(&self) -> bool {
+            false
+        }
+
+        fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+            self.bounds = bounds;
+        }
+
+        fn prepare_for_drawing(&mut self, _ui_context: &UiContext<'cx>, _canvas: &CanvasRef) {}
+
+        fn draw(&self, _ui_context: &UiContext<'cx>, _render_pass: &mut RenderPass) {}
+    }
+
+    struct Rows {
+        row_count: usize,
+    }
+
+    impl ListDataSource for Rows {
+        type RowView = Row;
+
+        fn row_count(&self) -> usize {
+            self.row_count
+        }
+
+        fn row_height(&self) -> f32 {
+            10.
+        }
+
+        fn create_row_view(&mut self) -> Row {
+            Row::default()
+        }
+
+        fn bind_row_view(&mut self, _row_view: &mut Row, _index: usize) {}
+    }
+
+    const BOUNDS: Bounds<f32> = Bounds::new(point2(0., 0.), RectSize::new(100., 50.));
+
+    #[test]
+    fn lays_out_recycled_rows_with_no_height() {
+        let event_router = EventRouter::<()>::new();
+        let mut state = ListState::new(&event_router);
+        let mut source = Rows { row_count: 10 };
+        let layout = LayoutPass::new();
+        layout.lay_out(layout.list_view(&mut state, &mut source), BOUNDS);
+        // Rows 0 to 4 are bound, then only rows 3 and 4 are visible.
+        state.scroll_state_mut().set_offset(vec2(0., 30.));
+        let layout = LayoutPass::new();
+        let bounds = Bounds::new(BOUNDS.origin, RectSize::new(100., 20.));
+        layout.lay_out(layout.list_view(&mut state, &mut source), bounds);
+
+        let bound_indices: Vec<usize> = state.bound_rows.iter().map(|&(index, _)| index).collect();
+        assert_eq!(bound_indices, [3, 4]);
+        assert_eq!(
+            state.bound_rows[0].1.bounds,
+            Bounds::new(point2(0., 0.), RectSize::new(100., 10.)),
+        );
+        assert_eq!(state.recycled_rows.len(), 3);
+        for row_view in &state.recycled_rows {
+            assert_eq!(row_view.bounds.height(), 0.);
+        }
+    }
+
+    #[test]
+    fn is_dirty_after_the_row_count_changes() {
+        let event_router = EventRouter::<()>::new();
+        let mut state = ListState::new(&event_router);
+        let mut source = Rows { row_count: 10 };
+        let viewport = Cell::new(BOUNDS);
+        let (_, mut rows) = ListRows::new(&viewport, &mut state, &mut source);
+        assert!(View::is_dirty(&rows));
+        rows.apply_bounds(BOUNDS);
+        assert!(!View::is_dirty(&rows));
+
+        source.row_count = 20;
+        let (_, rows) = ListRows::new(&viewport, &mut state, &mut source);
+        assert!(View::is_dirty(&rows));
+    }
+}
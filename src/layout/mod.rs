@@ -1,14 +1,34 @@
-use std::marker::PhantomData;
+use std::{cell::Cell, marker::PhantomData};
 
 use bumpalo::Bump;
 
-use crate::{Axis, RectSize, View};
+use crate::{Axis, Bounds, RectSize, View};
 
+mod anchored;
+mod aspect_ratio;
+mod cached;
+mod clip;
 mod container;
+mod flow;
+mod grid;
+mod list;
+mod overlay;
+mod scroll;
+mod split;
 mod stack;
 mod spacer;
 
+pub use anchored::*;
+pub use aspect_ratio::*;
+pub use cached::*;
+pub use clip::*;
 pub use container::*;
+pub use flow::*;
+pub use grid::*;
+pub use list::*;
+pub use overlay::*;
+pub use scroll::*;
+pub use split::*;
 pub use stack::*;
 pub use spacer::*;
 
@@ -24,13 +44,64 @@ impl Default for LayoutPass<'_> {
 }
 
 impl<'cx> LayoutPass<'cx> {
-    pub(crate) fn new() -> Self {
+    /// Usually layout passes are begun with `UiContext::begin_layout_pass`, this is for laying out
+    /// without a `UiContext` (see `lay_out`).
+    pub fn new() -> Self {
         Self {
             bumpalo: Bump::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Lay out `view` in `bounds`, without preparing it for drawing.
+    ///
+    /// Unlike `UiContext::prepare_view_bounded`, this does not need a `UiContext`, so view trees
+    /// (built from views that do not need one either) can be laid out without a GPU.
+    pub fn lay_out(&self, view: &mut dyn View<'cx>, bounds: Bounds<f32>) {
+        view.apply_bounds(bounds);
+    }
+
+    pub fn anchored<'pass, 'views>(
+        &'pass self,
+        build: impl FnOnce(&mut AnchoredBuilder<'pass, 'views, 'cx>),
+    ) -> &'pass mut Anchored<'pass, 'views, 'cx> {
+        let mut builder = AnchoredBuilder::new(&self.bumpalo);
+        build(&mut builder);
+        self.bumpalo.alloc(builder.finish())
+    }
+
+    pub fn aspect_ratio<'pass, 'view, Subview>(
+        &'pass self,
+        ratio: f32,
+        subview: &'view mut Subview,
+    ) -> &'pass mut AspectRatio<'view, Subview>
+    where
+        Subview: View<'cx>,
+    {
+        self.bumpalo.alloc(AspectRatio::new(ratio, subview))
+    }
+
+    pub fn cached<'pass, 'view, Subview>(
+        &'pass self,
+        cache: &'view mut LayoutCache,
+        subview: &'view mut Subview,
+    ) -> &'pass mut Cached<'view, Subview>
+    where
+        Subview: View<'cx>,
+    {
+        self.bumpalo.alloc(Cached::new(cache, subview))
+    }
+
+    pub fn clip_view<'pass, 'view, Subview>(
+        &'pass self,
+        subview: &'view mut Subview,
+    ) -> &'pass mut ClipView<'view, Subview>
+    where
+        Subview: View<'cx>,
+    {
+        self.bumpalo.alloc(ClipView::new(subview))
+    }
+
     pub fn container<'pass, 'view, Subview>(
         &'pass self,
         subview: &'view mut Subview,
@@ -41,6 +112,56 @@ impl<'cx> LayoutPass<'cx> {
         self.bumpalo.alloc(Container::new(subview))
     }
 
+    pub fn list_view<'pass, 'view, Source>(
+        &'pass self,
+        state: &'view mut ListState<Source::RowView>,
+        source: &'view mut Source,
+    ) -> &'pass mut ListView<'pass, Source>
+    where
+        'view: 'pass,
+        Source: ListDataSource,
+        Source::RowView: View<'cx>,
+    {
+        let viewport = &*self.bumpalo.alloc(Cell::new(Bounds::default()));
+        let (scroll_state, rows) = ListRows::new(viewport, state, source);
+        let rows = self.bumpalo.alloc(rows);
+        self.bumpalo.alloc(ListView::new(viewport, scroll_state, rows))
+    }
+
+    pub fn overlay<'pass, 'views>(
+        &'pass self,
+        build: impl FnOnce(&mut OverlayBuilder<'pass, 'views, 'cx>),
+    ) -> &'pass mut Overlay<'pass, 'views, 'cx> {
+        let mut builder = OverlayBuilder::new(&self.bumpalo);
+        build(&mut builder);
+        self.bumpalo.alloc(builder.finish())
+    }
+
+    pub fn scroll_view<'pass, 'view, Subview>(
+        &'pass self,
+        state: &'view mut ScrollState,
+        subview: &'view mut Subview,
+    ) -> &'pass mut ScrollView<'view, Subview>
+    where
+        Subview: View<'cx>,
+    {
+        self.bumpalo.alloc(ScrollView::new(state, subview))
+    }
+
+    pub fn split_view<'pass, 'view, Leading, Trailing>(
+        &'pass self,
+        axis: Axis,
+        state: &'view mut SplitState,
+        leading: &'view mut Leading,
+        trailing: &'view mut Trailing,
+    ) -> &'pass mut SplitView<'view, Leading, Trailing>
+    where
+        Leading: View<'cx>,
+        Trailing: View<'cx>,
+    {
+        self.bumpalo.alloc(SplitView::new(state, axis, leading, trailing))
+    }
+
     pub fn spacer(&self, size: RectSize<f32>) -> &'_ mut Spacer {
         self.bumpalo.alloc(Spacer::new(size))
     }
@@ -62,6 +183,25 @@ impl<'cx> LayoutPass<'cx> {
         self.stack(Axis::Vertical, build)
     }
 
+    pub fn flow<'pass, 'views>(
+        &'pass self,
+        axis: Axis,
+        build: impl FnOnce(&mut FlowBuilder<'pass, 'views, 'cx>),
+    ) -> &'pass mut Flow<'pass, 'views, 'cx> {
+        let mut builder = FlowBuilder::new(&self.bumpalo, axis);
+        build(&mut builder);
+        self.bumpalo.alloc(builder.finish())
+    }
+
+    pub fn grid<'pass, 'views>(
+        &'pass self,
+        build: impl FnOnce(&mut GridBuilder<'pass, 'views, 'cx>),
+    ) -> &'pass mut Grid<'pass, 'views, 'cx> {
+        let mut builder = GridBuilder::new(&self.bumpalo);
+        build(&mut builder);
+        self.bumpalo.alloc(builder.finish())
+    }
+
     pub fn hstack<'pass, 'views>(
         &'pass self,
         build: impl FnOnce(&mut StackBuilder<'pass, 'views, 'cx>),
@@ -69,3 +209,239 @@ impl<'cx> LayoutPass<'cx> {
         self.stack(Axis::Horizontal, build)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cgmath::*;
+
+    use crate::{
+        Constraints, ContainerPadding, FontMetrics, GridTrack, RectView, StackAlignmentHorizontal,
+        StackAlignmentVertical, StackFlex, TextView,
+    };
+
+    use super::*;
+
+    /// Glyphs are half as wide as they are high, so they are 6 wide at the default font size of 12.
+    const FONT_METRICS: FontMetrics = FontMetrics::new(RectSize::new(8, 16), 32..127);
+
+    #[test]
+    fn lays_out_stack_of_container_and_rect() {
+        let mut text = TextView::with_font_metrics(FONT_METRICS).with_text("hello");
+        let mut rect = RectView::new(RectSize::new(50., 20.));
+        let layout = LayoutPass::new();
+        let container = layout
+            .container(&mut text)
+            .set_padding(ContainerPadding::Fixed(10.));
+        let stack = layout.vstack(|vstack| {
+            vstack.set_fixed_padding(4.);
+            vstack.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            vstack.set_alignment_vertical(StackAlignmentVertical::Top);
+            vstack.subview(container);
+            vstack.subview(&mut rect);
+        });
+        layout.lay_out(stack, Bounds::new(point2(0., 0.), RectSize::new(200., 200.)));
+        assert_eq!(
+            text.bounds(),
+            Bounds::new(point2(10., 10.), RectSize::new(30., 12.)),
+        );
+        // Below the container, which is 32 high with the paddings.
+        assert_eq!(
+            rect.bounds(),
+            Bounds::new(point2(0., 36.), RectSize::new(50., 20.)),
+        );
+    }
+
+    #[test]
+    fn wraps_text_to_the_width_of_the_stack() {
+        let mut text = TextView::with_font_metrics(FONT_METRICS)
+            .with_text("hello world")
+            .with_line_wrapping(true);
+        let layout = LayoutPass::new();
+        let stack = layout.vstack(|vstack| {
+            vstack.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            vstack.set_alignment_vertical(StackAlignmentVertical::Top);
+            vstack.subview(&mut text);
+        });
+        layout.lay_out(stack, Bounds::new(point2(0., 0.), RectSize::new(40., 100.)));
+        assert_eq!(
+            text.bounds(),
+            Bounds::new(point2(0., 0.), RectSize::new(30., 24.)),
+        );
+    }
+
+    /// Lay out `rects` with their flexes in an hstack of `width` without paddings, and return
+    /// their x offsets and widths.
+    fn lay_out_in_hstack<const N: usize>(
+        width: f32,
+        rects: &mut [(RectView, StackFlex); N],
+    ) -> [(f32, f32); N] {
+        let layout = LayoutPass::new();
+        let stack = layout.hstack(|hstack| {
+            hstack.set_fixed_padding(0.);
+            hstack.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            hstack.set_alignment_vertical(StackAlignmentVertical::Top);
+            for (rect, flex) in rects.iter_mut() {
+                hstack.subview_with_flex(rect, *flex);
+            }
+        });
+        layout.lay_out(stack, bounds(0., 0., width, 100.));
+        rects.each_ref().map(|(rect, _)| {
+            let bounds = rect.bounds();
+            (bounds.x_min(), bounds.width())
+        })
+    }
+
+    #[test]
+    fn stack_grows_subviews_by_their_weights() {
+        let mut rects = [
+            (rect(10., 10.), StackFlex::new().with_grow(1.)),
+            (rect(10., 10.), StackFlex::new().with_grow(3.)),
+        ];
+        // The 80 left is split 1:3.
+        assert_eq!(lay_out_in_hstack(100., &mut rects), [(0., 30.), (30., 70.)]);
+    }
+
+    #[test]
+    fn stack_shrinks_subviews_down_to_their_min_lengths() {
+        let mut rects = [
+            (rect(60., 10.), StackFlex::new().with_min_length(50.)),
+            (rect(60., 10.), StackFlex::new()),
+        ];
+        // The first subview would be shrunk by half of the 40 lacking, but stops at 50 and leaves
+        // the rest to the second.
+        assert_eq!(lay_out_in_hstack(80., &mut rects), [(0., 50.), (50., 30.)]);
+    }
+
+    #[test]
+    fn stack_gives_what_subviews_cannot_take_beyond_their_max_lengths_to_others() {
+        let mut rects = [
+            (
+                rect(10., 10.),
+                StackFlex::new().with_grow(1.).with_max_length(20.),
+            ),
+            (rect(10., 10.), StackFlex::new().with_grow(1.)),
+        ];
+        assert_eq!(lay_out_in_hstack(100., &mut rects), [(0., 20.), (20., 80.)]);
+    }
+
+    #[test]
+    fn stack_grows_spreading_subviews_by_default() {
+        let mut fixed = rect(10., 10.);
+        let mut growing = rect(10., 10.);
+        let layout = LayoutPass::new();
+        let spacer = layout.spacer(RectSize::new(f32::INFINITY, 0.));
+        let stack = layout.hstack(|hstack| {
+            hstack.set_fixed_padding(0.);
+            hstack.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            hstack.set_alignment_vertical(StackAlignmentVertical::Top);
+            hstack.subview(&mut fixed);
+            hstack.subview(spacer);
+            hstack.subview_with_flex(&mut growing, StackFlex::new().with_grow(1.));
+        });
+        layout.lay_out(stack, bounds(0., 0., 100., 100.));
+        // `grow: None` is 0 for the fixed subview and 1 for the spacer, which splits the 80 left
+        // with the growing subview.
+        assert_eq!(fixed.bounds(), bounds(0., 0., 10., 10.));
+        assert_eq!(growing.bounds(), bounds(50., 0., 50., 10.));
+    }
+
+    fn rect(width: f32, height: f32) -> RectView {
+        RectView::new(RectSize::new(width, height))
+    }
+
+    fn bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<f32> {
+        Bounds::new(point2(x, y), RectSize::new(width, height))
+    }
+
+    #[test]
+    fn grid_gives_fraction_tracks_the_space_left_by_fixed_and_fit_tracks() {
+        let mut rects = [
+            rect(10., 10.),
+            rect(10., 10.),
+            rect(30., 10.),
+            rect(10., 10.),
+        ];
+        let layout = LayoutPass::new();
+        let grid = layout.grid(|grid| {
+            grid.set_columns([
+                GridTrack::Fixed(20.),
+                GridTrack::Fraction(1.),
+                GridTrack::Fit,
+                GridTrack::Fraction(3.),
+            ]);
+            grid.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            grid.set_alignment_vertical(StackAlignmentVertical::Top);
+            for (column, rect) in rects.iter_mut().enumerate() {
+                grid.subview(0, column, rect);
+            }
+        });
+        layout.lay_out(grid, bounds(0., 0., 170., 100.));
+        // The 120 left after the fixed and the fit columns is split 1:3.
+        let x_offsets = rects.map(|rect| rect.bounds().origin.x);
+        assert_eq!(x_offsets, [0., 20., 50., 80.]);
+    }
+
+    #[test]
+    fn grid_spreads_what_spanning_subviews_lack_over_their_tracks() {
+        let mut top_left = rect(10., 10.);
+        let mut top_right = rect(10., 10.);
+        let mut bottom = rect(40., 10.);
+        let layout = LayoutPass::new();
+        let grid = layout.grid(|grid| {
+            grid.set_column_spacing(4.);
+            grid.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            grid.set_alignment_vertical(StackAlignmentVertical::Top);
+            grid.subview(0, 0, &mut top_left);
+            grid.subview(0, 1, &mut top_right);
+            grid.subview_spanning(1, 0, 1, 2, &mut bottom);
+        });
+        assert_eq!(
+            grid.size_that_fits(Constraints::UNBOUNDED),
+            RectSize::new(40., 20.),
+        );
+        layout.lay_out(grid, bounds(0., 0., 100., 100.));
+        // The 16 that the bottom subview lacks over the 10 + 4 + 10 is split between the columns.
+        assert_eq!(top_right.bounds(), bounds(22., 0., 10., 10.));
+        assert_eq!(bottom.bounds(), bounds(0., 10., 40., 10.));
+    }
+
+    #[test]
+    fn grid_shrinks_tracks_below_their_content() {
+        let mut fixed = rect(40., 10.);
+        let mut fit = rect(40., 10.);
+        let layout = LayoutPass::new();
+        let grid = layout.grid(|grid| {
+            grid.set_columns([GridTrack::Fixed(40.), GridTrack::Fit]);
+            grid.set_column_spacing(10.);
+            grid.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            grid.set_alignment_vertical(StackAlignmentVertical::Top);
+            grid.subview(0, 0, &mut fixed);
+            grid.subview(0, 1, &mut fit);
+        });
+        // Half of the 40 + 10 + 40 the columns and the spacing need.
+        layout.lay_out(grid, bounds(0., 0., 45., 100.));
+        assert_eq!(fixed.bounds(), bounds(0., 0., 20., 10.));
+        assert_eq!(fit.bounds(), bounds(25., 0., 20., 10.));
+    }
+
+    #[test]
+    fn grid_fits_fraction_tracks_to_their_content_in_unbounded_space() {
+        let mut narrow = rect(10., 10.);
+        let mut wide = rect(20., 10.);
+        let layout = LayoutPass::new();
+        let grid = layout.grid(|grid| {
+            grid.set_columns([GridTrack::Fraction(1.), GridTrack::Fraction(2.)]);
+            grid.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+            grid.set_alignment_vertical(StackAlignmentVertical::Top);
+            grid.subview(0, 0, &mut narrow);
+            grid.subview(0, 1, &mut wide);
+        });
+        assert_eq!(
+            grid.size_that_fits(Constraints::UNBOUNDED),
+            RectSize::new(30., 10.),
+        );
+        layout.lay_out(grid, bounds(0., 0., f32::INFINITY, f32::INFINITY));
+        assert_eq!(narrow.bounds(), bounds(0., 0., 10., 10.));
+        assert_eq!(wide.bounds(), bounds(10., 0., 20., 10.));
+    }
+}
@@ -0,0 +1,134 @@
+use bumpalo::{Bump, collections::Vec as BumpVec};
+use cgmath::*;
+
+use crate::{
+    Bounds, CanvasRef, Constraints, LayoutDebugKind, LayoutDebugOverlay, RectSize, RenderPass,
+    StackAlignment, StackAlignmentHorizontal, StackAlignmentVertical, UiContext, View,
+};
+
+pub(crate) struct OverlaySubview<'view, 'cx> {
+    preferred_size: RectSize<f32>,
+    /// Horizontal and vertical alignments, `None` if the subview fills the whole overlay.
+    alignment: Option<(StackAlignment, StackAlignment)>,
+    view: &'view mut (dyn View<'cx> + 'view),
+}
+
+/// Layers subviews on top of each other. Subviews added first are drawn at the back.
+pub struct Overlay<'pass, 'views, 'cx> {
+    subviews: BumpVec<'pass, OverlaySubview<'views, 'cx>>,
+    bounds: Bounds<f32>,
+}
+
+impl<'pass, 'views, 'cx> Overlay<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump) -> Self {
+        Self {
+            subviews: BumpVec::new_in(bump),
+            bounds: Bounds::default(),
+        }
+    }
+
+    pub(crate) fn subview(
+        &mut self,
+        subview: &'views mut (dyn View<'cx> + 'views),
+        alignment: Option<(StackAlignment, StackAlignment)>,
+    ) {
+        self.subviews.push(OverlaySubview {
+            preferred_size: subview.preferred_size(),
+            alignment,
+            view: subview,
+        });
+    }
+}
+
+impl<'pass, 'views, 'cx> View<'cx> for Overlay<'pass, 'views, 'cx> {
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        self.subviews
+            .iter()
+            .fold(RectSize::new(0., 0.), |size, subview| {
+                size.max(subview.preferred_size)
+            })
+    }
+
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        let size = self
+            .subviews
+            .iter_mut()
+            .fold(RectSize::new(0., 0.), |size, subview| {
+                size.max(subview.view.size_that_fits(constraints))
+            });
+        constraints.constrain(size)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subviews.iter().any(|subview| subview.view.is_dirty())
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        for subview in &mut self.subviews {
+            let subview_bounds = match subview.alignment {
+                None => bounds,
+                Some((alignment_horizontal, alignment_vertical)) => {
+                    let subview_size = subview
+                        .view
+                        .size_that_fits(Constraints::loose(bounds.size));
+                    let leftover = bounds.size.as_vec() - subview_size.as_vec();
+                    let offset = vec2(
+                        alignment_horizontal.ratio() * leftover.x,
+                        alignment_vertical.ratio() * leftover.y,
+                    );
+                    Bounds::new(bounds.origin + offset, subview_size)
+                }
+            };
+            LayoutDebugOverlay::nested(|| subview.view.apply_bounds(subview_bounds));
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        for subview in &mut self.subviews {
+            subview.view.prepare_for_drawing(ui_context, canvas);
+        }
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        for subview in &self.subviews {
+            subview.view.draw(ui_context, render_pass);
+        }
+    }
+}
+
+pub struct OverlayBuilder<'pass, 'views, 'cx> {
+    overlay: Overlay<'pass, 'views, 'cx>,
+}
+
+impl<'pass, 'views, 'cx> OverlayBuilder<'pass, 'views, 'cx> {
+    pub(crate) fn new(bump: &'pass Bump) -> Self {
+        Self {
+            overlay: Overlay::new(bump),
+        }
+    }
+
+    /// Add a subview that fills the whole overlay, on top of the subviews added before.
+    pub fn subview(&mut self, subview: &'views mut (dyn View<'cx> + 'views)) {
+        self.overlay.subview(subview, None);
+    }
+
+    /// Add a subview in its preferred size (or smaller if there isn't enough space), aligned
+    /// inside the overlay, on top of the subviews added before.
+    pub fn subview_aligned(
+        &mut self,
+        subview: &'views mut (dyn View<'cx> + 'views),
+        alignment_horizontal: StackAlignmentHorizontal,
+        alignment_vertical: StackAlignmentVertical,
+    ) {
+        self.overlay.subview(
+            subview,
+            Some((alignment_horizontal.into(), alignment_vertical.into())),
+        );
+    }
+
+    pub(crate) fn finish(self) -> Overlay<'pass, 'views, 'cx> {
+        self.overlay
+    }
+}
@@ -0,0 +1,435 @@
+use std::sync::{Arc, Mutex};
+
+use cgmath::*;
+use winit::event::MouseButton;
+
+use crate::{
+    Axis, Bounds, CanvasRef, EventRouter, LayoutDebugKind, LayoutDebugOverlay, ListenerHandle,
+    MouseEvent, MouseEventKind, MouseEventListener, RectSize, RectView, RenderPass, Srgba,
+    UiContext, View, axis_utils::*,
+};
+
+/// Style of the scrollbars of a `ScrollView`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollbarStyle {
+    pub thickness: f32,
+    /// Minimum length of the thumb, so it stays draggable for very long contents.
+    pub min_thumb_length: f32,
+    pub track_color: Srgba,
+    pub thumb_color: Srgba,
+}
+
+impl Default for ScrollbarStyle {
+    fn default() -> Self {
+        Self {
+            thickness: 8.,
+            min_thumb_length: 24.,
+            track_color: Srgba::from_hex(0x00000040),
+            thumb_color: Srgba::from_hex(0xA2A2A2C0),
+        }
+    }
+}
+
+/// Geometry of a scrollbar from the last layout, for hit-testing.
+#[derive(Debug, Clone, Copy)]
+struct Scrollbar {
+    track: Bounds<f32>,
+    thumb: Bounds<f32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScrollbarDrag {
+    axis: Axis,
+    /// Position of the cursor on the axis when the drag started.
+    cursor_start: f32,
+    /// Scroll offset on the axis when the drag started.
+    offset_start: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScrollDispatchInner {
+    offset: Vector2<f32>,
+    /// Content size minus viewport size, updated in every layout.
+    max_offset: Vector2<f32>,
+    horizontal_scrollbar: Option<Scrollbar>,
+    vertical_scrollbar: Option<Scrollbar>,
+    drag: Option<ScrollbarDrag>,
+}
+
+impl ScrollDispatchInner {
+    fn scrollbar(&self, axis: Axis) -> Option<Scrollbar> {
+        match axis {
+            Axis::Horizontal => self.horizontal_scrollbar,
+            Axis::Vertical => self.vertical_scrollbar,
+        }
+    }
+
+    /// Scroll by `delta`, returning if the offset changed.
+    fn scroll(&mut self, delta: Vector2<f32>) -> bool {
+        let offset_before = self.offset;
+        self.set_offset(offset_before - delta);
+        self.offset != offset_before
+    }
+
+    fn set_offset(&mut self, offset: Vector2<f32>) {
+        self.offset = vec2(
+            offset.x.clamp(0., self.max_offset.x.max(0.)),
+            offset.y.clamp(0., self.max_offset.y.max(0.)),
+        );
+    }
+
+    /// Scroll offset on `axis` that moves the thumb by `thumb_delta`.
+    fn offset_for_thumb_delta(&self, axis: Axis, scrollbar: Scrollbar, thumb_delta: f32) -> f32 {
+        let travel = scrollbar.track.alpha(axis) - scrollbar.thumb.alpha(axis);
+        match travel > 0. {
+            true => thumb_delta * self.max_offset.a(axis) / travel,
+            false => 0.,
+        }
+    }
+
+    fn begin_drag(&mut self, cursor_position: Point2<f32>) {
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            let Some(scrollbar) = self.scrollbar(axis) else {
+                continue;
+            };
+            if !scrollbar.track.contains(cursor_position) {
+                continue;
+            }
+            // Clicking on the track outside of the thumb centers the thumb at the cursor.
+            if !scrollbar.thumb.contains(cursor_position) {
+                let thumb_delta = cursor_position.a(axis)
+                    - 0.5 * scrollbar.thumb.alpha(axis)
+                    - scrollbar.thumb.a_min(axis);
+                let mut offset = self.offset;
+                *offset.a_mut(axis) += self.offset_for_thumb_delta(axis, scrollbar, thumb_delta);
+                self.set_offset(offset);
+            }
+            self.drag = Some(ScrollbarDrag {
+                axis,
+                cursor_start: cursor_position.a(axis),
+                offset_start: self.offset.a(axis),
+            });
+            return;
+        }
+    }
+
+    fn drag(&mut self, cursor_position: Point2<f32>) {
+        let Some(drag) = self.drag else {
+            return;
+        };
+        let Some(scrollbar) = self.scrollbar(drag.axis) else {
+            return;
+        };
+        let thumb_delta = cursor_position.a(drag.axis) - drag.cursor_start;
+        let mut offset = self.offset;
+        *offset.a_mut(drag.axis) =
+            drag.offset_start + self.offset_for_thumb_delta(drag.axis, scrollbar, thumb_delta);
+        self.set_offset(offset);
+    }
+}
+
+#[derive(Debug)]
+struct ScrollDispatch {
+    inner: Mutex<ScrollDispatchInner>,
+}
+
+impl<UiState> MouseEventListener<UiState> for Arc<ScrollDispatch> {
+    fn mouse_event(&self, event: MouseEvent, _ui_state: &mut UiState) {
+        let mut inner = self.inner.lock().unwrap();
+        match event.kind {
+            MouseEventKind::Scroll { delta } => {
+                inner.scroll(delta);
+            }
+            MouseEventKind::ButtonDown {
+                button: MouseButton::Left,
+                started_inside: true,
+            } => inner.begin_drag(event.cursor_position),
+            MouseEventKind::Dragging {
+                button: MouseButton::Left,
+            } => inner.drag(event.cursor_position),
+            MouseEventKind::ButtonUp {
+                button: MouseButton::Left,
+                inside: _,
+            } => inner.drag = None,
+            _ => (),
+        }
+    }
+
+    /// Only consumes the event if there is room to scroll, so that scrolling past the end of a
+    /// nested scroll view scrolls the enclosing one.
+    fn scroll_event(&self, event: MouseEvent, _ui_state: &mut UiState) -> bool {
+        match event.kind {
+            MouseEventKind::Scroll { delta } => self.inner.lock().unwrap().scroll(delta),
+            _ => false,
+        }
+    }
+}
+
+/// The persistent part of a `ScrollView`, which keeps the scroll offset and receives scroll
+/// events across frames.
+pub struct ScrollState {
+    dispatch: Arc<ScrollDispatch>,
+    listener_handle: ListenerHandle,
+    /// The offset used in the last layout, `None` before the first layout.
+    laid_out_offset: Option<Vector2<f32>>,
+    scrollbar_style: ScrollbarStyle,
+    horizontal_track: RectView,
+    horizontal_thumb: RectView,
+    vertical_track: RectView,
+    vertical_thumb: RectView,
+}
+
+impl ScrollState {
+    pub fn new<UiState>(event_router: &EventRouter<'_, UiState>) -> Self {
+        let dispatch = Arc::new(ScrollDispatch {
+            inner: Mutex::new(ScrollDispatchInner {
+                offset: vec2(0., 0.),
+                max_offset: vec2(0., 0.),
+                horizontal_scrollbar: None,
+                vertical_scrollbar: None,
+                drag: None,
+            }),
+        });
+        let listener_handle = event_router.register_listener(Bounds::default(), dispatch.clone());
+        let mut scroll_state = Self {
+            dispatch,
+            listener_handle,
+            laid_out_offset: None,
+            scrollbar_style: ScrollbarStyle::default(),
+            horizontal_track: RectView::default(),
+            horizontal_thumb: RectView::default(),
+            vertical_track: RectView::default(),
+            vertical_thumb: RectView::default(),
+        };
+        scroll_state.update_scrollbar_colors();
+        scroll_state
+    }
+
+    /// The distance the content is scrolled by, from the top-left of the content.
+    pub fn offset(&self) -> Vector2<f32> {
+        self.dispatch.inner.lock().unwrap().offset
+    }
+
+    /// The offset is clamped to the scrollable range in the next layout.
+    pub fn set_offset(&mut self, offset: Vector2<f32>) {
+        self.dispatch.inner.lock().unwrap().offset = offset;
+    }
+
+    pub fn with_offset(mut self, offset: Vector2<f32>) -> Self {
+        self.set_offset(offset);
+        self
+    }
+
+    pub fn scrollbar_style(&self) -> ScrollbarStyle {
+        self.scrollbar_style
+    }
+
+    pub fn set_scrollbar_style(&mut self, scrollbar_style: ScrollbarStyle) {
+        self.scrollbar_style = scrollbar_style;
+        self.update_scrollbar_colors();
+    }
+
+    pub fn with_scrollbar_style(mut self, scrollbar_style: ScrollbarStyle) -> Self {
+        self.set_scrollbar_style(scrollbar_style);
+        self
+    }
+
+    fn update_scrollbar_colors(&mut self) {
+        let style = self.scrollbar_style;
+        self.horizontal_track.set_fill_color(style.track_color);
+        self.vertical_track.set_fill_color(style.track_color);
+        self.horizontal_thumb.set_fill_color(style.thumb_color);
+        self.vertical_thumb.set_fill_color(style.thumb_color);
+    }
+
+    fn scrollbar_views_mut(&mut self, axis: Axis) -> (&mut RectView, &mut RectView) {
+        match axis {
+            Axis::Horizontal => (&mut self.horizontal_track, &mut self.horizontal_thumb),
+            Axis::Vertical => (&mut self.vertical_track, &mut self.vertical_thumb),
+        }
+    }
+
+    fn scrollbar_views(&self, axis: Axis) -> (&RectView, &RectView) {
+        match axis {
+            Axis::Horizontal => (&self.horizontal_track, &self.horizontal_thumb),
+            Axis::Vertical => (&self.vertical_track, &self.vertical_thumb),
+        }
+    }
+}
+
+/// Lays out its subview in the subview's full preferred size, and shows a part of it that can be
+/// scrolled around by mouse wheels, trackpads or by dragging the scrollbars.
+///
+/// Spreading subviews are given the length of the scroll view on that axis.
+///
+/// Drawing of the subview is clipped to the bounds of the scroll view, so are the bounds of event
+/// listeners (e.g. buttons) in the subview.
+pub struct ScrollView<'view, Subview> {
+    state: &'view mut ScrollState,
+    subview: &'view mut Subview,
+    subview_size: RectSize<f32>,
+    bounds: Bounds<f32>,
+    shows_horizontal_scrollbar: bool,
+    shows_vertical_scrollbar: bool,
+}
+
+impl<'view, Subview> ScrollView<'view, Subview> {
+    pub(crate) fn new<'cx>(state: &'view mut ScrollState, subview: &'view mut Subview) -> Self
+    where
+        Subview: View<'cx>,
+    {
+        Self {
+            state,
+            subview_size: subview.preferred_size(),
+            subview,
+            bounds: Bounds::default(),
+            shows_horizontal_scrollbar: false,
+            shows_vertical_scrollbar: false,
+        }
+    }
+
+    fn shows_scrollbar(&self, axis: Axis) -> bool {
+        match axis {
+            Axis::Horizontal => self.shows_horizontal_scrollbar,
+            Axis::Vertical => self.shows_vertical_scrollbar,
+        }
+    }
+
+    /// Calculate the track and thumb bounds of the scrollbar on `axis`.
+    fn scrollbar_geometry(
+        &self,
+        axis: Axis,
+        content_size: RectSize<f32>,
+        offset: Vector2<f32>,
+        max_offset: Vector2<f32>,
+    ) -> Scrollbar {
+        let style = self.state.scrollbar_style;
+        let bounds = self.bounds;
+        let other_axis = match axis {
+            Axis::Horizontal => Axis::Vertical,
+            Axis::Vertical => Axis::Horizontal,
+        };
+        // Leave the corner for the other scrollbar.
+        let track_alpha = match self.shows_scrollbar(other_axis) {
+            true => (bounds.alpha(axis) - style.thickness).max(0.),
+            false => bounds.alpha(axis),
+        };
+        let track_beta = style.thickness.min(bounds.beta(axis));
+        let track_origin = bounds.origin
+            + Vector2::new_on_axis(axis, 0., bounds.beta(axis) - track_beta);
+        let track = Bounds::new(
+            track_origin,
+            RectSize::new_on_axis(axis, track_alpha, track_beta),
+        );
+        let thumb_alpha = (track_alpha * bounds.alpha(axis) / content_size.alpha(axis))
+            .max(style.min_thumb_length)
+            .min(track_alpha);
+        let thumb_a = match max_offset.a(axis) > 0. {
+            true => (track_alpha - thumb_alpha) * offset.a(axis) / max_offset.a(axis),
+            false => 0.,
+        };
+        let thumb = Bounds::new(
+            track_origin + Vector2::new_on_axis(axis, thumb_a, 0.),
+            RectSize::new_on_axis(axis, thumb_alpha, track_beta),
+        );
+        Scrollbar { track, thumb }
+    }
+}
+
+impl<'cx, 'view, Subview> View<'cx> for ScrollView<'view, Subview>
+where
+    Subview: View<'cx>,
+{
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        self.subview_size
+    }
+
+    fn is_dirty(&self) -> bool {
+        // Only the scrollbars shown in the last layout have been prepared.
+        let inner = self.state.dispatch.inner.lock().unwrap();
+        let scrollbars_dirty = [Axis::Horizontal, Axis::Vertical].into_iter().any(|axis| {
+            let (track_view, thumb_view) = self.state.scrollbar_views(axis);
+            inner.scrollbar(axis).is_some() && (track_view.is_dirty() || thumb_view.is_dirty())
+        });
+        drop(inner);
+        self.state.laid_out_offset != Some(self.state.offset())
+            || scrollbars_dirty
+            || self.subview.is_dirty()
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        let content_size = RectSize::new(
+            match self.subview_size.width.is_finite() {
+                true => self.subview_size.width,
+                false => bounds.width(),
+            },
+            match self.subview_size.height.is_finite() {
+                true => self.subview_size.height,
+                false => bounds.height(),
+            },
+        );
+        let max_offset = (content_size.as_vec() - bounds.size.as_vec()).map(|x| x.max(0.));
+        self.shows_horizontal_scrollbar = max_offset.x > 0.;
+        self.shows_vertical_scrollbar = max_offset.y > 0.;
+
+        let dispatch = self.state.dispatch.clone();
+        let mut inner = dispatch.inner.lock().unwrap();
+        inner.max_offset = max_offset;
+        let offset = inner.offset;
+        inner.set_offset(offset);
+        let offset = inner.offset;
+        self.state.laid_out_offset = Some(offset);
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            let scrollbar = self
+                .shows_scrollbar(axis)
+                .then(|| self.scrollbar_geometry(axis, content_size, offset, max_offset));
+            match axis {
+                Axis::Horizontal => inner.horizontal_scrollbar = scrollbar,
+                Axis::Vertical => inner.vertical_scrollbar = scrollbar,
+            }
+            if let Some(scrollbar) = scrollbar {
+                let (track_view, thumb_view) = self.state.scrollbar_views_mut(axis);
+                LayoutDebugOverlay::nested(|| {
+                    track_view.apply_bounds(scrollbar.track);
+                    thumb_view.apply_bounds(scrollbar.thumb);
+                });
+            }
+        }
+        drop(inner);
+
+        let listener_handle = &self.state.listener_handle;
+        listener_handle.update_bounds(bounds);
+        listener_handle.push_clip_bounds(bounds);
+        let subview_bounds = Bounds::new(bounds.origin - offset, content_size);
+        LayoutDebugOverlay::nested(|| {
+            LayoutDebugOverlay::clipped(bounds, || self.subview.apply_bounds(subview_bounds));
+        });
+        listener_handle.pop_clip_bounds();
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        self.subview.prepare_for_drawing(ui_context, canvas);
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            if self.shows_scrollbar(axis) {
+                let (track_view, thumb_view) = self.state.scrollbar_views_mut(axis);
+                track_view.prepare_for_drawing(ui_context, canvas);
+                thumb_view.prepare_for_drawing(ui_context, canvas);
+            }
+        }
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        render_pass.push_clip_rect(self.bounds);
+        self.subview.draw(ui_context, render_pass);
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            if self.shows_scrollbar(axis) {
+                let (track_view, thumb_view) = self.state.scrollbar_views(axis);
+                track_view.draw(ui_context, render_pass);
+                thumb_view.draw(ui_context, render_pass);
+            }
+        }
+        render_pass.pop_clip_rect();
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{RectSize, View};
+use crate::{LayoutDebugKind, LayoutDebugOverlay, RectSize, View};
 
 pub struct Spacer {
     size: RectSize<f32>,
@@ -15,8 +15,12 @@ impl<'cx> View<'cx> for Spacer {
         self.size
     }
 
+    fn is_dirty(&self) -> bool {
+        false
+    }
+
     fn apply_bounds(&mut self, bounds: crate::Bounds<f32>) {
-        _ = bounds;
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Spacer);
     }
 
     fn prepare_for_drawing(&mut self, ui_context: &crate::UiContext<'cx>, canvas: &crate::CanvasRef) {
@@ -0,0 +1,384 @@
+use std::sync::{Arc, Mutex};
+
+use cgmath::*;
+use serde::{Deserialize, Serialize};
+use winit::event::MouseButton;
+
+use crate::{
+    Axis, Bounds, CanvasRef, EventRouter, LayoutDebugKind, LayoutDebugOverlay, ListenerHandle,
+    MouseEvent, MouseEventKind, MouseEventListener, RectSize, RectView, RenderPass, Srgba,
+    UiContext, View, axis_utils::*,
+};
+
+/// One of the two panes of a `SplitView`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitPane {
+    /// The left pane of a horizontal split, or the top pane of a vertical split.
+    Leading,
+    /// The right pane of a horizontal split, or the bottom pane of a vertical split.
+    Trailing,
+}
+
+/// Position of the divider of a `SplitView`.
+///
+/// This is the part of `SplitState` that can be saved and restored across restarts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SplitPosition {
+    /// Length of the leading pane, as a ratio of the space of both panes (i.e. excluding the
+    /// divider).
+    pub ratio: f32,
+    /// The pane that is collapsed, if any.
+    pub collapsed: Option<SplitPane>,
+}
+
+impl Default for SplitPosition {
+    fn default() -> Self {
+        Self {
+            ratio: 0.5,
+            collapsed: None,
+        }
+    }
+}
+
+/// Style of the divider of a `SplitView`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitDividerStyle {
+    pub thickness: f32,
+    pub color: Srgba,
+}
+
+impl Default for SplitDividerStyle {
+    fn default() -> Self {
+        Self {
+            thickness: 4.,
+            color: Srgba::from_hex(0x494949FF),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SplitDrag {
+    /// Distance from the start of the divider to the cursor on the axis when the drag started.
+    grab_offset: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SplitDispatchInner {
+    position: SplitPosition,
+    min_leading_length: f32,
+    min_trailing_length: f32,
+    collapsible: bool,
+    /// Axis of the split view, updated in every layout.
+    axis: Axis,
+    /// Start on the axis of the space of both panes, updated in every layout.
+    panes_a: f32,
+    /// Length on the axis of the space of both panes, updated in every layout.
+    panes_alpha: f32,
+    drag: Option<SplitDrag>,
+}
+
+impl SplitDispatchInner {
+    /// Lengths on the axis of the leading and the trailing panes.
+    fn pane_lengths(&self) -> (f32, f32) {
+        let panes_alpha = self.panes_alpha;
+        let leading_alpha = match self.position.collapsed {
+            Some(SplitPane::Leading) => 0.,
+            Some(SplitPane::Trailing) => panes_alpha,
+            None => (self.position.ratio * panes_alpha)
+                .min(panes_alpha - self.min_trailing_length)
+                .max(self.min_leading_length)
+                .clamp(0., panes_alpha),
+        };
+        (leading_alpha, panes_alpha - leading_alpha)
+    }
+
+    /// Move the divider so that the leading pane has `leading_alpha` on the axis, collapsing a
+    /// pane if it is dragged to below half of its minimum length.
+    fn move_divider(&mut self, leading_alpha: f32) {
+        let trailing_alpha = self.panes_alpha - leading_alpha;
+        self.position.collapsed = match self.collapsible {
+            true if leading_alpha < 0.5 * self.min_leading_length => Some(SplitPane::Leading),
+            true if trailing_alpha < 0.5 * self.min_trailing_length => Some(SplitPane::Trailing),
+            _ => None,
+        };
+        if self.position.collapsed.is_none() && self.panes_alpha > 0. {
+            self.position.ratio = (leading_alpha / self.panes_alpha).clamp(0., 1.);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SplitDispatch {
+    inner: Mutex<SplitDispatchInner>,
+}
+
+impl<UiState> MouseEventListener<UiState> for Arc<SplitDispatch> {
+    fn mouse_event(&self, event: MouseEvent, _ui_state: &mut UiState) {
+        let mut inner = self.inner.lock().unwrap();
+        let cursor_a = event.cursor_position.a(inner.axis);
+        match event.kind {
+            MouseEventKind::ButtonDown {
+                button: MouseButton::Left,
+                started_inside: true,
+            } => {
+                let (leading_alpha, _) = inner.pane_lengths();
+                inner.drag = Some(SplitDrag {
+                    grab_offset: cursor_a - (inner.panes_a + leading_alpha),
+                });
+            }
+            MouseEventKind::Dragging {
+                button: MouseButton::Left,
+            } => {
+                if let Some(drag) = inner.drag {
+                    let leading_alpha = cursor_a - drag.grab_offset - inner.panes_a;
+                    inner.move_divider(leading_alpha);
+                }
+            }
+            MouseEventKind::ButtonUp {
+                button: MouseButton::Left,
+                inside: _,
+            } => inner.drag = None,
+            _ => (),
+        }
+    }
+}
+
+/// The persistent part of a `SplitView`, which keeps the position of the divider and receives
+/// drag events on the divider across frames.
+pub struct SplitState {
+    dispatch: Arc<SplitDispatch>,
+    listener_handle: ListenerHandle,
+    /// The position used in the last layout, `None` before the first layout.
+    laid_out_position: Option<SplitPosition>,
+    divider_style: SplitDividerStyle,
+    divider_view: RectView,
+}
+
+impl SplitState {
+    pub fn new<UiState>(event_router: &EventRouter<'_, UiState>) -> Self {
+        let dispatch = Arc::new(SplitDispatch {
+            inner: Mutex::new(SplitDispatchInner {
+                position: SplitPosition::default(),
+                min_leading_length: 0.,
+                min_trailing_length: 0.,
+                collapsible: false,
+                axis: Axis::Horizontal,
+                panes_a: 0.,
+                panes_alpha: 0.,
+                drag: None,
+            }),
+        });
+        let listener_handle = event_router.register_listener(Bounds::default(), dispatch.clone());
+        let mut split_state = Self {
+            dispatch,
+            listener_handle,
+            laid_out_position: None,
+            divider_style: SplitDividerStyle::default(),
+            divider_view: RectView::default(),
+        };
+        split_state.update_divider_color();
+        split_state
+    }
+
+    pub fn position(&self) -> SplitPosition {
+        self.dispatch.inner.lock().unwrap().position
+    }
+
+    pub fn set_position(&mut self, position: SplitPosition) {
+        self.dispatch.inner.lock().unwrap().position = position;
+    }
+
+    pub fn with_position(mut self, position: SplitPosition) -> Self {
+        self.set_position(position);
+        self
+    }
+
+    /// Minimum lengths of the panes on the axis of the split view.
+    ///
+    /// The minimum lengths are not respected if there isn't enough space for both.
+    pub fn min_lengths(&self) -> (f32, f32) {
+        let inner = self.dispatch.inner.lock().unwrap();
+        (inner.min_leading_length, inner.min_trailing_length)
+    }
+
+    pub fn set_min_lengths(&mut self, min_leading_length: f32, min_trailing_length: f32) {
+        let mut inner = self.dispatch.inner.lock().unwrap();
+        inner.min_leading_length = min_leading_length;
+        inner.min_trailing_length = min_trailing_length;
+    }
+
+    pub fn with_min_lengths(mut self, min_leading_length: f32, min_trailing_length: f32) -> Self {
+        self.set_min_lengths(min_leading_length, min_trailing_length);
+        self
+    }
+
+    /// Whether a pane collapses when the divider is dragged to below half of its minimum length.
+    pub fn is_collapsible(&self) -> bool {
+        self.dispatch.inner.lock().unwrap().collapsible
+    }
+
+    pub fn set_collapsible(&mut self, collapsible: bool) {
+        self.dispatch.inner.lock().unwrap().collapsible = collapsible;
+    }
+
+    pub fn with_collapsible(mut self, collapsible: bool) -> Self {
+        self.set_collapsible(collapsible);
+        self
+    }
+
+    pub fn divider_style(&self) -> SplitDividerStyle {
+        self.divider_style
+    }
+
+    pub fn set_divider_style(&mut self, divider_style: SplitDividerStyle) {
+        self.divider_style = divider_style;
+        self.update_divider_color();
+    }
+
+    pub fn with_divider_style(mut self, divider_style: SplitDividerStyle) -> Self {
+        self.set_divider_style(divider_style);
+        self
+    }
+
+    fn update_divider_color(&mut self) {
+        self.divider_view.set_fill_color(self.divider_style.color);
+    }
+}
+
+/// Lays out two subviews next to each other along an axis, with a divider in between that can be
+/// dragged to resize them.
+///
+/// Like a spreading view, `SplitView` takes all the space it is given. Each pane is clipped to its
+/// bounds, and so are the bounds of event listeners in it. Collapsed panes are laid out with no
+/// length, which leaves their event listeners without bounds, and are not drawn.
+pub struct SplitView<'view, Leading, Trailing> {
+    state: &'view mut SplitState,
+    axis: Axis,
+    leading: &'view mut Leading,
+    trailing: &'view mut Trailing,
+    bounds: Bounds<f32>,
+    leading_bounds: Bounds<f32>,
+    trailing_bounds: Bounds<f32>,
+}
+
+impl<'view, Leading, Trailing> SplitView<'view, Leading, Trailing> {
+    pub(crate) fn new(
+        state: &'view mut SplitState,
+        axis: Axis,
+        leading: &'view mut Leading,
+        trailing: &'view mut Trailing,
+    ) -> Self {
+        Self {
+            state,
+            axis,
+            leading,
+            trailing,
+            bounds: Bounds::default(),
+            leading_bounds: Bounds::default(),
+            trailing_bounds: Bounds::default(),
+        }
+    }
+
+    fn collapsed(&self) -> Option<SplitPane> {
+        self.state.laid_out_position.and_then(|position| position.collapsed)
+    }
+}
+
+impl<'cx, 'view, Leading, Trailing> View<'cx> for SplitView<'view, Leading, Trailing>
+where
+    Leading: View<'cx>,
+    Trailing: View<'cx>,
+{
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        RectSize::new(f32::INFINITY, f32::INFINITY)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.state.laid_out_position != Some(self.state.position())
+            || self.state.divider_view.is_dirty()
+            || (self.collapsed() != Some(SplitPane::Leading) && self.leading.is_dirty())
+            || (self.collapsed() != Some(SplitPane::Trailing) && self.trailing.is_dirty())
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        // For the lingo "a", "b", "alpha", "beta", see `axis_utils`.
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        let axis = self.axis;
+        let thickness = self
+            .state
+            .divider_style
+            .thickness
+            .min(bounds.alpha(axis))
+            .max(0.);
+
+        let dispatch = self.state.dispatch.clone();
+        let mut inner = dispatch.inner.lock().unwrap();
+        inner.axis = axis;
+        inner.panes_a = bounds.a_min(axis);
+        inner.panes_alpha = bounds.alpha(axis) - thickness;
+        let (leading_alpha, trailing_alpha) = inner.pane_lengths();
+        let position = inner.position;
+        drop(inner);
+        self.state.laid_out_position = Some(position);
+
+        let beta = bounds.beta(axis);
+        let leading_bounds = Bounds::new(
+            bounds.origin,
+            RectSize::new_on_axis(axis, leading_alpha, beta),
+        );
+        let divider_bounds = Bounds::new(
+            bounds.origin + Vector2::new_on_axis(axis, leading_alpha, 0.),
+            RectSize::new_on_axis(axis, thickness, beta),
+        );
+        let trailing_bounds = Bounds::new(
+            bounds.origin + Vector2::new_on_axis(axis, leading_alpha + thickness, 0.),
+            RectSize::new_on_axis(axis, trailing_alpha, beta),
+        );
+        self.leading_bounds = leading_bounds;
+        self.trailing_bounds = trailing_bounds;
+        let listener_handle = &self.state.listener_handle;
+        listener_handle.update_bounds(divider_bounds);
+        LayoutDebugOverlay::nested(|| {
+            listener_handle.push_clip_bounds(leading_bounds);
+            LayoutDebugOverlay::clipped(leading_bounds, || {
+                self.leading.apply_bounds(leading_bounds);
+            });
+            listener_handle.pop_clip_bounds();
+            listener_handle.push_clip_bounds(trailing_bounds);
+            LayoutDebugOverlay::clipped(trailing_bounds, || {
+                self.trailing.apply_bounds(trailing_bounds);
+            });
+            listener_handle.pop_clip_bounds();
+            self.state.divider_view.apply_bounds(divider_bounds);
+        });
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        let collapsed = self.collapsed();
+        if collapsed != Some(SplitPane::Leading) {
+            self.leading.prepare_for_drawing(ui_context, canvas);
+        }
+        if collapsed != Some(SplitPane::Trailing) {
+            self.trailing.prepare_for_drawing(ui_context, canvas);
+        }
+        self.state
+            .divider_view
+            .prepare_for_drawing(ui_context, canvas);
+    }
+
+    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        let collapsed = self.collapsed();
+        if collapsed != Some(SplitPane::Leading) {
+            render_pass.push_clip_rect(self.leading_bounds);
+            self.leading.draw(ui_context, render_pass);
+            render_pass.pop_clip_rect();
+        }
+        if collapsed != Some(SplitPane::Trailing) {
+            render_pass.push_clip_rect(self.trailing_bounds);
+            self.trailing.draw(ui_context, render_pass);
+            render_pass.pop_clip_rect();
+        }
+        self.state.divider_view.draw(ui_context, render_pass);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{Axis, Bounds, CanvasRef, RectSize, RenderPass, UiContext, View, axis_utils::*};
+use std::iter;
+
+use crate::{
+    Axis, Bounds, CanvasRef, Constraints, LayoutDebugKind, LayoutDebugOverlay, RectSize,
+    RenderPass, UiContext, View, axis_utils::*,
+};
 
 use bumpalo::{Bump, collections::Vec as BumpVec};
 use cgmath::*;
@@ -41,7 +46,7 @@ pub(crate) enum StackAlignment {
 }
 
 impl StackAlignment {
-    fn ratio(self) -> f32 {
+    pub(crate) fn ratio(self) -> f32 {
         match self {
             StackAlignment::Center => 0.5,
             StackAlignment::Leading => 0.0,
@@ -95,9 +100,86 @@ impl From<StackAlignment> for StackAlignmentVertical {
     }
 }
 
+/// How a subview of a `Stack` grows and shrinks along the axis of the stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StackFlex {
+    /// Share of the leftover space this subview takes, relative to the other growing subviews.
+    ///
+    /// `None` means `1.0` for subviews with infinite preferred lengths (i.e. spreading subviews),
+    /// and `0.0` for other subviews.
+    pub grow: Option<f32>,
+    /// How fast this subview shrinks when there is not enough space, relative to the other
+    /// subviews. The amount of shrinking is also proportional to the preferred length.
+    pub shrink: f32,
+    /// The subview never grows or shrinks below this length, unless the stack itself runs out of
+    /// space.
+    pub min_length: f32,
+    /// The subview never grows beyond this length.
+    pub max_length: f32,
+}
+
+impl Default for StackFlex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StackFlex {
+    pub const fn new() -> Self {
+        Self {
+            grow: None,
+            shrink: 1.,
+            min_length: 0.,
+            max_length: f32::INFINITY,
+        }
+    }
+
+    pub const fn with_grow(self, grow: f32) -> Self {
+        Self {
+            grow: Some(grow),
+            ..self
+        }
+    }
+
+    pub const fn with_shrink(self, shrink: f32) -> Self {
+        Self { shrink, ..self }
+    }
+
+    pub const fn with_min_length(self, min_length: f32) -> Self {
+        Self { min_length, ..self }
+    }
+
+    pub const fn with_max_length(self, max_length: f32) -> Self {
+        Self { max_length, ..self }
+    }
+
+    /// `grow` with its default value resolved.
+    fn grow_for(self, preferred_alpha: f32) -> f32 {
+        match self.grow {
+            Some(grow) => grow,
+            None if preferred_alpha.is_finite() => 0.,
+            None => 1.,
+        }
+    }
+
+    /// The length a subview starts from before growing or shrinking.
+    fn base_length_for(self, preferred_alpha: f32) -> f32 {
+        match preferred_alpha.is_finite() {
+            true => preferred_alpha.clamp(self.min_length, self.max_length),
+            false => self.min_length,
+        }
+    }
+}
+
 #[derive(AsRef, AsMut, Deref, DerefMut)]
 pub(crate) struct StackSubview<'view, 'cx> {
-    pub(crate) preferred_size: RectSize<f32>,
+    /// Size of the subview under the constraints of the current layout.
+    pub(crate) measured_size: RectSize<f32>,
+    pub(crate) flex: StackFlex,
+    /// Length on the axis of the stack, before growing or shrinking.
+    pub(crate) base_alpha: f32,
+    /// Length on the axis of the stack, after growing or shrinking.
+    pub(crate) resolved_alpha: f32,
     #[deref]
     #[deref_mut]
     #[as_ref]
@@ -115,16 +197,15 @@ pub struct Stack<'pass, 'views, 'cx> {
     fixed_padding: Option<f32>,
     shrink_together: bool,
     subviews: BumpVec<'pass, StackSubview<'views, 'cx>>,
+    bounds: Bounds<f32>,
     /// Sum of the alphas of subviews.
     alpha_sum: f32,
-    /// Sum of alphas of subviews, excluding those who has infinite alphas.
+    /// Sum of the base alphas (see `StackFlex`) of subviews, which excludes infinite alphas.
     alpha_sum_finite: f32,
     /// Max among the betas of subviews.
     beta_max: f32,
     /// Max among the betas of subviews, excluding those who has infinite betas.
     beta_max_finite: f32,
-    /// Number of subviews who has infinite alphas.
-    n_infinite_alphas: usize,
 }
 
 impl<'pass, 'views, 'cx> Stack<'pass, 'views, 'cx> {
@@ -137,31 +218,35 @@ impl<'pass, 'views, 'cx> Stack<'pass, 'views, 'cx> {
             fixed_padding: None,
             shrink_together: false,
             subviews: BumpVec::new_in(bump),
+            bounds: Bounds::default(),
             alpha_sum: 0.,
             alpha_sum_finite: 0.,
             beta_max: 0.,
             beta_max_finite: 0.,
-            n_infinite_alphas: 0,
         }
     }
 
-    pub(crate) fn subview(&mut self, subview: &'views mut (dyn View<'cx> + 'views)) {
+    pub(crate) fn subview(
+        &mut self,
+        subview: &'views mut (dyn View<'cx> + 'views),
+        flex: StackFlex,
+    ) {
         // For the lingo "a", "b", "alpha", "beta", see `axis_utils`.
         let subview_size = subview.preferred_size();
         let subview_alpha = subview_size.alpha(self.axis);
         let subview_beta = subview_size.beta(self.axis);
-        self.alpha_sum += subview_size.alpha(self.axis);
+        let base_alpha = flex.base_length_for(subview_alpha);
+        self.alpha_sum += subview_alpha.clamp(flex.min_length, flex.max_length);
+        self.alpha_sum_finite += base_alpha;
         self.beta_max = self.beta_max.max(subview_beta);
-        if subview_alpha.is_finite() {
-            self.alpha_sum_finite += subview_alpha;
-        } else {
-            self.n_infinite_alphas += 1;
-        }
         if subview_beta.is_finite() {
             self.beta_max_finite = self.beta_max_finite.max(subview_beta);
         }
         self.subviews.push(StackSubview {
-            preferred_size: subview_size,
+            measured_size: subview_size,
+            flex,
+            base_alpha,
+            resolved_alpha: base_alpha,
             view: subview,
         });
     }
@@ -172,6 +257,158 @@ impl<'pass, 'views, 'cx> Stack<'pass, 'views, 'cx> {
             StackPaddingType::Omnipadded => n_subviews + 1,
         }
     }
+
+    /// Measure the subviews again with `available_beta` as the limit on the other axis, since
+    /// their lengths on the axis of the stack may depend on it (e.g. wrapping texts).
+    fn measure(&mut self, available_beta: f32) {
+        let axis = self.axis;
+        let constraints =
+            Constraints::loose(RectSize::new_on_axis(axis, f32::INFINITY, available_beta));
+        self.alpha_sum_finite = 0.;
+        self.beta_max_finite = 0.;
+        for subview in &mut self.subviews {
+            let measured_size = subview.view.size_that_fits(constraints);
+            subview.measured_size = measured_size;
+            subview.base_alpha = subview.flex.base_length_for(measured_size.alpha(axis));
+            self.alpha_sum_finite += subview.base_alpha;
+            if measured_size.beta(axis).is_finite() {
+                self.beta_max_finite = self.beta_max_finite.max(measured_size.beta(axis));
+            }
+        }
+    }
+
+    /// Length of a subview on the other axis if it is given `alpha` on the axis of the stack.
+    fn fitted_beta(
+        axis: Axis,
+        subview: &mut StackSubview,
+        alpha: f32,
+        available_beta: f32,
+    ) -> f32 {
+        if alpha >= subview.measured_size.alpha(axis) {
+            return subview.measured_size.beta(axis);
+        }
+        subview
+            .view
+            .size_that_fits(Constraints::loose(RectSize::new_on_axis(
+                axis,
+                alpha,
+                available_beta,
+            )))
+            .beta(axis)
+    }
+
+    /// Resolve the lengths of the subviews on the axis of the stack for `available_alpha` (see
+    /// `resolve_alphas`).
+    ///
+    /// Returns the part of the leftover space that is not taken by any subview, and the shrinking
+    /// factor on the axis of the stack.
+    fn resolve_for(&mut self, available_alpha: f32) -> (f32, f32) {
+        let n_paddings = Self::n_paddings(self.subviews.len(), self.padding_type) as f32;
+        let min_alpha = self.alpha_sum_finite + n_paddings * self.fixed_padding.unwrap_or(0.);
+        let leftover_alpha = (available_alpha - min_alpha).max(0.);
+        let shrink_a = (available_alpha / min_alpha).min(1.);
+        (self.resolve_alphas(leftover_alpha, shrink_a), shrink_a)
+    }
+
+    /// Grow or shrink the subviews according to their `StackFlex`es, and store the results in
+    /// `resolved_alpha`s.
+    ///
+    /// Returns the part of the leftover space that is not taken by any subview.
+    fn resolve_alphas(&mut self, leftover_alpha: f32, shrink_a: f32) -> f32 {
+        let axis = self.axis;
+        for subview in &mut self.subviews {
+            subview.resolved_alpha = subview.base_alpha;
+        }
+        if shrink_a < 1. {
+            // Shrinking subviews at the same rate as the paddings, with the deficit distributed by
+            // `shrink * base_alpha`, and then redistributed if some subviews hit `min_length`.
+            let mut deficit = (1. - shrink_a) * self.alpha_sum_finite;
+            let mut frozen = BumpVec::from_iter_in(
+                self.subviews
+                    .iter()
+                    .map(|subview| subview.flex.shrink * subview.base_alpha <= 0.),
+                self.subviews.bump(),
+            );
+            while deficit > 0. {
+                let weight_sum: f32 = iter::zip(&self.subviews, &frozen)
+                    .filter(|&(_, &frozen)| !frozen)
+                    .map(|(subview, _)| subview.flex.shrink * subview.base_alpha)
+                    .sum();
+                if weight_sum <= 0. {
+                    break;
+                }
+                let mut froze_any = false;
+                for (subview, frozen) in iter::zip(&mut self.subviews, &mut frozen) {
+                    let weight = subview.flex.shrink * subview.base_alpha;
+                    if *frozen
+                        || subview.resolved_alpha - deficit * weight / weight_sum
+                            >= subview.flex.min_length
+                    {
+                        continue;
+                    }
+                    deficit -= subview.resolved_alpha - subview.flex.min_length;
+                    subview.resolved_alpha = subview.flex.min_length;
+                    *frozen = true;
+                    froze_any = true;
+                }
+                if froze_any {
+                    continue;
+                }
+                for (subview, &frozen) in iter::zip(&mut self.subviews, &frozen) {
+                    if !frozen {
+                        let weight = subview.flex.shrink * subview.base_alpha;
+                        subview.resolved_alpha -= deficit * weight / weight_sum;
+                    }
+                }
+                deficit = 0.;
+            }
+            0.
+        } else {
+            // Growing subviews by their `grow`, and then redistributed if some subviews hit
+            // `max_length`.
+            let mut leftover_alpha = leftover_alpha;
+            let mut frozen = BumpVec::from_iter_in(
+                self.subviews.iter().map(|subview| {
+                    subview.flex.grow_for(subview.measured_size.alpha(axis)) <= 0.
+                }),
+                self.subviews.bump(),
+            );
+            while leftover_alpha > 0. {
+                let grow_sum: f32 = iter::zip(&self.subviews, &frozen)
+                    .filter(|&(_, &frozen)| !frozen)
+                    .map(|(subview, _)| subview.flex.grow_for(subview.measured_size.alpha(axis)))
+                    .sum();
+                if grow_sum <= 0. {
+                    break;
+                }
+                let mut froze_any = false;
+                for (subview, frozen) in iter::zip(&mut self.subviews, &mut frozen) {
+                    let grow = subview.flex.grow_for(subview.measured_size.alpha(axis));
+                    if *frozen
+                        || subview.resolved_alpha + leftover_alpha * grow / grow_sum
+                            <= subview.flex.max_length
+                    {
+                        continue;
+                    }
+                    leftover_alpha -= subview.flex.max_length - subview.resolved_alpha;
+                    subview.resolved_alpha = subview.flex.max_length;
+                    *frozen = true;
+                    froze_any = true;
+                }
+                if froze_any {
+                    continue;
+                }
+                for (subview, &frozen) in iter::zip(&mut self.subviews, &frozen) {
+                    if !frozen {
+                        let grow = subview.flex.grow_for(subview.measured_size.alpha(axis));
+                        subview.resolved_alpha += leftover_alpha * grow / grow_sum;
+                    }
+                }
+                leftover_alpha = 0.;
+            }
+            leftover_alpha
+        }
+    }
 }
 
 impl<'pass, 'views, 'cx> View<'cx> for Stack<'pass, 'views, 'cx> {
@@ -184,36 +421,71 @@ impl<'pass, 'views, 'cx> View<'cx> for Stack<'pass, 'views, 'cx> {
         )
     }
 
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        // For the lingo "a", "b", "alpha", "beta", see `axis_utils`.
+        let axis = self.axis;
+        let available_alpha = constraints.max.alpha(axis);
+        let available_beta = constraints.max.beta(axis);
+        self.measure(available_beta);
+        let n_paddings = Self::n_paddings(self.subviews.len(), self.padding_type) as f32;
+        let alpha = self
+            .subviews
+            .iter()
+            .map(|subview| {
+                let flex = subview.flex;
+                subview
+                    .measured_size
+                    .alpha(axis)
+                    .clamp(flex.min_length, flex.max_length)
+            })
+            .sum::<f32>()
+            + n_paddings * self.fixed_padding.unwrap_or(0.);
+        if alpha <= available_alpha {
+            let beta = self
+                .subviews
+                .iter()
+                .map(|subview| subview.measured_size.beta(axis))
+                .fold(0.0f32, f32::max);
+            return constraints.constrain(RectSize::new_on_axis(axis, alpha, beta));
+        }
+        // The subviews are shrunk (or grown, for spreading subviews) to fit, and may need a
+        // different length on the other axis.
+        self.resolve_for(available_alpha);
+        let mut beta = 0.0f32;
+        for subview in &mut self.subviews {
+            let alpha = subview.resolved_alpha;
+            beta = beta.max(Self::fitted_beta(axis, subview, alpha, available_beta));
+        }
+        constraints.constrain(RectSize::new_on_axis(axis, available_alpha, beta))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.subviews.iter().any(|subview| subview.view.is_dirty())
+    }
+
     fn apply_bounds(&mut self, bounds: Bounds<f32>) {
         // For the lingo "a", "b", "alpha", "beta", see `axis_utils`.
 
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::Layout);
+        self.bounds = bounds;
+        self.measure(bounds.beta(self.axis));
         let n_paddings = Self::n_paddings(self.subviews.len(), self.padding_type) as f32;
-
-        let min_alpha = self.alpha_sum_finite + n_paddings * self.fixed_padding.unwrap_or(0.);
-        let leftover_alpha = (bounds.alpha(self.axis) - min_alpha).max(0.);
-        let shrink_a = (bounds.alpha(self.axis) / min_alpha).min(1.);
         let shrink_b = match self.shrink_together {
             true => (bounds.beta(self.axis) / self.beta_max_finite).min(1.),
             false => 1.0f32,
         };
+        // Leftover space that is not taken by growing subviews.
+        let (leftover_alpha, shrink_a) = self.resolve_for(bounds.alpha(self.axis));
         // Leading padding, excluding the padding caused by `Omnipadded`.
         let padding_leading = match (self.fixed_padding, self.alignment_alpha) {
-            // If one of the views spread:
-            (_, _) if self.n_infinite_alphas != 0 => 0.,
             // `alignment_alpha` is ineffective unless set with fixed padding.
             (None, _alignment) => 0.0f32,
             (Some(_), alignment) => alignment.ratio() * leftover_alpha,
         };
         let padding = match self.fixed_padding {
             Some(fixed_padding) => fixed_padding * shrink_a,
-            // If one of the views spread:
-            None if self.n_infinite_alphas != 0 => 0.,
             None => leftover_alpha / n_paddings,
         };
-        let spread_view_alpha = match self.n_infinite_alphas {
-            0 => 0.0f32,
-            n => leftover_alpha / (n as f32)
-        };
 
         // Accumulator for A-axis offset while we iterate through the subviews.
         let mut offset_a = padding_leading;
@@ -223,19 +495,16 @@ impl<'pass, 'views, 'cx> View<'cx> for Stack<'pass, 'views, 'cx> {
                 // condition, because `remaining_size` uses offset_a later.
                 offset_a += padding;
             }
-            let mut requested_size = subview
-                .preferred_size
-                .scaled_on_axis(self.axis, shrink_a, shrink_b);
-            let requested_alpha = requested_size.alpha_mut(self.axis);
-            if *requested_alpha == f32::INFINITY {
-                // This view wants to spread.
-                *requested_alpha = spread_view_alpha;
-            }
             let remaining_size = RectSize::new_on_axis(
                 self.axis,
                 bounds.alpha(self.axis) - offset_a,
                 bounds.beta(self.axis),
             );
+            let subview_alpha = subview.resolved_alpha.min(remaining_size.alpha(self.axis));
+            let subview_beta =
+                Self::fitted_beta(self.axis, subview, subview_alpha, bounds.beta(self.axis));
+            let requested_size =
+                RectSize::new_on_axis(self.axis, subview_alpha, subview_beta * shrink_b);
             let subview_size = requested_size.min(remaining_size);
             let leftover_beta = bounds.beta(self.axis) - subview_size.beta(self.axis);
             let offset_b = self.alignment_beta.ratio() * leftover_beta;
@@ -243,7 +512,7 @@ impl<'pass, 'views, 'cx> View<'cx> for Stack<'pass, 'views, 'cx> {
                 bounds.origin + Vector2::new_on_axis(self.axis, offset_a, offset_b),
                 subview_size,
             );
-            subview.apply_bounds(subview_bounds);
+            LayoutDebugOverlay::nested(|| subview.apply_bounds(subview_bounds));
             offset_a += subview_size.alpha(self.axis);
         }
     }
@@ -273,7 +542,18 @@ impl<'pass, 'views, 'cx> StackBuilder<'pass, 'views, 'cx> {
     }
 
     pub fn subview(&mut self, subview: &'views mut (dyn View<'cx> + 'views)) {
-        self.stack.subview(subview);
+        self.stack.subview(subview, StackFlex::new());
+    }
+
+    /// Add a subview that grows, shrinks, and is limited in length as specified by `flex`.
+    ///
+    /// See `StackFlex`.
+    pub fn subview_with_flex(
+        &mut self,
+        subview: &'views mut (dyn View<'cx> + 'views),
+        flex: StackFlex,
+    ) {
+        self.stack.subview(subview, flex);
     }
 
     pub fn set_alignment_vertical(&mut self, alignment: StackAlignmentVertical) {
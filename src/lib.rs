@@ -6,8 +6,10 @@ pub use winit;
 
 mod canvas;
 mod event_router;
+mod fill;
 mod font;
 mod misc;
+mod path;
 mod resources;
 mod texture;
 mod view;
@@ -16,8 +18,10 @@ mod layout;
 
 pub use canvas::*;
 pub use event_router::*;
+pub use fill::*;
 pub use font::*;
 pub use misc::*;
+pub use path::*;
 pub use resources::*;
 pub use texture::*;
 pub use view::*;
@@ -25,6 +29,7 @@ pub use lazy_app_handler::*;
 pub use layout::*;
 
 pub mod element;
+pub mod snapshot;
 pub mod wgpu_utils;
 
 #[macro_use]
@@ -131,6 +131,134 @@ impl From<[f32; 4]> for LineWidth {
     }
 }
 
+/// Radii of the corners of a rect, in logical pixels.
+///
+/// Radii larger than half of the width or height of the rect are clamped when drawn.
+#[derive(Debug, Clone, Copy, PartialEq, From)]
+pub enum CornerRadius {
+    /// All corners have the same radius.
+    Uniform(f32),
+    /// Corners have different radii.
+    PerCorner {
+        top_left: f32,
+        top_right: f32,
+        bottom_right: f32,
+        bottom_left: f32,
+    },
+}
+
+impl Default for CornerRadius {
+    fn default() -> Self {
+        Self::Uniform(0.)
+    }
+}
+
+impl CornerRadius {
+    /// In the order of top left, top right, bottom right and bottom left.
+    pub const fn to_array(self) -> [f32; 4] {
+        match self {
+            Self::Uniform(radius) => [radius, radius, radius, radius],
+            Self::PerCorner {
+                top_left,
+                top_right,
+                bottom_right,
+                bottom_left,
+            } => [top_left, top_right, bottom_right, bottom_left],
+        }
+    }
+
+    pub const fn scaled(self, scale: f32) -> Self {
+        let [top_left, top_right, bottom_right, bottom_left] = self.to_array();
+        Self::PerCorner {
+            top_left: top_left * scale,
+            top_right: top_right * scale,
+            bottom_right: bottom_right * scale,
+            bottom_left: bottom_left * scale,
+        }
+    }
+}
+
+impl From<[f32; 4]> for CornerRadius {
+    fn from([top_left, top_right, bottom_right, bottom_left]: [f32; 4]) -> Self {
+        Self::PerCorner {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        }
+    }
+}
+
+/// A drop shadow cast by a (rounded) rect, in logical pixels.
+///
+/// A glow is a shadow with no offset and a light color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxShadow {
+    pub offset: Vector2<f32>,
+    /// Twice the standard deviation of the Gaussian blur, like in CSS.
+    pub blur_radius: f32,
+    /// How much larger the shadow is than the rect on each side before blurring, can be negative.
+    pub spread: f32,
+    pub color: Rgba,
+}
+
+impl BoxShadow {
+    pub const fn new(color: Rgba) -> Self {
+        Self {
+            offset: vec2(0., 0.),
+            blur_radius: 0.,
+            spread: 0.,
+            color,
+        }
+    }
+
+    pub const fn with_offset(self, offset: Vector2<f32>) -> Self {
+        Self { offset, ..self }
+    }
+
+    pub const fn with_blur_radius(self, blur_radius: f32) -> Self {
+        Self {
+            blur_radius,
+            ..self
+        }
+    }
+
+    pub const fn with_spread(self, spread: f32) -> Self {
+        Self { spread, ..self }
+    }
+
+    pub const fn with_color(self, color: Rgba) -> Self {
+        Self { color, ..self }
+    }
+
+    pub const fn scaled(self, scale: f32) -> Self {
+        Self {
+            offset: vec2(self.offset.x * scale, self.offset.y * scale),
+            blur_radius: self.blur_radius * scale,
+            spread: self.spread * scale,
+            color: self.color,
+        }
+    }
+
+    /// Bounds of the shadow cast by a rect of `bounds`, before blurring.
+    pub const fn shadow_bounds(self, bounds: Bounds<f32>) -> Bounds<f32> {
+        let bounds = bounds.with_inset(-self.spread);
+        Bounds::from_scalars(
+            bounds.x_min() + self.offset.x,
+            bounds.y_min() + self.offset.y,
+            bounds.width(),
+            bounds.height(),
+        )
+    }
+
+    /// Bounds outside of which the shadow cast by a rect of `bounds` is invisible.
+    pub const fn visible_bounds(self, bounds: Bounds<f32>) -> Bounds<f32> {
+        // Three standard deviations.
+        self.shadow_bounds(bounds)
+            .with_inset(-1.5 * self.blur_radius - 1.)
+    }
+}
+
 pub fn linear_to_srgb(linear: f32) -> f32 {
     if linear <= 0.0031308 {
         linear * 12.92
@@ -410,6 +538,18 @@ impl Bounds<f32> {
             && point.y <= self.y_max_()
     }
 
+    /// Returns `None` if the two bounds do not overlap.
+    pub const fn intersection(self, other: Self) -> Option<Self> {
+        let x_min = self.x_min().max(other.x_min());
+        let y_min = self.y_min().max(other.y_min());
+        let x_max = self.x_max_().min(other.x_max_());
+        let y_max = self.y_max_().min(other.y_max_());
+        if x_max <= x_min || y_max <= y_min {
+            return None;
+        }
+        Some(Self::from_scalars(x_min, y_min, x_max - x_min, y_max - y_min))
+    }
+
     pub const fn with_inset(self, padding: f32) -> Self {
         Self::from_scalars(
             self.x_min() + padding,
@@ -0,0 +1,437 @@
+use cgmath::*;
+
+use crate::Rgba;
+
+/// A command of a `Path`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    MoveTo(Point2<f32>),
+    LineTo(Point2<f32>),
+    /// Quadratic Bézier curve with a control point.
+    QuadTo(Point2<f32>, Point2<f32>),
+    /// Cubic Bézier curve with two control points.
+    CubicTo(Point2<f32>, Point2<f32>, Point2<f32>),
+    /// Line back to the start of the subpath.
+    Close,
+}
+
+/// A vector path made of subpaths of lines and Bézier curves, in logical coordinates.
+///
+/// Drawn by `PathView`, which fills and/or strokes it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+    /// Start of the current subpath.
+    start: Option<Point2<f32>>,
+    current: Option<Point2<f32>>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The end point of the last segment.
+    pub fn current_point(&self) -> Option<Point2<f32>> {
+        self.current
+    }
+
+    /// Start a new subpath at `point`.
+    pub fn move_to(&mut self, point: Point2<f32>) -> &mut Self {
+        self.segments.push(PathSegment::MoveTo(point));
+        self.start = Some(point);
+        self.current = Some(point);
+        self
+    }
+
+    /// Same as `move_to` if there is no current point.
+    pub fn line_to(&mut self, point: Point2<f32>) -> &mut Self {
+        if self.current.is_none() {
+            return self.move_to(point);
+        }
+        self.segments.push(PathSegment::LineTo(point));
+        self.current = Some(point);
+        self
+    }
+
+    pub fn quad_to(&mut self, control: Point2<f32>, point: Point2<f32>) -> &mut Self {
+        if self.current.is_none() {
+            self.move_to(control);
+        }
+        self.segments.push(PathSegment::QuadTo(control, point));
+        self.current = Some(point);
+        self
+    }
+
+    pub fn cubic_to(
+        &mut self,
+        control_0: Point2<f32>,
+        control_1: Point2<f32>,
+        point: Point2<f32>,
+    ) -> &mut Self {
+        if self.current.is_none() {
+            self.move_to(control_0);
+        }
+        self.segments
+            .push(PathSegment::CubicTo(control_0, control_1, point));
+        self.current = Some(point);
+        self
+    }
+
+    /// Circular arc around `center`, from `start_angle` to `end_angle`.
+    ///
+    /// Angles are in radians, clockwise from the positive x axis. The arc goes clockwise if
+    /// `end_angle` is larger than `start_angle`, and counterclockwise otherwise. A line is added
+    /// from the current point to the start of the arc, if there is a current point.
+    pub fn arc(
+        &mut self,
+        center: Point2<f32>,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+    ) -> &mut Self {
+        let point_at = |angle: f32| center + radius * vec2(angle.cos(), angle.sin());
+        self.line_to(point_at(start_angle));
+        let sweep = end_angle - start_angle;
+        // Arcs up to a quarter circle are approximated by one cubic each.
+        let n_parts = (sweep.abs() / std::f32::consts::FRAC_PI_2).ceil().max(1.);
+        let part_sweep = sweep / n_parts;
+        let k = 4. / 3. * (part_sweep / 4.).tan();
+        for i in 0..n_parts as u32 {
+            let angle_0 = start_angle + i as f32 * part_sweep;
+            let angle_1 = angle_0 + part_sweep;
+            let tangent = |angle: f32| radius * vec2(-angle.sin(), angle.cos());
+            let point_0 = point_at(angle_0);
+            let point_1 = point_at(angle_1);
+            self.cubic_to(
+                point_0 + k * tangent(angle_0),
+                point_1 - k * tangent(angle_1),
+                point_1,
+            );
+        }
+        self
+    }
+
+    /// Close the current subpath with a line back to its start.
+    pub fn close(&mut self) -> &mut Self {
+        if self.current.is_some() {
+            self.segments.push(PathSegment::Close);
+            self.current = self.start;
+        }
+        self
+    }
+
+    /// Approximate the subpaths with polylines, each point being at most `tolerance` away from
+    /// the curves.
+    pub(crate) fn flatten(&self, tolerance: f32) -> Vec<Polyline> {
+        let mut polylines: Vec<Polyline> = Vec::new();
+        let mut current = point2(0., 0.);
+        for &segment in &self.segments {
+            match segment {
+                PathSegment::MoveTo(point) => {
+                    polylines.push(Polyline::new(point));
+                    current = point;
+                    continue;
+                }
+                PathSegment::Close => {
+                    if let Some(polyline) = polylines.last_mut() {
+                        polyline.closed = true;
+                        current = polyline.points[0];
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+            // Segments after a `Close` start a new subpath at the same point.
+            if polylines.last().is_none_or(|polyline| polyline.closed) {
+                polylines.push(Polyline::new(current));
+            }
+            let polyline = polylines.last_mut().unwrap();
+            match segment {
+                PathSegment::LineTo(point) => {
+                    polyline.push(point);
+                    current = point;
+                }
+                PathSegment::QuadTo(control, point) => {
+                    let deviation =
+                        (current.to_vec() - 2. * control.to_vec() + point.to_vec()).magnitude();
+                    let n = n_subdivisions(deviation / 4., tolerance);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let s = 1. - t;
+                        polyline.push(Point2::from_vec(
+                            s * s * current.to_vec()
+                                + 2. * s * t * control.to_vec()
+                                + t * t * point.to_vec(),
+                        ));
+                    }
+                    current = point;
+                }
+                PathSegment::CubicTo(control_0, control_1, point) => {
+                    let deviation = f32::max(
+                        (current.to_vec() - 2. * control_0.to_vec() + control_1.to_vec())
+                            .magnitude(),
+                        (control_0.to_vec() - 2. * control_1.to_vec() + point.to_vec()).magnitude(),
+                    );
+                    let n = n_subdivisions(0.75 * deviation, tolerance);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let s = 1. - t;
+                        polyline.push(Point2::from_vec(
+                            s * s * s * current.to_vec()
+                                + 3. * s * s * t * control_0.to_vec()
+                                + 3. * s * t * t * control_1.to_vec()
+                                + t * t * t * point.to_vec(),
+                        ));
+                    }
+                    current = point;
+                }
+                PathSegment::MoveTo(_) | PathSegment::Close => unreachable!(),
+            }
+        }
+        for polyline in &mut polylines {
+            if polyline.closed
+                && polyline.points.len() > 1
+                && polyline.points.first() == polyline.points.last()
+            {
+                polyline.points.pop();
+            }
+        }
+        polylines
+    }
+}
+
+/// Number of line segments for a curve of which the flattening error is `error / n^2` with `n`
+/// segments.
+fn n_subdivisions(error: f32, tolerance: f32) -> u32 {
+    let n = (error / tolerance.max(1e-4)).sqrt().ceil();
+    if n.is_nan() {
+        return 1;
+    }
+    (n as u32).clamp(1, 256)
+}
+
+/// A flattened subpath.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Polyline {
+    /// No two consecutive points are the same.
+    pub(crate) points: Vec<Point2<f32>>,
+    pub(crate) closed: bool,
+}
+
+impl Polyline {
+    fn new(start: Point2<f32>) -> Self {
+        Self {
+            points: vec![start],
+            closed: false,
+        }
+    }
+
+    fn push(&mut self, point: Point2<f32>) {
+        if self.points.last() != Some(&point) {
+            self.points.push(point);
+        }
+    }
+
+    /// Split into the dashes of `pattern` (lengths of alternating dashes and gaps), starting
+    /// `offset` into the pattern.
+    pub(crate) fn dashed(&self, pattern: &[f32], offset: f32) -> Vec<Polyline> {
+        let pattern_length: f32 = pattern.iter().sum();
+        if !(pattern_length > 0. && pattern.iter().all(|&length| length >= 0.)) {
+            return vec![self.clone()];
+        }
+        let mut points = self.points.clone();
+        if self.closed {
+            points.push(points[0]);
+        }
+        // Find where in the pattern the polyline starts.
+        let mut i_pattern = 0;
+        let mut remaining = pattern[0];
+        let mut offset = offset.rem_euclid(pattern_length);
+        while offset > 0. {
+            if offset < remaining {
+                remaining -= offset;
+                break;
+            }
+            offset -= remaining;
+            i_pattern = (i_pattern + 1) % pattern.len();
+            remaining = pattern[i_pattern];
+        }
+        let mut dashes = Vec::new();
+        let mut dash = (i_pattern % 2 == 0).then(|| Polyline::new(points[0]));
+        for window in points.windows(2) {
+            let (mut start, end) = (window[0], window[1]);
+            let mut segment_length = (end - start).magnitude();
+            while segment_length > remaining {
+                let split = start + (end - start) * (remaining / segment_length);
+                match dash.take() {
+                    Some(mut dash) => {
+                        dash.push(split);
+                        dashes.push(dash);
+                    }
+                    None => dash = Some(Polyline::new(split)),
+                }
+                segment_length -= remaining;
+                start = split;
+                i_pattern = (i_pattern + 1) % pattern.len();
+                remaining = pattern[i_pattern];
+            }
+            remaining -= segment_length;
+            if let Some(dash) = dash.as_mut() {
+                dash.push(end);
+            }
+        }
+        dashes.extend(dash);
+        dashes
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Falls back to `Bevel` if the miter is longer than `Stroke::miter_limit`.
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    /// Extends past the ends by half the width.
+    Square,
+    Round,
+}
+
+/// How the outline of a path is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub color: Rgba,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Max ratio of the miter length to the width for `LineJoin::Miter`.
+    pub miter_limit: f32,
+    /// Lengths of alternating dashes and gaps, or empty for a solid line.
+    ///
+    /// Caps are added to both ends of every dash.
+    pub dashes: Vec<f32>,
+    /// Distance into the dash pattern at which the stroke starts.
+    pub dash_offset: f32,
+}
+
+impl Stroke {
+    pub fn new(width: f32, color: impl Into<Rgba>) -> Self {
+        Self {
+            width,
+            color: color.into(),
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.,
+            dashes: Vec::new(),
+            dash_offset: 0.,
+        }
+    }
+
+    pub fn with_join(self, join: LineJoin) -> Self {
+        Self { join, ..self }
+    }
+
+    pub fn with_cap(self, cap: LineCap) -> Self {
+        Self { cap, ..self }
+    }
+
+    pub fn with_miter_limit(self, miter_limit: f32) -> Self {
+        Self {
+            miter_limit,
+            ..self
+        }
+    }
+
+    pub fn with_dashes(self, dashes: impl Into<Vec<f32>>) -> Self {
+        Self {
+            dashes: dashes.into(),
+            ..self
+        }
+    }
+
+    pub fn with_dash_offset(self, dash_offset: f32) -> Self {
+        Self {
+            dash_offset,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polyline(points: &[(f32, f32)], closed: bool) -> Polyline {
+        Polyline {
+            points: points.iter().map(|&(x, y)| point2(x, y)).collect(),
+            closed,
+        }
+    }
+
+    #[test]
+    fn flattens_closed_subpaths_without_repeating_the_start() {
+        let mut path = Path::new();
+        path.move_to(point2(0., 0.))
+            .line_to(point2(10., 0.))
+            .line_to(point2(10., 10.))
+            .line_to(point2(0., 0.))
+            .close()
+            .line_to(point2(0., 10.));
+        assert_eq!(
+            path.flatten(0.25),
+            [
+                polyline(&[(0., 0.), (10., 0.), (10., 10.)], true),
+                // Segments after `close` start a new subpath at the start of the closed one.
+                polyline(&[(0., 0.), (0., 10.)], false),
+            ],
+        );
+    }
+
+    #[test]
+    fn dashes_start_at_the_offset_into_the_pattern() {
+        let line = polyline(&[(0., 0.), (10., 0.)], false);
+        assert_eq!(
+            line.dashed(&[2., 1.], 1.),
+            [
+                polyline(&[(0., 0.), (1., 0.)], false),
+                polyline(&[(2., 0.), (4., 0.)], false),
+                polyline(&[(5., 0.), (7., 0.)], false),
+                polyline(&[(8., 0.), (10., 0.)], false),
+            ],
+        );
+        // Offsets are wrapped around the pattern length, also when negative.
+        assert_eq!(line.dashed(&[2., 1.], -2.), line.dashed(&[2., 1.], 1.));
+        // Starting in a gap.
+        assert_eq!(
+            line.dashed(&[2., 1.], 2.5)[0],
+            polyline(&[(0.5, 0.), (2.5, 0.)], false),
+        );
+    }
+
+    #[test]
+    fn dashes_of_closed_polylines_include_the_closing_edge() {
+        let square = polyline(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)], true);
+        assert_eq!(
+            square.dashed(&[15., 5.], 0.),
+            [
+                polyline(&[(0., 0.), (10., 0.), (10., 5.)], false),
+                polyline(&[(10., 10.), (0., 10.), (0., 5.)], false),
+            ],
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Golden-image snapshot testing of views.
+//!
+//! `Snapshots` renders views offscreen (see `UiContext::render_to_image`) with the software adapter,
+//! so that the results are the same across machines, and compares them against the reference PNGs
+//! in a directory. Snapshots are checked with `Snapshots::assert_snapshot` inside
+//! of normal tests, which then run with plain `cargo test`.
+//!
+//! When a snapshot differs from its reference `<name>.png`, the rendered image is written to
+//! `<name>.actual.png`, and an image highlighting the differing pixels in red to
+//! `<name>.diff.png`. Running the tests with the environment variable `MUILIB_BLESS_SNAPSHOTS=1`
+//! writes the rendered images as the new references instead.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use derive_more::{Display, Error};
+
+use crate::{
+    AlphaBlend, AppResources, CanvasFormat, ClearValues, ReadCanvasError, RectSize, Rgba,
+    UiContext, UiContextCreationError, View,
+};
+
+/// Environment variable that, if set to anything other than `0`, makes snapshots be written as the
+/// new references instead of being compared against them.
+pub const BLESS_ENV_VAR: &str = "MUILIB_BLESS_SNAPSHOTS";
+
+/// Whether `BLESS_ENV_VAR` is set.
+pub fn is_blessing() -> bool {
+    env::var_os(BLESS_ENV_VAR).is_some_and(|value| value != "0")
+}
+
+#[derive(Debug, Display, Error)]
+pub enum SnapshotError {
+    #[display("{_0}")]
+    Render(ReadCanvasError),
+    #[display("{_0}")]
+    Image(image::ImageError),
+    #[display("{_0}")]
+    Io(std::io::Error),
+    #[display(
+        "no reference snapshot at {}, run with {BLESS_ENV_VAR}=1 to create it",
+        path.display()
+    )]
+    MissingReference {
+        #[error(not(source))]
+        path: PathBuf,
+    },
+    #[display(
+        "snapshot is {actual_width}x{actual_height}, but the reference at {} is {expected_width}x{expected_height}",
+        path.display()
+    )]
+    SizeMismatch {
+        #[error(not(source))]
+        path: PathBuf,
+        expected_width: u32,
+        expected_height: u32,
+        actual_width: u32,
+        actual_height: u32,
+    },
+    #[display(
+        "{n_differing_pixels} pixels differ from the reference at {}, see {}",
+        path.display(),
+        diff_path.display()
+    )]
+    Mismatch {
+        #[error(not(source))]
+        path: PathBuf,
+        diff_path: PathBuf,
+        n_differing_pixels: usize,
+    },
+}
+
+impl From<ReadCanvasError> for SnapshotError {
+    fn from(error: ReadCanvasError) -> Self {
+        Self::Render(error)
+    }
+}
+
+impl From<image::ImageError> for SnapshotError {
+    fn from(error: image::ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Renders views offscreen and compares them against the reference PNGs in a directory.
+pub struct Snapshots<'cx> {
+    ui_context: UiContext<'cx>,
+    directory: PathBuf,
+    tolerance: u8,
+    scale_factor: f32,
+    clear_values: ClearValues,
+}
+
+impl<'cx> Snapshots<'cx> {
+    /// Format of the canvases snapshots are rendered onto.
+    pub const CANVAS_FORMAT: CanvasFormat = CanvasFormat {
+        color_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        depth_stencil_format: Some(wgpu::TextureFormat::Depth24PlusStencil8),
+        sample_count: 1,
+        alpha_blend: AlphaBlend::Over,
+    };
+
+    /// Snapshots with references in `directory`, compared with a tolerance of 2, at a scale factor
+    /// of 1, on a transparent background.
+    ///
+    /// Fails if there is no software adapter (see `UiContext::create_headless_software`).
+    pub fn new(
+        resources: &'cx AppResources,
+        directory: impl Into<PathBuf>,
+    ) -> Result<Self, UiContextCreationError> {
+        let ui_context = UiContext::create_headless_software(resources, Self::CANVAS_FORMAT)?;
+        Ok(Self::from_ui_context(ui_context, directory.into()))
+    }
+
+    /// Like `new`, but falls back to a hardware adapter if there is no software adapter, in which
+    /// case the snapshots may differ from references rendered on other machines.
+    pub fn new_with_hardware_fallback(
+        resources: &'cx AppResources,
+        directory: impl Into<PathBuf>,
+    ) -> Result<Self, UiContextCreationError> {
+        let ui_context =
+            UiContext::create_headless_software_or_hardware(resources, Self::CANVAS_FORMAT)?;
+        Ok(Self::from_ui_context(ui_context, directory.into()))
+    }
+
+    fn from_ui_context(ui_context: UiContext<'cx>, directory: PathBuf) -> Self {
+        Self {
+            ui_context,
+            directory,
+            tolerance: 2,
+            scale_factor: 1.,
+            clear_values: ClearValues::new(Rgba::from_hex(0x00000000)),
+        }
+    }
+
+    /// Max difference of each channel of a pixel from the reference for the pixel to be the
+    /// same.
+    pub fn with_tolerance(self, tolerance: u8) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    pub fn with_scale_factor(self, scale_factor: f32) -> Self {
+        Self {
+            scale_factor,
+            ..self
+        }
+    }
+
+    pub fn with_clear_values(self, clear_values: impl Into<ClearValues>) -> Self {
+        Self {
+            clear_values: clear_values.into(),
+            ..self
+        }
+    }
+
+    /// The `UiContext` views are drawn with, e.g. for creating `TextView`s.
+    pub fn ui_context(&self) -> &UiContext<'cx> {
+        &self.ui_context
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Render `view` onto a canvas of `physical_size` and compare it against the reference
+    /// `<name>.png`, or write it as the reference if blessing (see `BLESS_ENV_VAR`).
+    pub fn check(
+        &self,
+        name: &str,
+        view: &mut dyn View<'cx>,
+        physical_size: RectSize<u32>,
+    ) -> Result<(), SnapshotError> {
+        let actual = self.ui_context.render_to_image(
+            view,
+            physical_size,
+            self.scale_factor,
+            self.clear_values,
+        )?;
+        let path = self.directory.join(format!("{name}.png"));
+        let actual_path = self.directory.join(format!("{name}.actual.png"));
+        let diff_path = self.directory.join(format!("{name}.diff.png"));
+        if is_blessing() {
+            fs::create_dir_all(&self.directory)?;
+            actual.save(&path)?;
+            remove_if_exists(&actual_path)?;
+            remove_if_exists(&diff_path)?;
+            return Ok(());
+        }
+        if !path.exists() {
+            return Err(SnapshotError::MissingReference { path });
+        }
+        let expected = image::open(&path)?.into_rgba8();
+        if expected.dimensions() != actual.dimensions() {
+            actual.save(&actual_path)?;
+            return Err(SnapshotError::SizeMismatch {
+                path,
+                expected_width: expected.width(),
+                expected_height: expected.height(),
+                actual_width: actual.width(),
+                actual_height: actual.height(),
+            });
+        }
+        let (diff, n_differing_pixels) = diff_images(&expected, &actual, self.tolerance);
+        if n_differing_pixels == 0 {
+            remove_if_exists(&actual_path)?;
+            remove_if_exists(&diff_path)?;
+            return Ok(());
+        }
+        actual.save(&actual_path)?;
+        diff.save(&diff_path)?;
+        Err(SnapshotError::Mismatch {
+            path,
+            diff_path,
+            n_differing_pixels,
+        })
+    }
+
+    /// Like `check`, but panics if the snapshot differs from the reference.
+    #[track_caller]
+    pub fn assert_snapshot(
+        &self,
+        name: &str,
+        view: &mut dyn View<'cx>,
+        physical_size: RectSize<u32>,
+    ) {
+        if let Err(error) = self.check(name, view, physical_size) {
+            panic!("snapshot {name:?} failed: {error}");
+        }
+    }
+}
+
+/// Compare `actual` against `expected` of the same size, returning an image with the differing
+/// pixels in red over a faded `expected`, and the number of differing pixels.
+fn diff_images(
+    expected: &image::RgbaImage,
+    actual: &image::RgbaImage,
+    tolerance: u8,
+) -> (image::RgbaImage, usize) {
+    let mut n_differing_pixels = 0;
+    let diff = image::RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let expected_pixel = expected.get_pixel(x, y);
+        let actual_pixel = actual.get_pixel(x, y);
+        let differs = (expected_pixel.0.iter())
+            .zip(actual_pixel.0)
+            .any(|(&a, b)| a.abs_diff(b) > tolerance);
+        if differs {
+            n_differing_pixels += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, a] = expected_pixel.0;
+            let fade = |channel: u8| 192 + channel / 4;
+            image::Rgba([fade(r), fade(g), fade(b), a.max(64)])
+        }
+    });
+    (diff, n_differing_pixels)
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
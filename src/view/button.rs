@@ -14,8 +14,9 @@ use cgmath::*;
 use winit::event::MouseButton;
 
 use crate::{
-    Bounds, CanvasRef, EventRouter, LineWidth, ListenerHandle, MouseEvent, MouseEventKind,
-    MouseEventListener, RectSize, RectView, RenderPass, Srgb, Srgba, TextView, UiContext, View,
+    Bounds, BoxShadow, CanvasRef, Constraints, CornerRadius, EventRouter, Fill, ImageView,
+    LineWidth, ListenerHandle, MouseEvent, MouseEventKind, MouseEventListener, NineSlice, RectSize,
+    RectView, RenderPass, Srgb, Srgba, TextView, Texture2d, UiContext, View,
     utils::AtomicBoolExt as _,
 };
 
@@ -69,7 +70,11 @@ impl From<ButtonState> for AtomicButtonState {
 #[derive(Debug, Clone, Copy)]
 pub struct ButtonStyle {
     pub line_width: LineWidth,
+    pub corner_radius: CornerRadius,
     pub font_size: f32,
+    /// Insets of the background images of the button (see `ButtonBackgroundImages`), `None` for
+    /// stretching the whole images.
+    pub nine_slice: Option<NineSlice>,
     pub idle_style: ButtonStateStyle,
     pub hovered_style: ButtonStateStyle,
     pub pressed_style: ButtonStateStyle,
@@ -92,10 +97,24 @@ impl ButtonStyle {
         }
     }
 
+    pub fn with_corner_radius(self, corner_radius: impl Into<CornerRadius>) -> Self {
+        Self {
+            corner_radius: corner_radius.into(),
+            ..self
+        }
+    }
+
     pub fn with_font_size(self, font_size: f32) -> Self {
         Self { font_size, ..self }
     }
 
+    pub fn with_nine_slice(self, nine_slice: impl Into<Option<NineSlice>>) -> Self {
+        Self {
+            nine_slice: nine_slice.into(),
+            ..self
+        }
+    }
+
     pub fn scaled(self, scale: f32) -> ButtonStyle {
         Self {
             line_width: match self.line_width {
@@ -112,8 +131,12 @@ impl ButtonStyle {
                     bottom: bottom * scale,
                 },
             },
+            corner_radius: self.corner_radius.scaled(scale),
             font_size: self.font_size * scale,
-            ..self
+            nine_slice: self.nine_slice,
+            idle_style: self.idle_style.scaled(scale),
+            hovered_style: self.hovered_style.scaled(scale),
+            pressed_style: self.pressed_style.scaled(scale),
         }
     }
 }
@@ -122,8 +145,41 @@ impl ButtonStyle {
 #[derive(Debug, Clone, Copy)]
 pub struct ButtonStateStyle {
     pub text_color: Srgb,
-    pub fill_color: Srgb,
+    pub fill_color: Fill,
     pub line_color: Srgb,
+    pub shadow: Option<BoxShadow>,
+}
+
+impl ButtonStateStyle {
+    pub fn scaled(self, scale: f32) -> Self {
+        Self {
+            shadow: self.shadow.map(|shadow| shadow.scaled(scale)),
+            ..self
+        }
+    }
+}
+
+/// Background images of a `ButtonView` for all `ButtonState`s, drawn as nine-slice images if
+/// `ButtonStyle::nine_slice` is set.
+///
+/// The images are drawn over the fill and the line of the button, and are not clipped to its corner
+/// radius, so they would usually come with their own borders and corners.
+#[derive(Debug, Clone, Default)]
+pub struct ButtonBackgroundImages {
+    pub idle_image: Option<Texture2d>,
+    pub hovered_image: Option<Texture2d>,
+    pub pressed_image: Option<Texture2d>,
+}
+
+impl ButtonBackgroundImages {
+    pub fn image_for(&self, state: ButtonState) -> Option<&Texture2d> {
+        match state {
+            ButtonState::Idle => self.idle_image.as_ref(),
+            ButtonState::Hovered => self.hovered_image.as_ref(),
+            ButtonState::PressedOutside => self.hovered_image.as_ref(),
+            ButtonState::Pressed => self.pressed_image.as_ref(),
+        }
+    }
 }
 
 pub type ButtonCallback<UiState> = fn(&mut UiState, ButtonEvent);
@@ -131,8 +187,10 @@ pub type ButtonCallback<UiState> = fn(&mut UiState, ButtonEvent);
 /// `ButtonView` takes a type parameter `UiState` because it contains a button callback.
 pub struct ButtonView<'cx, UiState> {
     rect_view: RectView,
+    image_view: ImageView,
     text_view: TextView<'cx>,
     style: ButtonStyle,
+    background_images: ButtonBackgroundImages,
     dispatch: Arc<ButtonDispatch<UiState>>,
     listener_handle: ListenerHandle,
 }
@@ -150,8 +208,10 @@ impl<'cx, UiState> ButtonView<'cx, UiState> {
         let listener_handle = event_router.register_listener(Bounds::default(), dispatch.clone());
         Self {
             rect_view: RectView::new(Self::DEFAULT_SIZE),
+            image_view: ImageView::new(Self::DEFAULT_SIZE),
             text_view: TextView::new(ui_context).with_text("Button"),
             style: Self::DEFAULT_STYLE,
+            background_images: ButtonBackgroundImages::default(),
             dispatch,
             listener_handle,
         }
@@ -179,21 +239,26 @@ impl<'cx, UiState> ButtonView<'cx, UiState> {
 
     const DEFAULT_STYLE: ButtonStyle = ButtonStyle {
         line_width: LineWidth::Uniform(1.),
+        corner_radius: CornerRadius::Uniform(0.),
         font_size: 12.,
+        nine_slice: None,
         idle_style: ButtonStateStyle {
             text_color: Srgb::from_hex(0xFFFFFF),
-            fill_color: Srgb::from_hex(0x2A2A2A),
+            fill_color: Fill::Solid(Srgba::from_hex(0x2A2A2AFF)),
             line_color: Srgb::from_hex(0x494949),
+            shadow: None,
         },
         hovered_style: ButtonStateStyle {
             text_color: Srgb::from_hex(0xFFFFFF),
-            fill_color: Srgb::from_hex(0x424242),
+            fill_color: Fill::Solid(Srgba::from_hex(0x424242FF)),
             line_color: Srgb::from_hex(0xA2A2A2),
+            shadow: None,
         },
         pressed_style: ButtonStateStyle {
             text_color: Srgb::from_hex(0xFFFFFF),
-            fill_color: Srgb::from_hex(0xA2A2A2),
+            fill_color: Fill::Solid(Srgba::from_hex(0xA2A2A2FF)),
             line_color: Srgb::from_hex(0xA2A2A2),
+            shadow: None,
         },
     };
 
@@ -224,6 +289,20 @@ impl<'cx, UiState> ButtonView<'cx, UiState> {
         self
     }
 
+    pub fn background_images(&self) -> &ButtonBackgroundImages {
+        &self.background_images
+    }
+
+    pub fn set_background_images(&mut self, background_images: ButtonBackgroundImages) {
+        self.background_images = background_images;
+        self.update_styles();
+    }
+
+    pub fn with_background_images(mut self, background_images: ButtonBackgroundImages) -> Self {
+        self.set_background_images(background_images);
+        self
+    }
+
     pub fn set_title(&mut self, title: impl Into<Cow<'cx, str>>) {
         self.text_view.set_text(title);
         self.relayout_text();
@@ -248,6 +327,11 @@ impl<'cx, UiState> ButtonView<'cx, UiState> {
         self.rect_view.set_fill_color(state_style.fill_color);
         self.rect_view.set_line_color(state_style.line_color);
         self.rect_view.set_line_width(style.line_width);
+        self.rect_view.set_corner_radius(style.corner_radius);
+        self.rect_view.set_shadow(state_style.shadow);
+        self.image_view
+            .set_texture(self.background_images.image_for(self.state()).cloned());
+        self.image_view.set_nine_slice(style.nine_slice);
         if self.text_view.font_size() != style.font_size {
             self.relayout_text();
         }
@@ -257,8 +341,10 @@ impl<'cx, UiState> ButtonView<'cx, UiState> {
     }
 
     fn relayout_text(&mut self) {
-        let text_size = self.text_view.size();
         let rect_bounds = self.rect_view.bounds();
+        let text_size = self
+            .text_view
+            .size_that_fits(Constraints::loose(rect_bounds.size));
         let origin = point2(
             rect_bounds.x_min() + 0.5 * (rect_bounds.width() - text_size.width).max(0.),
             rect_bounds.y_min() + 0.5 * (rect_bounds.height() - text_size.height).max(0.),
@@ -275,8 +361,16 @@ impl<'cx, UiState: 'cx> View<'cx> for ButtonView<'cx, UiState> {
         self.size()
     }
 
+    fn is_dirty(&self) -> bool {
+        self.dispatch.state_updated.load(Acquire)
+            || self.rect_view.is_dirty()
+            || self.image_view.is_dirty()
+            || self.text_view.is_dirty()
+    }
+
     fn apply_bounds(&mut self, bounds: Bounds<f32>) {
         self.rect_view.apply_bounds(bounds);
+        self.image_view.apply_bounds(bounds);
         self.relayout_text();
         self.listener_handle.update_bounds(self.rect_view.bounds());
     }
@@ -287,11 +381,13 @@ impl<'cx, UiState: 'cx> View<'cx> for ButtonView<'cx, UiState> {
             self.update_styles();
         }
         self.rect_view.prepare_for_drawing(ui_context, canvas);
+        self.image_view.prepare_for_drawing(ui_context, canvas);
         self.text_view.prepare_for_drawing(ui_context, canvas);
     }
 
     fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
         self.rect_view.draw(ui_context, render_pass);
+        self.image_view.draw(ui_context, render_pass);
         self.text_view.draw(ui_context, render_pass);
     }
 }
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use crate::{
+    Bounds, Texture2d,
+    element::{
+        GlyphInstance, ImageElement, ImageInstance, PathElement, RectElement, RectInstance,
+        ShadowElement, StencilMaskOp,
+    },
+};
+
+/// A primitive emitted by a view in `View::draw`.
+#[derive(Debug, Clone)]
+pub enum DisplayItem {
+    /// Batched with other rects, drawn by `InstancedRectRenderer`.
+    Rect(RectInstance),
+    /// Glyphs of a text, batched with other glyphs, drawn by `InstancedTextRenderer`.
+    Glyphs(Arc<[GlyphInstance]>),
+    /// Batched with other images of the same texture, drawn by `InstancedImageRenderer`.
+    Image(Texture2d, ImageInstance),
+    /// Drawn on its own by `RectRenderer`, e.g. for rects with gradient fills.
+    RectElement(RectElement),
+    /// Drawn on its own by `ImageRenderer`, e.g. for nine-slice images.
+    ImageElement(ImageElement),
+    /// Drawn on its own by `ShadowRenderer`.
+    Shadow(ShadowElement),
+    /// Drawn on its own by `PathRenderer`.
+    Path(PathElement),
+    /// Pushed by `RenderPass::{push|pop}_stencil_mask`, drawn on its own into the stencil buffer by
+    /// `InstancedRectRenderer`.
+    StencilMask(RectInstance, StencilMaskOp),
+}
+
+impl DisplayItem {
+    fn batch_kind(&self) -> BatchKind {
+        match self {
+            DisplayItem::Rect(_) => BatchKind::Rects,
+            DisplayItem::Glyphs(_) => BatchKind::Glyphs,
+            DisplayItem::Image(texture, _) => {
+                BatchKind::Images(texture.wgpu_texture_view().clone())
+            }
+            DisplayItem::StencilMask(_, op) => BatchKind::StencilMask(*op),
+            DisplayItem::RectElement(_)
+            | DisplayItem::ImageElement(_)
+            | DisplayItem::Shadow(_)
+            | DisplayItem::Path(_) => BatchKind::Unbatched,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DisplayEntry {
+    pub(crate) item: DisplayItem,
+    /// Bounds of the item after clipping.
+    pub(crate) bounds: Bounds<f32>,
+    pub(crate) clip_rect: Option<Bounds<f32>>,
+    pub(crate) stencil_reference: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BatchKind {
+    Rects,
+    Glyphs,
+    Images(wgpu::TextureView),
+    StencilMask(StencilMaskOp),
+    Unbatched,
+}
+
+impl BatchKind {
+    /// Whether later entries can be merged into a batch of this kind.
+    fn is_mergeable(&self) -> bool {
+        !matches!(self, BatchKind::StencilMask(_) | BatchKind::Unbatched)
+    }
+}
+
+/// Entries drawn together in one draw call.
+#[derive(Debug, Clone)]
+pub(crate) struct Batch {
+    pub(crate) kind: BatchKind,
+    pub(crate) clip_rect: Option<Bounds<f32>>,
+    pub(crate) stencil_reference: u32,
+    /// Union of the bounds of the entries.
+    bounds: Bounds<f32>,
+    /// Indices of the entries, in painter's order.
+    pub(crate) entries: Vec<usize>,
+}
+
+impl Batch {
+    fn overlaps(&self, bounds: Bounds<f32>) -> bool {
+        self.bounds.intersection(bounds).is_some()
+    }
+
+    fn push(&mut self, index: usize, bounds: Bounds<f32>) {
+        self.entries.push(index);
+        self.bounds = union(self.bounds, bounds);
+    }
+}
+
+fn union(bounds: Bounds<f32>, other: Bounds<f32>) -> Bounds<f32> {
+    let x_min = bounds.x_min().min(other.x_min());
+    let y_min = bounds.y_min().min(other.y_min());
+    let x_max = bounds.x_max().max(other.x_max());
+    let y_max = bounds.y_max().max(other.y_max());
+    Bounds::from_scalars(x_min, y_min, x_max - x_min, y_max - y_min)
+}
+
+/// Primitives emitted by views in one frame, in painter's order.
+///
+/// Views push items into the display list of the `RenderPass` in `View::draw`, and
+/// `UiContext::flush_display_list` (called by `UiContext::draw_view`) merges them into batches of
+/// instanced draws.
+///
+/// An item is merged into an earlier batch of the same kind only if it does not overlap anything
+/// drawn after that batch, so that the result looks the same as drawing the items one by one.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayList {
+    entries: Vec<DisplayEntry>,
+    clip_rect: Option<Bounds<f32>>,
+    stencil_reference: u32,
+}
+
+impl DisplayList {
+    /// Max number of batches searched backwards for a batch an item can be merged into.
+    const MAX_LOOKBACK: usize = 16;
+
+    /// Push an item drawn inside of `bounds`.
+    ///
+    /// `bounds` must cover everything the item draws, as it decides whether the item can be
+    /// reordered with other items.
+    pub fn push(&mut self, item: DisplayItem, bounds: Bounds<f32>) {
+        let bounds = match self.clip_rect {
+            Some(clip_rect) => clip_rect.intersection(bounds),
+            None => Some(bounds),
+        };
+        // Clipped entirely.
+        let Some(bounds) = bounds else {
+            return;
+        };
+        self.entries.push(DisplayEntry {
+            item,
+            bounds,
+            clip_rect: self.clip_rect,
+            stencil_reference: self.stencil_reference,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn entries(&self) -> &[DisplayEntry] {
+        &self.entries
+    }
+
+    /// Items pushed after this are clipped to `clip_rect`.
+    pub(crate) fn set_clip_rect(&mut self, clip_rect: Option<Bounds<f32>>) {
+        self.clip_rect = clip_rect;
+    }
+
+    /// Items pushed after this are drawn with `stencil_reference`.
+    pub(crate) fn set_stencil_reference(&mut self, stencil_reference: u32) {
+        self.stencil_reference = stencil_reference;
+    }
+
+    /// Take the items pushed so far, leaving the clip rect and the stencil reference.
+    pub(crate) fn take(&mut self) -> Self {
+        Self {
+            entries: std::mem::take(&mut self.entries),
+            clip_rect: self.clip_rect,
+            stencil_reference: self.stencil_reference,
+        }
+    }
+
+    /// Group the entries into batches, in the order the batches are to be drawn.
+    pub(crate) fn batches(&self) -> Vec<Batch> {
+        let mut batches: Vec<Batch> = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let kind = entry.item.batch_kind();
+            let mut target = None;
+            if kind.is_mergeable() {
+                for (i, batch) in batches.iter().enumerate().rev().take(Self::MAX_LOOKBACK) {
+                    if batch.kind == kind
+                        && batch.clip_rect == entry.clip_rect
+                        && batch.stencil_reference == entry.stencil_reference
+                    {
+                        target = Some(i);
+                        break;
+                    }
+                    if batch.overlaps(entry.bounds) {
+                        break;
+                    }
+                }
+            }
+            match target {
+                Some(i) => batches[i].push(index, entry.bounds),
+                None => batches.push(Batch {
+                    kind,
+                    clip_rect: entry.clip_rect,
+                    stencil_reference: entry.stencil_reference,
+                    bounds: entry.bounds,
+                    entries: vec![index],
+                }),
+            }
+        }
+        batches
+    }
+}
@@ -1,15 +1,107 @@
+use cgmath::*;
+
 use crate::{
-    Bounds, CanvasRef, RectSize, RenderPass, Texture2d, UiContext, View, element::ImageElement,
-    property, utils::*,
+    Bounds, CanvasRef, Constraints, DisplayItem, LayoutDebugKind, LayoutDebugOverlay, RectSize,
+    RenderPass, Rgba, Texture2d, UiContext, View,
+    element::{ImageElement, ImageInstance},
+    property,
+    utils::*,
 };
 
+/// Insets (in texture pixels) of the corners of a nine-slice image.
+///
+/// The corners are drawn unstretched at one logical pixel per texture pixel, the edges are
+/// stretched along their lengths, and the centre is stretched in both directions.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NineSlice {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl NineSlice {
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Same insets on all four sides.
+    pub const fn uniform(inset: f32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+
+    pub const fn to_array(self) -> [f32; 4] {
+        [self.left, self.top, self.right, self.bottom]
+    }
+}
+
+/// How the texture of an `ImageView` is laid out inside of its bounds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentMode {
+    /// Stretched to the bounds, ignoring the aspect ratio.
+    #[default]
+    Stretch,
+    /// Scaled to fit inside of the bounds, keeping the aspect ratio, and centered.
+    Fit,
+    /// Scaled to cover the bounds, keeping the aspect ratio, centered, and cropped.
+    Fill,
+    /// Unscaled at one logical pixel per texture pixel, centered, and cropped if larger than the
+    /// bounds.
+    Center,
+    /// Unscaled, and repeated from the top left corner to cover the bounds.
+    Tile,
+}
+
+impl ContentMode {
+    /// Bounds of content of `content_size` laid out inside of `size`, relative to the origin of
+    /// `size`.
+    ///
+    /// For `Tile`, it is the bounds of the first tile.
+    pub fn content_bounds(self, content_size: RectSize<f32>, size: RectSize<f32>) -> Bounds<f32> {
+        let scale = match self {
+            ContentMode::Stretch => return Bounds::new(point2(0., 0.), size),
+            ContentMode::Tile => return Bounds::new(point2(0., 0.), content_size),
+            ContentMode::Center => 1.,
+            ContentMode::Fit => {
+                (size.width / content_size.width).min(size.height / content_size.height)
+            }
+            ContentMode::Fill => {
+                (size.width / content_size.width).max(size.height / content_size.height)
+            }
+        };
+        let scaled_size = content_size.scaled(scale, scale);
+        let origin = point2(
+            0.5 * (size.width - scaled_size.width),
+            0.5 * (size.height - scaled_size.height),
+        );
+        Bounds::new(origin, scaled_size)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ImageView {
     size: RectSize<f32>,
     bounds: Bounds<f32>,
     bounds_updated: bool,
+    size_updated: bool,
     texture: Option<Texture2d>,
     texture_updated: bool,
+    nine_slice: Option<NineSlice>,
+    content_mode: ContentMode,
+    /// The part of the texture (in texture pixels) that is drawn, e.g. a sprite in an atlas, or
+    /// `None` for the whole texture.
+    source_rect: Option<Bounds<f32>>,
+    /// Multiplied with the colors of the texture.
+    tint: Rgba,
+    opacity: f32,
+    parameters_updated: bool,
+    /// Only created for images that `ImageInstance` cannot draw, e.g. nine-slice images and images
+    /// with tints.
     raw: Option<ImageElement>,
 }
 
@@ -19,8 +111,15 @@ impl ImageView {
             size,
             bounds: the_default(),
             bounds_updated: false,
+            size_updated: true,
             texture: None,
             texture_updated: false,
+            nine_slice: None,
+            content_mode: ContentMode::Stretch,
+            source_rect: None,
+            tint: Rgba::from_hex(0xFFFFFFFF),
+            opacity: 1.,
+            parameters_updated: false,
             raw: None,
         }
     }
@@ -32,7 +131,62 @@ impl ImageView {
         param_mut: size_mut,
         set_param: set_size,
         with_param: with_size,
-        param_mut_preamble: |_: &mut Self| {},
+        param_mut_preamble: |self_: &mut Self| self_.size_updated = true,
+    }
+
+    property! {
+        vis: pub,
+        param_ty: Option<NineSlice>,
+        param: nine_slice,
+        param_mut: nine_slice_mut,
+        set_param: set_nine_slice,
+        with_param: with_nine_slice,
+        param_mut_preamble: |self_: &mut Self| self_.parameters_updated = true,
+    }
+
+    property! {
+        vis: pub,
+        param_ty: ContentMode,
+        param: content_mode,
+        param_mut: content_mode_mut,
+        set_param: set_content_mode,
+        with_param: with_content_mode,
+        param_mut_preamble: |self_: &mut Self| self_.parameters_updated = true,
+    }
+
+    property! {
+        vis: pub,
+        param_ty: Option<Bounds<f32>>,
+        param: source_rect,
+        param_mut: source_rect_mut,
+        set_param: set_source_rect,
+        with_param: with_source_rect,
+        param_mut_preamble: |self_: &mut Self| self_.parameters_updated = true,
+    }
+
+    property! {
+        vis: pub,
+        param_ty: Rgba,
+        param: tint,
+        param_mut: tint_mut,
+        set_param: set_tint,
+        with_param: with_tint,
+        param_mut_preamble: |self_: &mut Self| self_.parameters_updated = true,
+    }
+
+    property! {
+        vis: pub,
+        param_ty: f32,
+        param: opacity,
+        param_mut: opacity_mut,
+        set_param: set_opacity,
+        with_param: with_opacity,
+        param_mut_preamble: |self_: &mut Self| self_.parameters_updated = true,
+    }
+
+    /// The bounds from the last layout.
+    pub fn bounds(&self) -> Bounds<f32> {
+        self.bounds
     }
 
     pub fn texture(&self) -> Option<&Texture2d> {
@@ -53,12 +207,71 @@ impl ImageView {
         self
     }
 
-    /// Set the preferred size to size of the texture.
+    /// Set the preferred size to size of the texture, or of the source rect if there is one.
     pub fn resize_to_fit(&mut self) {
         if let Some(texture) = self.texture.as_ref() {
-            self.set_size(texture.size_f());
+            self.set_size(self.source_rect_in(texture).size);
         }
     }
+
+    fn source_rect_in(&self, texture: &Texture2d) -> Bounds<f32> {
+        self.source_rect
+            .unwrap_or(Bounds::new(point2(0., 0.), texture.size_f()))
+    }
+
+    /// Whether the image can only be drawn by `ImageElement`.
+    fn needs_raw(&self) -> bool {
+        self.nine_slice.is_some()
+            || self.content_mode != ContentMode::Stretch
+            || self.source_rect.is_some()
+            || self.tint != Rgba::from_hex(0xFFFFFFFF)
+            || self.opacity != 1.
+    }
+
+    fn update_raw(&self, ui_context: &UiContext) {
+        let (Some(raw), Some(texture)) = (self.raw.as_ref(), self.texture.as_ref()) else {
+            return;
+        };
+        let queue = ui_context.wgpu_queue();
+        let texture_size = texture.size_f();
+        let size = self.bounds.size;
+        let source_rect = self.source_rect_in(texture);
+        let content_bounds = self.content_mode.content_bounds(source_rect.size, size);
+        let normalized = |x: f32, length: f32| if length > 0. { x / length } else { 0. };
+        raw.set_parameters(queue, self.bounds);
+        raw.set_source_rect(
+            queue,
+            Bounds::from_scalars(
+                normalized(source_rect.x_min(), texture_size.width),
+                normalized(source_rect.y_min(), texture_size.height),
+                normalized(source_rect.width(), texture_size.width),
+                normalized(source_rect.height(), texture_size.height),
+            ),
+        );
+        raw.set_content_bounds(
+            queue,
+            Bounds::from_scalars(
+                normalized(content_bounds.x_min(), size.width),
+                normalized(content_bounds.y_min(), size.height),
+                normalized(content_bounds.width(), size.width),
+                normalized(content_bounds.height(), size.height),
+            ),
+            self.content_mode == ContentMode::Tile,
+        );
+        raw.set_nine_slice(
+            queue,
+            self.nine_slice,
+            source_rect.size,
+            content_bounds.size,
+        );
+        raw.set_tint(
+            queue,
+            Rgba {
+                a: self.tint.a * self.opacity,
+                ..self.tint
+            },
+        );
+    }
 }
 
 impl<'cx> View<'cx> for ImageView {
@@ -66,34 +279,70 @@ impl<'cx> View<'cx> for ImageView {
         self.size
     }
 
+    /// Scales the preferred size uniformly to fit the constraints, so the aspect ratio is kept
+    /// where possible.
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        let size = self.size;
+        if !(size.width > 0. && size.height > 0.) {
+            return constraints.constrain(size);
+        }
+        let scale_max =
+            (constraints.max.width / size.width).min(constraints.max.height / size.height);
+        let scale_min =
+            (constraints.min.width / size.width).max(constraints.min.height / size.height);
+        let scale = scale_min.max(scale_max.min(1.));
+        constraints.constrain(size.scaled(scale, scale))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.size_updated || self.bounds_updated || self.texture_updated || self.parameters_updated
+    }
+
     fn apply_bounds(&mut self, bounds: Bounds<f32>) {
-        self.bounds = bounds;
-        self.bounds_updated = true
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::View);
+        if bounds != self.bounds {
+            self.bounds = bounds;
+            self.bounds_updated = true;
+        }
     }
 
     fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, _canvas: &CanvasRef) {
-        if (self.texture_updated || self.raw.is_none())
-            && let Some(texture) = self.texture.as_ref()
-        {
-            self.raw = Some(
-                ui_context
-                    .image_renderer()
-                    .create_image(ui_context.wgpu_device(), texture),
-            );
+        self.size_updated = false;
+        if self.texture_updated {
+            self.raw = None;
         }
-        if self.bounds_updated
-            && let Some(raw) = self.raw.as_ref()
+        if let Some(texture) = self.texture.as_ref()
+            && self.needs_raw()
+            && (self.raw.is_none() || self.bounds_updated || self.parameters_updated)
         {
-            self.bounds_updated = false;
-            raw.set_parameters(ui_context.wgpu_queue(), self.bounds);
+            if self.raw.is_none() {
+                self.raw = Some(
+                    ui_context
+                        .image_renderer()
+                        .create_image(ui_context.wgpu_device(), texture),
+                );
+            }
+            self.update_raw(ui_context);
         }
+        self.texture_updated = false;
+        self.bounds_updated = false;
+        self.parameters_updated = false;
     }
 
-    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
-        if let Some(raw) = self.raw.as_ref() {
-            ui_context
-                .image_renderer()
-                .draw_image(render_pass.wgpu_render_pass(), raw);
-        }
+    fn draw(&self, _ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        let Some(texture) = self.texture.as_ref() else {
+            return;
+        };
+        let item = match (self.needs_raw(), self.raw.as_ref()) {
+            (false, _) => {
+                DisplayItem::Image(texture.clone(), ImageInstance::from_bounds(self.bounds))
+            }
+            (true, Some(raw)) => DisplayItem::ImageElement(raw.clone()),
+            (true, None) => {
+                log::warn!("`<ImageView as View>::draw` is called without `prepare_for_drawing`");
+                return;
+            }
+        };
+        render_pass.display_list_mut().push(item, self.bounds);
     }
 }
@@ -0,0 +1,292 @@
+use std::{
+    cell::RefCell,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::{
+    Bounds, LineWidth, RenderPass, Rgba, Srgba,
+    element::{InstancedRectsElement, RectInstance},
+};
+
+use super::UiContext;
+
+/// What a region recorded by `LayoutDebugOverlay` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutDebugKind {
+    /// Bounds of a layout (e.g. a `Stack` or a `Container`).
+    Layout,
+    /// Bounds of a non-layout view.
+    View,
+    /// Paddings of a layout, between its bounds and the bounds of its subview.
+    Padding,
+    /// Bounds of a `Spacer`.
+    Spacer,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutDebugColoring {
+    /// Color-coded by `LayoutDebugKind`.
+    #[default]
+    ByKind,
+    /// Color-coded by how deeply the view is nested in the view tree.
+    ByDepth,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LayoutDebugRecord {
+    bounds: Bounds<f32>,
+    line_width: LineWidth,
+    kind: LayoutDebugKind,
+    depth: usize,
+}
+
+#[derive(Debug, Default)]
+struct LayoutDebugRecording {
+    depth: usize,
+    /// Bounds outside of which nothing is visible, e.g. because of a `ScrollView`.
+    clip_bounds: Option<Bounds<f32>>,
+    records: Vec<LayoutDebugRecord>,
+}
+
+thread_local! {
+    /// The recording of the layout in progress on this thread, `None` if no layout with the
+    /// overlay enabled is in progress.
+    static RECORDING: RefCell<Option<LayoutDebugRecording>> = const { RefCell::new(None) };
+}
+
+/// Records the bounds views are given in `View::apply_bounds`, and draws their outlines on top of
+/// everything else, in one batch with `InstancedRectRenderer`.
+///
+/// Views record themselves by calling `LayoutDebugOverlay::record` (or `record_padding`) in
+/// `apply_bounds`, and layouts call `apply_bounds` of their subviews inside `nested`. Since
+/// `apply_bounds` has no access to the `UiContext`, records go to the layout in progress on the
+/// current thread, which `UiContext::prepare_view` and `UiContext::prepare_view_bounded` start if
+/// the overlay is enabled. Recording does nothing otherwise.
+///
+/// Records are accumulated until they are drawn by `UiContext::draw_layout_debug_overlay`.
+#[derive(Debug, Default)]
+pub struct LayoutDebugOverlay {
+    enabled: AtomicBool,
+    coloring: Mutex<LayoutDebugColoring>,
+    records: Mutex<Vec<LayoutDebugRecord>>,
+    /// Kept alive until the next time the overlay is drawn.
+    rects: Mutex<Option<InstancedRectsElement>>,
+}
+
+impl LayoutDebugOverlay {
+    const PALETTE: [u32; 6] = [
+        0xFF4040FF, 0xFFA040FF, 0xE0E040FF, 0x40E040FF, 0x40C0FFFF, 0xC060FFFF,
+    ];
+
+    const REGION_ALPHA: f32 = 0.3;
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.records.lock().unwrap().clear();
+        }
+    }
+
+    pub fn coloring(&self) -> LayoutDebugColoring {
+        *self.coloring.lock().unwrap()
+    }
+
+    pub fn set_coloring(&self, coloring: LayoutDebugColoring) {
+        *self.coloring.lock().unwrap() = coloring;
+    }
+
+    /// Run `f`, which lays out views, recording the bounds they are given if the overlay is
+    /// enabled.
+    pub(crate) fn recording<R>(&self, f: impl FnOnce() -> R) -> R {
+        if !self.is_enabled() {
+            return f();
+        }
+        let outer_recording = RECORDING.replace(Some(LayoutDebugRecording::default()));
+        let result = f();
+        if let Some(recording) = RECORDING.replace(outer_recording) {
+            self.records.lock().unwrap().extend(recording.records);
+        }
+        result
+    }
+
+    /// Run `f` with the layout in progress on this thread, if there is one.
+    fn with_recording(f: impl FnOnce(&mut LayoutDebugRecording)) {
+        RECORDING.with_borrow_mut(|recording| {
+            if let Some(recording) = recording {
+                f(recording);
+            }
+        });
+    }
+
+    /// Record `bounds` at the current depth.
+    pub fn record(bounds: Bounds<f32>, kind: LayoutDebugKind) {
+        Self::record_(bounds, LineWidth::Uniform(1.), kind);
+    }
+
+    /// Record the paddings between `bounds` and `inner_bounds` at the current depth.
+    pub fn record_padding(bounds: Bounds<f32>, inner_bounds: Bounds<f32>) {
+        let line_width = LineWidth::PerBorder {
+            left: (inner_bounds.x_min() - bounds.x_min()).max(0.),
+            top: (inner_bounds.y_min() - bounds.y_min()).max(0.),
+            right: (bounds.x_max() - inner_bounds.x_max()).max(0.),
+            bottom: (bounds.y_max() - inner_bounds.y_max()).max(0.),
+        };
+        Self::record_(bounds, line_width, LayoutDebugKind::Padding);
+    }
+
+    fn record_(bounds: Bounds<f32>, line_width: LineWidth, kind: LayoutDebugKind) {
+        Self::with_recording(|recording| {
+            let visible = bounds.width() > 0.
+                && bounds.height() > 0.
+                && bounds.width().is_finite()
+                && bounds.height().is_finite()
+                && recording
+                    .clip_bounds
+                    .is_none_or(|clip_bounds| clip_bounds.intersection(bounds).is_some());
+            if !visible {
+                return;
+            }
+            recording.records.push(LayoutDebugRecord {
+                bounds,
+                line_width,
+                kind,
+                depth: recording.depth,
+            });
+        });
+    }
+
+    /// Run `f` one level deeper in the view tree.
+    pub fn nested<R>(f: impl FnOnce() -> R) -> R {
+        Self::with_recording(|recording| recording.depth += 1);
+        let result = f();
+        Self::with_recording(|recording| recording.depth -= 1);
+        result
+    }
+
+    /// Run `f` with views outside of `clip_bounds` not recorded.
+    pub fn clipped<R>(clip_bounds: Bounds<f32>, f: impl FnOnce() -> R) -> R {
+        let mut previous_clip_bounds = None;
+        Self::with_recording(|recording| {
+            previous_clip_bounds = recording.clip_bounds;
+            let clip_bounds = match previous_clip_bounds {
+                Some(previous_clip_bounds) => previous_clip_bounds.intersection(clip_bounds),
+                None => Some(clip_bounds),
+            };
+            // Zero-sized, so that everything recorded inside is invisible.
+            recording.clip_bounds =
+                Some(clip_bounds.unwrap_or(Bounds::from_scalars(0., 0., 0., 0.)));
+        });
+        let result = f();
+        Self::with_recording(|recording| recording.clip_bounds = previous_clip_bounds);
+        result
+    }
+
+    fn color_for(&self, record: &LayoutDebugRecord) -> Srgba {
+        let hex = match self.coloring() {
+            LayoutDebugColoring::ByKind => match record.kind {
+                LayoutDebugKind::Layout => Self::PALETTE[4],
+                LayoutDebugKind::View => Self::PALETTE[3],
+                LayoutDebugKind::Padding => Self::PALETTE[1],
+                LayoutDebugKind::Spacer => Self::PALETTE[5],
+            },
+            LayoutDebugColoring::ByDepth => Self::PALETTE[record.depth % Self::PALETTE.len()],
+        };
+        Srgba::from_hex(hex)
+    }
+
+    fn rect_instance(&self, record: &LayoutDebugRecord) -> RectInstance {
+        let color = self.color_for(record);
+        let region_color = Srgba {
+            a: Self::REGION_ALPHA,
+            ..color
+        };
+        let instance = RectInstance::from_parameters(record.bounds, record.line_width);
+        match record.kind {
+            LayoutDebugKind::Layout | LayoutDebugKind::View => instance
+                .with_fill_color(Rgba::new(0., 0., 0., 0.))
+                .with_line_color(color),
+            LayoutDebugKind::Padding => instance
+                .with_fill_color(Rgba::new(0., 0., 0., 0.))
+                .with_line_color(region_color),
+            LayoutDebugKind::Spacer => instance
+                .with_fill_color(region_color)
+                .with_line_color(color),
+        }
+    }
+
+    pub(crate) fn draw(&self, ui_context: &UiContext, render_pass: &mut RenderPass) {
+        let mut records = std::mem::take(&mut *self.records.lock().unwrap());
+        if records.is_empty() {
+            return;
+        }
+        // Shallower regions first, so that deeper outlines are drawn on top of them.
+        records.sort_by_key(|record| record.depth);
+        let instances: Vec<RectInstance> = records
+            .iter()
+            .map(|record| self.rect_instance(record))
+            .collect();
+        let renderer = ui_context.instanced_rect_renderer();
+        let rects = renderer.create_rects(ui_context.wgpu_device(), &instances);
+        renderer.draw_rects(render_pass.wgpu_render_pass(), &rects);
+        *self.rects.lock().unwrap() = Some(rects);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::*;
+
+    use crate::{LayoutPass, RectSize, RectView, UiContext};
+
+    use super::*;
+
+    #[test]
+    fn ui_context_is_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<UiContext>();
+    }
+
+    #[test]
+    fn records_bounds_in_apply_bounds() {
+        let overlay = LayoutDebugOverlay::default();
+        overlay.set_enabled(true);
+        let mut rect_0 = RectView::new(RectSize::new(10., 20.));
+        let mut rect_1 = RectView::new(RectSize::new(30., 40.));
+        let layout = LayoutPass::new();
+        let stack = layout.vstack(|vstack| {
+            vstack.subview(&mut rect_0);
+            vstack.subview(&mut rect_1);
+        });
+        let bounds = Bounds::new(point2(0., 0.), RectSize::new(100., 100.));
+        overlay.recording(|| layout.lay_out(stack, bounds));
+        let records: Vec<_> = overlay
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|record| (record.kind, record.depth, record.bounds))
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], (LayoutDebugKind::Layout, 0, bounds));
+        assert_eq!(records[1].0, LayoutDebugKind::View);
+        assert_eq!(records[1].1, 1);
+        assert_eq!(records[1].2.size, RectSize::new(10., 20.));
+        assert_eq!(records[2].2.size, RectSize::new(30., 40.));
+    }
+
+    #[test]
+    fn records_nothing_when_disabled() {
+        let overlay = LayoutDebugOverlay::default();
+        let mut rect = RectView::new(RectSize::new(10., 20.));
+        let bounds = Bounds::new(point2(0., 0.), RectSize::new(100., 100.));
+        overlay.recording(|| LayoutPass::new().lay_out(&mut rect, bounds));
+        assert!(overlay.records.lock().unwrap().is_empty());
+    }
+}
@@ -1,19 +1,88 @@
 use crate::{Bounds, CanvasRef, RectSize};
 
 mod button;
+mod display_list;
 mod image;
+mod layout_debug;
+mod path;
 mod rect;
 mod text;
 mod ui_context;
 
 pub use button::*;
+pub use display_list::*;
 pub use image::*;
+pub use layout_debug::*;
+pub use path::*;
 pub use rect::*;
 pub use text::*;
 pub use ui_context::*;
 
+/// Minimum and maximum sizes a view is proposed to have during layout.
+///
+/// Maximum sizes may be infinite, which means the view is free to take however much space it
+/// wants on that axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constraints {
+    pub min: RectSize<f32>,
+    pub max: RectSize<f32>,
+}
+
+impl Constraints {
+    pub const UNBOUNDED: Self = Self::loose(RectSize::new(f32::INFINITY, f32::INFINITY));
+
+    pub const fn new(min: RectSize<f32>, max: RectSize<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Constraints that only allow exactly `size`.
+    pub const fn tight(size: RectSize<f32>) -> Self {
+        Self::new(size, size)
+    }
+
+    /// Constraints that allow any size no larger than `max`.
+    pub const fn loose(max: RectSize<f32>) -> Self {
+        Self::new(RectSize::new(0., 0.), max)
+    }
+
+    /// Clamp `size` into the constraints.
+    ///
+    /// Minimum sizes take priority if they are larger than maximum sizes.
+    pub fn constrain(self, size: RectSize<f32>) -> RectSize<f32> {
+        size.min(self.max).max(self.min)
+    }
+}
+
 pub trait View<'cx> {
+    /// The size this view would take if it is given unlimited space.
+    ///
+    /// Views that want to take all the space available (i.e. "spreading" views) report
+    /// `f32::INFINITY` on that axis.
     fn preferred_size(&mut self) -> RectSize<f32>;
+
+    /// The size this view would take under `constraints`.
+    ///
+    /// Layouts call this to measure subviews before `apply_bounds`, and are expected to give the
+    /// subviews bounds of (roughly) the returned size. Views whose size on one axis depends on
+    /// the size on the other axis (e.g. wrapping texts) should override this.
+    ///
+    /// The default implementation clamps `preferred_size` into the constraints.
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        constraints.constrain(self.preferred_size())
+    }
+
+    /// Whether the view has changed since it was last prepared for drawing, in ways that may
+    /// change its size, the bounds of its subviews, or how it is drawn.
+    ///
+    /// Layouts are dirty if any of their subviews are. `Cached` uses this to skip re-measuring,
+    /// re-laying out and re-preparing unchanged subtrees.
+    ///
+    /// The default implementation always returns `true`, which is always correct but stops
+    /// `Cached` from skipping any subtree that contains the view.
+    fn is_dirty(&self) -> bool {
+        true
+    }
+
     fn apply_bounds(&mut self, bounds: Bounds<f32>);
     fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef);
     fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass);
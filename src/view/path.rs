@@ -0,0 +1,185 @@
+use cgmath::*;
+
+use crate::{
+    Bounds, CanvasRef, DisplayItem, LayoutDebugKind, LayoutDebugOverlay, Path, RectSize,
+    RenderPass, Rgba, Stroke, UiContext, View,
+    element::{PathElement, PathMesh},
+    property,
+};
+
+/// Fills and/or strokes a `Path`, with the path's coordinates relative to the origin of the view.
+///
+/// The path is tessellated on the CPU whenever it changes, so paths that change every frame are
+/// costly. Paths that need a fill rule (see `element::PathFillError`) are only stroked.
+#[derive(Debug)]
+pub struct PathView {
+    size: RectSize<f32>,
+    path: Path,
+    fill_color: Option<Rgba>,
+    stroke: Option<Stroke>,
+    bounds: Bounds<f32>,
+    needs_update: bool,
+    needs_tessellation: bool,
+    /// Size of a physical pixel in logical units the path was tessellated for.
+    pixel_size: f32,
+    /// Bounds of the tessellated mesh, relative to the origin of the view.
+    mesh_bounds: Option<Bounds<f32>>,
+    /// Initialised in the first call of `View::prepare_for_drawing`.
+    raw: Option<PathElement>,
+}
+
+impl PathView {
+    pub fn new(size: RectSize<f32>, path: Path) -> Self {
+        Self {
+            size,
+            path,
+            fill_color: None,
+            stroke: None,
+            bounds: Bounds::new(point2(0., 0.), size),
+            needs_update: true,
+            needs_tessellation: true,
+            pixel_size: 1.,
+            mesh_bounds: None,
+            raw: None,
+        }
+    }
+
+    property! {
+        vis: pub,
+        param_ty: RectSize<f32>,
+        param: size,
+        param_mut: size_mut,
+        set_param: set_size,
+        with_param: with_size,
+        param_mut_preamble: |self_: &mut Self| self_.needs_update = true,
+    }
+
+    property! {
+        vis: pub,
+        param_ty: Option<Rgba>,
+        param: fill_color,
+        param_mut: fill_color_mut,
+        set_param: set_fill_color,
+        with_param: with_fill_color,
+        param_mut_preamble: |self_: &mut Self| {
+            self_.needs_update = true;
+            self_.needs_tessellation = true;
+        },
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn path_mut(&mut self) -> &mut Path {
+        self.needs_update = true;
+        self.needs_tessellation = true;
+        &mut self.path
+    }
+
+    pub fn set_path(&mut self, path: Path) {
+        *self.path_mut() = path;
+    }
+
+    pub fn with_path(mut self, path: Path) -> Self {
+        *self.path_mut() = path;
+        self
+    }
+
+    pub fn stroke(&self) -> Option<&Stroke> {
+        self.stroke.as_ref()
+    }
+
+    pub fn stroke_mut(&mut self) -> &mut Option<Stroke> {
+        self.needs_update = true;
+        self.needs_tessellation = true;
+        &mut self.stroke
+    }
+
+    pub fn set_stroke(&mut self, stroke: impl Into<Option<Stroke>>) {
+        *self.stroke_mut() = stroke.into();
+    }
+
+    pub fn with_stroke(mut self, stroke: impl Into<Option<Stroke>>) -> Self {
+        *self.stroke_mut() = stroke.into();
+        self
+    }
+
+    /// The bounds from the last layout.
+    pub fn bounds(&self) -> Bounds<f32> {
+        self.bounds
+    }
+
+    fn tessellate(&self) -> PathMesh {
+        let mut mesh = PathMesh::new();
+        if let Some(fill_color) = self.fill_color
+            && let Err(error) = mesh.fill(&self.path, fill_color, self.pixel_size)
+        {
+            log::warn!("the path of a `PathView` is not filled: {error}");
+        }
+        if let Some(stroke) = self.stroke.as_ref() {
+            mesh.stroke(&self.path, stroke, self.pixel_size);
+        }
+        mesh
+    }
+}
+
+impl<'cx> View<'cx> for PathView {
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        self.size
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.needs_update
+    }
+
+    fn apply_bounds(&mut self, bounds: Bounds<f32>) {
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::View);
+        if bounds != self.bounds {
+            self.bounds = bounds;
+            self.needs_update = true;
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, canvas: &CanvasRef) {
+        let physical_width = canvas.color_texture_view.texture().width() as f32;
+        let pixel_size = canvas.logical_size.width / physical_width;
+        if pixel_size.is_finite() && pixel_size > 0. && pixel_size != self.pixel_size {
+            self.pixel_size = pixel_size;
+            self.needs_tessellation = true;
+        }
+        if self.needs_tessellation || self.raw.is_none() {
+            self.needs_tessellation = false;
+            self.needs_update = true;
+            let mesh = self.tessellate();
+            self.mesh_bounds = mesh.bounds();
+            self.raw = Some(
+                ui_context
+                    .path_renderer()
+                    .create_path(ui_context.wgpu_device(), &mesh),
+            );
+        }
+        if self.needs_update {
+            self.needs_update = false;
+            let raw = self.raw.as_ref().unwrap();
+            raw.set_origin(ui_context.wgpu_queue(), self.bounds.origin);
+        }
+    }
+
+    fn draw(&self, _ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        if self.needs_update {
+            log::warn!("`<PathView as View>::draw` is called without `prepare_for_drawing`");
+            return;
+        }
+        let (Some(raw), Some(mesh_bounds)) = (self.raw.as_ref(), self.mesh_bounds) else {
+            return;
+        };
+        let bounds = Bounds::new(
+            self.bounds.origin + mesh_bounds.origin.to_vec(),
+            mesh_bounds.size,
+        );
+        render_pass
+            .display_list_mut()
+            .push(DisplayItem::Path(raw.clone()), bounds);
+    }
+}
@@ -1,34 +1,44 @@
 use cgmath::*;
 
 use crate::{
-    Bounds, CanvasRef, LineWidth, RectSize, RenderPass, Rgba, View, element::RectElement, property,
+    Bounds, BoxShadow, CanvasRef, CornerRadius, Fill, LayoutDebugKind, LayoutDebugOverlay,
+    LineWidth, RectSize, RenderPass, Rgba, Srgba, View,
+    element::{RectElement, RectInstance, ShadowElement},
+    property,
     utils::*,
 };
 
-use super::UiContext;
+use super::{DisplayItem, UiContext};
 
 #[derive(Debug)]
 pub struct RectView {
     size: RectSize<f32>,
-    fill_color: Rgba,
+    fill_color: Fill,
     line_color: Rgba,
     line_width: LineWidth,
+    corner_radius: CornerRadius,
+    shadow: Option<BoxShadow>,
     bounds: Bounds<f32>,
     needs_update: bool,
-    /// Initialised until the first call of `View::apply_size`.
+    /// Only created for fills that `RectInstance` cannot draw, i.e. gradients.
     raw: Option<RectElement>,
+    /// Initialised in the first call of `View::prepare_for_drawing` with a shadow.
+    raw_shadow: Option<ShadowElement>,
 }
 
 impl Default for RectView {
     fn default() -> Self {
         Self {
             size: the_default(),
-            fill_color: Rgba::from_hex(0xFFFFFF),
+            fill_color: Fill::Solid(Srgba::from_hex(0xFFFFFF)),
             line_color: the_default(),
             line_width: the_default(),
+            corner_radius: the_default(),
+            shadow: None,
             bounds: the_default(),
             needs_update: true,
             raw: the_default(),
+            raw_shadow: the_default(),
         }
     }
 }
@@ -36,19 +46,22 @@ impl Default for RectView {
 impl RectView {
     pub const fn new(size: RectSize<f32>) -> Self {
         Self {
-            fill_color: Rgba::from_hex(0xFFFFFFFF),
+            fill_color: Fill::Solid(Srgba::from_hex(0xFFFFFFFF)),
             line_color: Rgba::from_hex(0xFFFFFFFF),
             line_width: LineWidth::Uniform(0.),
+            corner_radius: CornerRadius::Uniform(0.),
+            shadow: None,
             size,
             bounds: Bounds::new(point2(0., 0.), size),
             needs_update: true,
             raw: None,
+            raw_shadow: None,
         }
     }
 
     property! {
         vis: pub,
-        param_ty: Rgba,
+        param_ty: Fill,
         param: fill_color,
         param_mut: fill_color_mut,
         set_param: set_fill_color,
@@ -76,6 +89,26 @@ impl RectView {
         param_mut_preamble: |self_: &mut Self| self_.needs_update = true,
     }
 
+    property! {
+        vis: pub,
+        param_ty: CornerRadius,
+        param: corner_radius,
+        param_mut: corner_radius_mut,
+        set_param: set_corner_radius,
+        with_param: with_corner_radius,
+        param_mut_preamble: |self_: &mut Self| self_.needs_update = true,
+    }
+
+    property! {
+        vis: pub,
+        param_ty: Option<BoxShadow>,
+        param: shadow,
+        param_mut: shadow_mut,
+        set_param: set_shadow,
+        with_param: with_shadow,
+        param_mut_preamble: |self_: &mut Self| self_.needs_update = true,
+    }
+
     property! {
         vis: pub,
         param_ty: RectSize<f32>,
@@ -96,34 +129,72 @@ impl<'cx> View<'cx> for RectView {
         self.size
     }
 
+    fn is_dirty(&self) -> bool {
+        self.needs_update
+    }
+
     fn apply_bounds(&mut self, bounds: Bounds<f32>) {
-        self.bounds = bounds;
-        self.needs_update = true;
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::View);
+        if bounds != self.bounds {
+            self.bounds = bounds;
+            self.needs_update = true;
+        }
     }
 
     fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, _canvas: &CanvasRef) {
-        let raw = self.raw.get_or_insert_with(|| {
-            ui_context
-                .rect_renderer()
-                .create_rect(ui_context.wgpu_device())
-        });
         if self.needs_update {
             self.needs_update = false;
-            raw.set_parameters(ui_context.wgpu_queue(), self.bounds, self.line_width);
-            raw.set_fill_color(ui_context.wgpu_queue(), self.fill_color);
-            raw.set_line_color(ui_context.wgpu_queue(), self.line_color);
+            if !matches!(self.fill_color, Fill::Solid(_)) {
+                let raw = self.raw.get_or_insert_with(|| {
+                    ui_context
+                        .rect_renderer()
+                        .create_rect(ui_context.wgpu_device())
+                });
+                raw.set_parameters(ui_context.wgpu_queue(), self.bounds, self.line_width);
+                raw.set_fill_color(ui_context.wgpu_queue(), self.fill_color);
+                raw.set_line_color(ui_context.wgpu_queue(), self.line_color);
+                raw.set_corner_radius(ui_context.wgpu_queue(), self.corner_radius);
+            }
+            if let Some(shadow) = self.shadow {
+                let raw_shadow = self.raw_shadow.get_or_insert_with(|| {
+                    ui_context
+                        .shadow_renderer()
+                        .create_shadow(ui_context.wgpu_device())
+                });
+                raw_shadow.set_parameters(
+                    ui_context.wgpu_queue(),
+                    self.bounds,
+                    self.corner_radius,
+                    shadow,
+                );
+            }
         }
     }
 
-    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
-        if let Some(raw) = self.raw.as_ref()
-            && !self.needs_update
-        {
-            ui_context
-                .rect_renderer()
-                .draw_rect(render_pass.wgpu_render_pass(), raw);
-        } else {
+    fn draw(&self, _ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        if self.needs_update {
             log::warn!("`<RectView as View>::draw` is called without `prepare_for_drawing`");
+            return;
+        }
+        let display_list = render_pass.display_list_mut();
+        if let Some(shadow) = self.shadow
+            && let Some(raw_shadow) = self.raw_shadow.as_ref()
+        {
+            display_list.push(
+                DisplayItem::Shadow(raw_shadow.clone()),
+                shadow.visible_bounds(self.bounds),
+            );
         }
+        let item = match (self.fill_color, self.raw.as_ref()) {
+            (Fill::Solid(fill_color), _) => DisplayItem::Rect(
+                RectInstance::from_parameters(self.bounds, self.line_width)
+                    .with_fill_color(fill_color)
+                    .with_line_color(self.line_color)
+                    .with_corner_radius(self.corner_radius),
+            ),
+            (_, Some(raw)) => DisplayItem::RectElement(raw.clone()),
+            (_, None) => unreachable!("`raw` is created for gradient fills when prepared"),
+        };
+        display_list.push(item, self.bounds);
     }
 }
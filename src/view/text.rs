@@ -1,10 +1,10 @@
-use std::{borrow::Cow, cell::OnceCell};
+use std::{borrow::Cow, sync::Arc};
 
 use cgmath::*;
 
 use crate::{
-    Bounds, CanvasRef, Font, RectSize, RenderPass, Rgba, UiContext, View, element::TextElement,
-    property,
+    Bounds, CanvasRef, Constraints, DisplayItem, FontMetrics, LayoutDebugKind, LayoutDebugOverlay,
+    RectSize, RenderPass, Rgba, UiContext, View, element::GlyphInstance, property,
 };
 
 #[derive(Debug)]
@@ -13,33 +13,54 @@ pub struct TextView<'cx> {
     n_columns: usize,
     text: Cow<'cx, str>,
     font_size: f32,
-    font: Font<'cx>,
+    font_metrics: FontMetrics,
     fg_color: Rgba,
     bg_color: Rgba,
-    origin: Point2<f32>,
+    bounds: Bounds<f32>,
     needs_update: bool,
+    /// Off by default. When on, the text is wrapped at spaces to fit the width it is laid out in,
+    /// before being squeezed.
+    line_wrapping: bool,
+    /// The number of columns the text is wrapped to in the last layout, `None` if the text is not
+    /// wrapped.
+    wrapping_columns: Option<usize>,
+    /// The last result of wrapping the text, reused while the text and the wrapping width stay
+    /// the same.
+    wrapped_text: Option<WrappedText>,
     squeeze_horizontal: f32,
     squeeze_vertical: f32,
     text_needs_update: bool,
-    raw: OnceCell<TextElement>,
+    /// Laid out in `View::prepare_for_drawing`.
+    glyphs: Arc<[GlyphInstance]>,
 }
 
 impl<'cx> TextView<'cx> {
     pub fn new(ui_context: &UiContext<'cx>) -> Self {
+        Self::with_font_metrics(ui_context.text_renderer().font().metrics())
+    }
+
+    /// Create a text view that is measured with `font_metrics`.
+    ///
+    /// This does not need a `UiContext`, so texts can be laid out without a GPU. When drawn, the
+    /// text is still drawn in the font of the `UiContext`.
+    pub fn with_font_metrics(font_metrics: FontMetrics) -> Self {
         Self {
             n_lines: 1,
             n_columns: 0,
             text: "".into(),
             font_size: 12.,
-            font: ui_context.text_renderer().font(),
+            font_metrics,
             fg_color: Rgba::from_hex(0xFFFFFFFF),
             bg_color: Rgba::from_hex(0x00000000),
-            origin: point2(0., 0.),
+            bounds: Bounds::default(),
             needs_update: false,
+            line_wrapping: false,
+            wrapping_columns: None,
+            wrapped_text: None,
             text_needs_update: false,
             squeeze_horizontal: 1.,
             squeeze_vertical: 1.,
-            raw: OnceCell::new(),
+            glyphs: Arc::new([]),
         }
     }
 
@@ -73,8 +94,20 @@ impl<'cx> TextView<'cx> {
         param_mut_preamble: |self_: &mut Self| self_.needs_update = true,
     }
 
+    property! {
+        vis: pub,
+        param_ty: bool,
+        param: line_wrapping,
+        param_mut: line_wrapping_mut,
+        set_param: set_line_wrapping,
+        with_param: with_line_wrapping,
+        param_mut_preamble: |self_: &mut Self| self_.needs_update = true,
+    }
+
     pub fn set_text(&mut self, text: impl Into<Cow<'cx, str>>) {
         self.text_needs_update = true;
+        self.wrapping_columns = None;
+        self.wrapped_text = None;
         let text = text.into();
         self.n_lines = 1usize;
         let mut n_columns = 0usize;
@@ -105,6 +138,11 @@ impl<'cx> TextView<'cx> {
         self
     }
 
+    /// The bounds from the last layout.
+    pub fn bounds(&self) -> Bounds<f32> {
+        self.bounds
+    }
+
     pub fn n_columns(&self) -> usize {
         self.n_columns
     }
@@ -113,12 +151,97 @@ impl<'cx> TextView<'cx> {
         self.n_lines
     }
 
+    /// The size of the text without wrapping.
     pub fn size(&self) -> RectSize<f32> {
+        self.size_for(self.n_columns, self.n_lines)
+    }
+
+    fn size_for(&self, n_columns: usize, n_lines: usize) -> RectSize<f32> {
         RectSize::new(
-            (self.n_columns as f32) * self.font.glyph_relative_width() * self.font_size(),
-            self.n_lines as f32 * self.font_size(),
+            (n_columns as f32) * self.glyph_width(),
+            n_lines as f32 * self.font_size(),
         )
     }
+
+    fn glyph_width(&self) -> f32 {
+        self.font_metrics.glyph_relative_width() * self.font_size()
+    }
+
+    /// The number of columns that fits in `width`, `None` if the text does not need wrapping
+    /// under this width.
+    fn wrapping_columns_for(&self, width: f32) -> Option<usize> {
+        if !self.line_wrapping || self.glyph_width() <= 0. {
+            return None;
+        }
+        let max_columns = (width / self.glyph_width()).floor();
+        match max_columns < self.n_columns as f32 {
+            true => Some((max_columns as usize).max(1)),
+            false => None,
+        }
+    }
+
+    /// The text wrapped to `max_columns`, from the cache if it was wrapped to the same number of
+    /// columns before.
+    fn wrapped_text(&mut self, max_columns: usize) -> &WrappedText {
+        let wrapped_text = self
+            .wrapped_text
+            .take()
+            .filter(|wrapped_text| wrapped_text.max_columns == max_columns)
+            .unwrap_or_else(|| wrap_text(&self.text, max_columns));
+        self.wrapped_text.insert(wrapped_text)
+    }
+}
+
+#[derive(Debug)]
+struct WrappedText {
+    max_columns: usize,
+    text: String,
+    n_lines: usize,
+    /// The number of columns of the longest line.
+    n_columns: usize,
+}
+
+/// Insert line breaks into `text` so that no line is longer than `max_columns` characters.
+/// Lines are broken at spaces where possible, and words that are too long are broken anywhere.
+fn wrap_text(text: &str, max_columns: usize) -> WrappedText {
+    let mut wrapped = String::with_capacity(text.len());
+    let mut n_lines = 1usize;
+    let mut n_columns = 0usize;
+    for (i_line, line) in text.split('\n').enumerate() {
+        if i_line != 0 {
+            wrapped.push('\n');
+            n_lines += 1;
+        }
+        let mut column = 0usize;
+        for (i_word, word) in line.split(' ').enumerate() {
+            if i_word != 0 {
+                if column + 1 + word.chars().count() <= max_columns {
+                    wrapped.push(' ');
+                    column += 1;
+                } else {
+                    wrapped.push('\n');
+                    n_lines += 1;
+                    column = 0;
+                }
+            }
+            for char in word.chars() {
+                if column == max_columns {
+                    wrapped.push('\n');
+                    n_lines += 1;
+                    column = 0;
+                }
+                wrapped.push(char);
+                column += 1;
+            }
+            n_columns = n_columns.max(column);
+        }
+    }
+    WrappedText {
+        max_columns,
+        text: wrapped,
+        n_lines,
+        n_columns,
+    }
 }
 
 impl<'cx> View<'cx> for TextView<'cx> {
@@ -126,50 +249,76 @@ impl<'cx> View<'cx> for TextView<'cx> {
         self.size()
     }
 
+    fn size_that_fits(&mut self, constraints: Constraints) -> RectSize<f32> {
+        let size = match self.wrapping_columns_for(constraints.max.width) {
+            Some(max_columns) => {
+                let wrapped_text = self.wrapped_text(max_columns);
+                let (n_columns, n_lines) = (wrapped_text.n_columns, wrapped_text.n_lines);
+                self.size_for(n_columns, n_lines)
+            }
+            None => self.size(),
+        };
+        constraints.constrain(size)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.needs_update || self.text_needs_update
+    }
+
     fn apply_bounds(&mut self, bounds: Bounds<f32>) {
-        let size = self.size();
-        self.squeeze_horizontal = (bounds.width() / size.width).min(1.);
-        self.squeeze_vertical = (bounds.height() / size.height).min(1.);
-        self.needs_update = true;
-        self.origin = bounds.origin;
+        LayoutDebugOverlay::record(bounds, LayoutDebugKind::View);
+        // Wrap the text if possible, and squeeze it if it still does not fit.
+        let wrapping_columns = self.wrapping_columns_for(bounds.width());
+        let size = match wrapping_columns {
+            Some(max_columns) => {
+                let wrapped_text = self.wrapped_text(max_columns);
+                let (n_columns, n_lines) = (wrapped_text.n_columns, wrapped_text.n_lines);
+                self.size_for(n_columns, n_lines)
+            }
+            None => self.size(),
+        };
+        if wrapping_columns != self.wrapping_columns {
+            self.wrapping_columns = wrapping_columns;
+            self.text_needs_update = true;
+        }
+        let squeeze_horizontal = (bounds.width() / size.width).min(1.);
+        let squeeze_vertical = (bounds.height() / size.height).min(1.);
+        if (bounds, squeeze_horizontal, squeeze_vertical)
+            != (self.bounds, self.squeeze_horizontal, self.squeeze_vertical)
+        {
+            self.bounds = bounds;
+            self.squeeze_horizontal = squeeze_horizontal;
+            self.squeeze_vertical = squeeze_vertical;
+            self.needs_update = true;
+        }
     }
 
     fn prepare_for_drawing(&mut self, ui_context: &UiContext<'cx>, _canvas: &CanvasRef) {
-        let raw = self.raw.get_or_init(|| {
-            self.text_needs_update = false; // `create_text` updates the text
-            ui_context
-                .text_renderer()
-                .create_text(ui_context.wgpu_device(), &self.text)
-        });
-        if self.needs_update {
+        if self.needs_update || self.text_needs_update {
             self.needs_update = false;
-            let this = &raw;
-            let origin = self.origin;
-            let font_size = self.font_size;
-            this.set_model_view(
-                ui_context.wgpu_queue(),
-                Matrix4::from_translation(origin.to_vec().extend(0.))
-                    * Matrix4::from_nonuniform_scale(
-                        self.squeeze_horizontal * font_size,
-                        self.squeeze_vertical * font_size,
-                        1.,
-                    ),
-            );
-            raw.set_fg_color(ui_context.wgpu_queue(), self.fg_color);
-            raw.set_bg_color(ui_context.wgpu_queue(), self.bg_color);
-        }
-        if self.text_needs_update {
             self.text_needs_update = false;
-            let raw = self.raw.get_mut().unwrap();
-            ui_context
-                .text_renderer()
-                .update_text(ui_context.wgpu_device(), raw, &self.text);
+            let scale = vec2(
+                self.squeeze_horizontal * self.font_size,
+                self.squeeze_vertical * self.font_size,
+            );
+            let (origin, fg_color, bg_color) = (self.bounds.origin, self.fg_color, self.bg_color);
+            let text = match self.wrapping_columns {
+                Some(max_columns) => &self.wrapped_text(max_columns).text,
+                None => &*self.text,
+            };
+            self.glyphs = ui_context
+                .instanced_text_renderer()
+                .layout_glyphs(text, origin, scale, fg_color, bg_color)
+                .into();
         }
     }
 
-    fn draw(&self, ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
-        ui_context
-            .text_renderer()
-            .draw_text(render_pass.wgpu_render_pass(), self.raw.get().unwrap());
+    fn draw(&self, _ui_context: &UiContext<'cx>, render_pass: &mut RenderPass) {
+        if self.glyphs.is_empty() {
+            return;
+        }
+        render_pass
+            .display_list_mut()
+            .push(DisplayItem::Glyphs(self.glyphs.clone()), self.bounds);
     }
 }
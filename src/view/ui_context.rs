@@ -1,37 +1,73 @@
 use std::{
+    collections::HashMap,
     fmt::{self, Display},
     mem::{ManuallyDrop, MaybeUninit},
+    ops::Range,
     ptr::drop_in_place,
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use cgmath::*;
-use derive_more::{Display, Error};
+use derive_more::{Display, Error, From};
 use pollster::FutureExt as _;
 use winit::window::Window;
 
 use crate::{
-    Bounds, Canvas as _, CanvasFormat, CanvasRef, Font, ImageRef, LayoutPass,
-    RectSize, Rgba, Texture2d, WindowCanvas,
-    element::{CameraBindGroup, ImageRenderer, InstancedRectRenderer, RectRenderer, TextRenderer},
+    BatchKind, Bounds, Canvas as _, CanvasFormat, CanvasRef, Constraints, CornerRadius,
+    DisplayItem, DisplayList, Font, ImageRef, LayoutDebugOverlay, LayoutPass, ReadCanvasError,
+    RectSize, Rgba, Srgb, Srgba, Texture2d, TextureCanvas, WindowCanvas,
+    element::{
+        CameraBindGroup, GlyphInstance, ImageInstance, ImageRenderer, ImageTextureBinding,
+        InstancedGlyphsElement, InstancedImageRenderer, InstancedImagesElement,
+        InstancedRectRenderer, InstancedRectsElement, InstancedTextRenderer, PathRenderer,
+        RectInstance, RectRenderer, ShadowRenderer, StencilMaskOp, TextRenderer,
+    },
     resources::{AppResources, LoadResourceError},
     utils::*,
     view::View,
-    wgpu_utils::{AsBindGroup, UniformBuffer},
+    wgpu_utils::{AsBindGroup, GrowableVertexBuffer, UniformBuffer, Vertex, VertexBuffer},
 };
 
-fn init_wgpu() -> (wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue) {
+/// Which adapters `init_wgpu` requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AdapterPreference {
+    /// A hardware adapter, falling back to a software adapter on machines without a GPU.
+    Hardware,
+    /// Only a software adapter.
+    Software,
+    /// A software adapter, falling back to a hardware adapter.
+    SoftwareOrHardware,
+}
+
+fn init_wgpu(
+    adapter_preference: AdapterPreference,
+) -> Result<(wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue), UiContextCreationError> {
     let instance = wgpu::Instance::new(&the_default());
-    let adapter = instance.request_adapter(&the_default()).block_on().unwrap();
-    let features = wgpu::FeaturesWGPU::POLYGON_MODE_LINE;
+    let request_adapter = |force_fallback_adapter| {
+        instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                force_fallback_adapter,
+                ..the_default()
+            })
+            .block_on()
+    };
+    let adapter = match adapter_preference {
+        AdapterPreference::Hardware => request_adapter(false).or_else(|_| request_adapter(true)),
+        AdapterPreference::Software => request_adapter(true),
+        AdapterPreference::SoftwareOrHardware => {
+            request_adapter(true).or_else(|_| request_adapter(false))
+        }
+    }
+    .map_err(|e| UiContextCreationError::new(UiContextCreationStage::AdapterRequest, e))?;
+    let features = wgpu::Features::from(wgpu::FeaturesWGPU::POLYGON_MODE_LINE) & adapter.features();
     let (device, queue) = adapter
         .request_device(&wgpu::DeviceDescriptor {
-            required_features: features.into(),
+            required_features: features,
             ..the_default()
         })
         .block_on()
-        .unwrap();
-    (instance, adapter, device, queue)
+        .map_err(|e| UiContextCreationError::new(UiContextCreationStage::DeviceRequest, e))?;
+    Ok((instance, adapter, device, queue))
 }
 
 /// `'cx` is for allowing `UiState` to contain captured lifetimes, which is necessary for
@@ -39,22 +75,44 @@ fn init_wgpu() -> (wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue) {
 pub struct UiContext<'cx> {
     device: wgpu::Device,
     queue: wgpu::Queue,
-    camera_bind_group: CameraBindGroup,
-    camera_bind_group_wgpu: wgpu::BindGroup,
+    camera: Camera,
+    /// Used by `render_to_image` instead of `camera`, so that rendering offscreen does not
+    /// overwrite the projection of a render pass that has not been submitted yet.
+    offscreen_camera: Camera,
     rect_renderer: RectRenderer<'cx>,
     instanced_rect_renderer: InstancedRectRenderer<'cx>,
+    shadow_renderer: ShadowRenderer<'cx>,
     text_renderer: TextRenderer<'cx>,
+    instanced_text_renderer: InstancedTextRenderer<'cx>,
     image_renderer: ImageRenderer<'cx>,
+    instanced_image_renderer: InstancedImageRenderer<'cx>,
+    path_renderer: PathRenderer<'cx>,
+    /// Instance buffers of the render passes that have been submitted, for the next render passes
+    /// to reuse.
+    instance_buffer_pool: Arc<Mutex<Vec<InstanceBuffers>>>,
+    texture_bindings: Mutex<TextureBindings>,
+    layout_debug_overlay: LayoutDebugOverlay,
+    canvas_format: CanvasFormat,
 }
 
 impl<'cx> UiContext<'cx> {
+    /// `sample_count` is lowered to one the adapter supports, see
+    /// `WindowCanvas::create_for_window`.
     pub fn create_for_window(
         resources: &'cx AppResources,
         window: Arc<Window>,
+        depth_stencil_format: Option<wgpu::TextureFormat>,
+        sample_count: u32,
     ) -> Result<(Self, WindowCanvas<'static>), UiContextCreationError> {
-        let (instance, adapter, device, queue) = init_wgpu();
-        let window_canvas =
-            WindowCanvas::create_for_window(&instance, &adapter, &device, window.clone());
+        let (instance, adapter, device, queue) = init_wgpu(AdapterPreference::Hardware)?;
+        let window_canvas = WindowCanvas::create_for_window(
+            &instance,
+            &adapter,
+            &device,
+            window.clone(),
+            depth_stencil_format,
+            sample_count,
+        );
         let ui_context = UiContext::create(device, queue, resources, window_canvas.format())?;
         Ok((ui_context, window_canvas))
     }
@@ -70,12 +128,8 @@ impl<'cx> UiContext<'cx> {
                 $x.map_err(|e| UiContextCreationError::new($stage, e))?
             };
         }
-        let camera_bind_group = CameraBindGroup {
-            projection: UniformBuffer::create_init(&device, Matrix4::identity().into()),
-            aaf: UniformBuffer::create_init(&device, 0.),
-        };
-        let camera_bind_group_wgpu = camera_bind_group
-            .create_bind_group(&CameraBindGroup::create_bind_group_layout(&device), &device);
+        let camera = Camera::create(&device);
+        let offscreen_camera = Camera::create(&device);
         // TODO: Move fonts loading to per-TextElement instance.
         let font = try_!(
             UiContextCreationStage::FontLoading,
@@ -85,6 +139,10 @@ impl<'cx> UiContext<'cx> {
             UiContextCreationStage::TextRendererCreation,
             TextRenderer::create(&device, &queue, font, resources, canvas_format),
         );
+        let instanced_text_renderer = try_!(
+            UiContextCreationStage::InstancedTextRendererCreation,
+            InstancedTextRenderer::create(&device, &text_renderer, resources, canvas_format),
+        );
         let rect_renderer = try_!(
             UiContextCreationStage::RectRendererCreation,
             RectRenderer::create(&device, resources, canvas_format)
@@ -93,56 +151,134 @@ impl<'cx> UiContext<'cx> {
             UiContextCreationStage::InstancedRectRendererCreation,
             InstancedRectRenderer::create(&device, resources, canvas_format),
         );
+        let shadow_renderer = try_!(
+            UiContextCreationStage::ShadowRendererCreation,
+            ShadowRenderer::create(&device, resources, canvas_format),
+        );
         let image_renderer = try_!(
             UiContextCreationStage::ImageRendererCreation,
             ImageRenderer::create(&device, resources, canvas_format),
         );
+        let instanced_image_renderer = try_!(
+            UiContextCreationStage::InstancedImageRendererCreation,
+            InstancedImageRenderer::create(&device, resources, canvas_format),
+        );
+        let path_renderer = try_!(
+            UiContextCreationStage::PathRendererCreation,
+            PathRenderer::create(&device, resources, canvas_format),
+        );
         Ok(Self {
             device,
             queue,
-            camera_bind_group,
-            camera_bind_group_wgpu,
+            camera,
+            offscreen_camera,
             rect_renderer,
             instanced_rect_renderer,
+            shadow_renderer,
             text_renderer,
+            instanced_text_renderer,
             image_renderer,
+            instanced_image_renderer,
+            path_renderer,
+            instance_buffer_pool: the_default(),
+            texture_bindings: the_default(),
+            layout_debug_overlay: the_default(),
+            canvas_format,
         })
     }
+
+    /// Create a `UiContext` that only draws onto offscreen canvases, e.g. for `render_to_image`.
+    ///
+    /// Falls back to a software adapter on machines without a GPU.
+    pub fn create_headless(
+        resources: &'cx AppResources,
+        canvas_format: CanvasFormat,
+    ) -> Result<Self, UiContextCreationError> {
+        let (_, _, device, queue) = init_wgpu(AdapterPreference::Hardware)?;
+        UiContext::create(device, queue, resources, canvas_format)
+    }
+
+    /// Like `create_headless`, but with the software adapter, so that the drawing results are the
+    /// same across machines. Fails if there is no software adapter.
+    pub fn create_headless_software(
+        resources: &'cx AppResources,
+        canvas_format: CanvasFormat,
+    ) -> Result<Self, UiContextCreationError> {
+        let (_, _, device, queue) = init_wgpu(AdapterPreference::Software)?;
+        UiContext::create(device, queue, resources, canvas_format)
+    }
+
+    /// Like `create_headless_software`, but falls back to a hardware adapter if there is no
+    /// software adapter, in which case the drawing results may differ across machines.
+    pub fn create_headless_software_or_hardware(
+        resources: &'cx AppResources,
+        canvas_format: CanvasFormat,
+    ) -> Result<Self, UiContextCreationError> {
+        let (_, _, device, queue) = init_wgpu(AdapterPreference::SoftwareOrHardware)?;
+        UiContext::create(device, queue, resources, canvas_format)
+    }
 }
 
 #[derive(Debug, Error)]
 pub struct UiContextCreationError {
     stage: UiContextCreationStage,
-    error: LoadResourceError,
+    error: UiContextCreationErrorSource,
 }
 
 impl UiContextCreationError {
-    fn new(stage: UiContextCreationStage, error: LoadResourceError) -> Self {
-        Self { stage, error }
+    fn new(stage: UiContextCreationStage, error: impl Into<UiContextCreationErrorSource>) -> Self {
+        Self {
+            stage,
+            error: error.into(),
+        }
     }
 
     pub fn stage(&self) -> UiContextCreationStage {
         self.stage
     }
 
-    pub fn error(&self) -> &LoadResourceError {
+    pub fn error(&self) -> &UiContextCreationErrorSource {
         &self.error
     }
 }
 
+/// The error of a `UiContextCreationStage`.
+#[derive(Debug, Display, From, Error)]
+#[non_exhaustive]
+pub enum UiContextCreationErrorSource {
+    #[display("{_0}")]
+    LoadResource(LoadResourceError),
+    #[display("{_0}")]
+    RequestAdapter(wgpu::RequestAdapterError),
+    #[display("{_0}")]
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Error)]
 #[non_exhaustive]
 pub enum UiContextCreationStage {
+    #[display("requesting the adapter")]
+    AdapterRequest,
+    #[display("requesting the device")]
+    DeviceRequest,
     #[display("creating the rect renderer")]
     RectRendererCreation,
     #[display("creating the instanced rect renderer")]
     InstancedRectRendererCreation,
+    #[display("creating the shadow renderer")]
+    ShadowRendererCreation,
     #[display("loading the font")]
     FontLoading,
     #[display("creating the text renderer")]
     TextRendererCreation,
+    #[display("creating the instanced text renderer")]
+    InstancedTextRendererCreation,
     #[display("creating the image renderer")]
     ImageRendererCreation,
+    #[display("creating the instanced image renderer")]
+    InstancedImageRendererCreation,
+    #[display("creating the path renderer")]
+    PathRendererCreation,
 }
 
 impl Display for UiContextCreationError {
@@ -168,29 +304,54 @@ impl<'cx> UiContext<'cx> {
         &self.instanced_rect_renderer
     }
 
+    pub fn shadow_renderer(&self) -> &ShadowRenderer<'cx> {
+        &self.shadow_renderer
+    }
+
     pub fn text_renderer(&self) -> &TextRenderer<'cx> {
         &self.text_renderer
     }
 
+    pub fn instanced_text_renderer(&self) -> &InstancedTextRenderer<'cx> {
+        &self.instanced_text_renderer
+    }
+
     pub fn image_renderer(&self) -> &ImageRenderer<'cx> {
         &self.image_renderer
     }
 
+    pub fn instanced_image_renderer(&self) -> &InstancedImageRenderer<'cx> {
+        &self.instanced_image_renderer
+    }
+
+    pub fn path_renderer(&self) -> &PathRenderer<'cx> {
+        &self.path_renderer
+    }
+
+    pub fn layout_debug_overlay(&self) -> &LayoutDebugOverlay {
+        &self.layout_debug_overlay
+    }
+
+    /// Format of the canvases this `UiContext` can draw onto.
+    pub fn canvas_format(&self) -> CanvasFormat {
+        self.canvas_format
+    }
+
     pub fn prepare_view(
         &self,
         canvas: &CanvasRef,
         origin: Point2<f32>,
         view: &mut dyn View<'cx>,
     ) -> Bounds<f32> {
-        let requested_size = view.preferred_size();
         let canvas_size = canvas.logical_size;
         let availible_size = RectSize {
             width: canvas_size.width - origin.x,
             height: canvas_size.height,
         };
-        let subview_size = availible_size.min(requested_size);
+        let subview_size = view.size_that_fits(Constraints::loose(availible_size));
         let bounds = Bounds::new(origin, subview_size);
-        view.apply_bounds(bounds);
+        self.layout_debug_overlay
+            .recording(|| view.apply_bounds(bounds));
         view.prepare_for_drawing(self, canvas);
         bounds
     }
@@ -202,12 +363,186 @@ impl<'cx> UiContext<'cx> {
         view: &mut dyn View<'cx>,
     ) {
         view.preferred_size();
-        view.apply_bounds(bounds);
+        self.layout_debug_overlay
+            .recording(|| view.apply_bounds(bounds));
         view.prepare_for_drawing(self, canvas);
     }
 
+    /// Draw `view` and flush the display list of `render_pass`.
     pub fn draw_view(&self, render_pass: &mut RenderPass, view: &dyn View<'cx>) {
         view.draw(self, render_pass);
+        self.flush_display_list(render_pass);
+    }
+
+    /// Draw the items in the display list of `render_pass` in batches, and clear the display
+    /// list.
+    ///
+    /// Views that draw onto `RenderPass::wgpu_render_pass` directly should call this first, so
+    /// that they are drawn on top of the items pushed before them.
+    pub fn flush_display_list(&self, render_pass: &mut RenderPass) {
+        let display_list = render_pass.display_list_mut().take();
+        if display_list.is_empty() {
+            return;
+        }
+        let entries = display_list.entries();
+        let batches = display_list.batches();
+
+        // Instances of all the batches of each kind go into one buffer, each batch drawing a
+        // range of it.
+        let mut rect_instances = Vec::new();
+        let mut glyph_instances = Vec::new();
+        let mut image_instances = Vec::new();
+        let ranges: Vec<Range<u32>> = batches
+            .iter()
+            .map(|batch| {
+                let items = batch.entries.iter().map(|&index| &entries[index].item);
+                match batch.kind {
+                    BatchKind::Rects | BatchKind::StencilMask(_) => extend_instances(
+                        &mut rect_instances,
+                        items.map(|item| match item {
+                            DisplayItem::Rect(instance) | DisplayItem::StencilMask(instance, _) => {
+                                *instance
+                            }
+                            _ => unreachable!(),
+                        }),
+                    ),
+                    BatchKind::Glyphs => extend_instances(
+                        &mut glyph_instances,
+                        items.flat_map(|item| match item {
+                            DisplayItem::Glyphs(glyphs) => glyphs.iter().copied(),
+                            _ => unreachable!(),
+                        }),
+                    ),
+                    BatchKind::Images(_) => extend_instances(
+                        &mut image_instances,
+                        items.map(|item| match item {
+                            DisplayItem::Image(_, instance) => *instance,
+                            _ => unreachable!(),
+                        }),
+                    ),
+                    BatchKind::Unbatched => 0..0,
+                }
+            })
+            .collect();
+        // Written after the instances of the previous flushes of this render pass, which have not
+        // been drawn yet, so each batch range is offset by where its buffer's instances start.
+        let instance_buffers = &mut render_pass.instance_buffers;
+        let rects = self
+            .push_instances(&mut instance_buffers.rects, &rect_instances)
+            .map(|(buffer, range)| {
+                let rects = InstancedRectsElement::from_raw_parts(buffer, range.end);
+                (rects, range.start)
+            });
+        let glyphs = self
+            .push_instances(&mut instance_buffers.glyphs, &glyph_instances)
+            .map(|(buffer, range)| {
+                let glyphs = InstancedGlyphsElement::from_raw_parts(buffer, range.end);
+                (glyphs, range.start)
+            });
+        let images = self
+            .push_instances(&mut instance_buffers.images, &image_instances)
+            .map(|(buffer, range)| {
+                let images = InstancedImagesElement::from_raw_parts(buffer, range.end);
+                (images, range.start)
+            });
+        let mut texture_bindings = self.texture_bindings.lock().unwrap();
+
+        let clip_rect = render_pass.clip_rect();
+        for (batch, range) in batches.iter().zip(ranges) {
+            render_pass.apply_scissor(batch.clip_rect);
+            let wgpu_render_pass = render_pass.wgpu_render_pass();
+            wgpu_render_pass.set_stencil_reference(batch.stencil_reference);
+            match &batch.kind {
+                BatchKind::Rects => {
+                    let (rects, start) = rects.as_ref().unwrap();
+                    self.instanced_rect_renderer.draw_rect_range(
+                        wgpu_render_pass,
+                        rects,
+                        offset_range(range, *start),
+                    )
+                }
+                &BatchKind::StencilMask(op) => {
+                    let (rects, start) = rects.as_ref().unwrap();
+                    self.instanced_rect_renderer.draw_stencil_mask_range(
+                        wgpu_render_pass,
+                        rects,
+                        offset_range(range, *start),
+                        op,
+                    )
+                }
+                BatchKind::Glyphs => {
+                    let (glyphs, start) = glyphs.as_ref().unwrap();
+                    self.instanced_text_renderer.draw_glyph_range(
+                        wgpu_render_pass,
+                        glyphs,
+                        offset_range(range, *start),
+                    )
+                }
+                BatchKind::Images(_) => {
+                    let DisplayItem::Image(texture, _) = &entries[batch.entries[0]].item else {
+                        unreachable!()
+                    };
+                    let texture = texture_bindings.get_or_create(texture, || {
+                        self.instanced_image_renderer
+                            .create_texture_binding(&self.device, texture)
+                    });
+                    let (images, start) = images.as_ref().unwrap();
+                    self.instanced_image_renderer.draw_image_range(
+                        wgpu_render_pass,
+                        images,
+                        &texture,
+                        offset_range(range, *start),
+                    );
+                }
+                BatchKind::Unbatched => {
+                    for &index in &batch.entries {
+                        match &entries[index].item {
+                            DisplayItem::RectElement(rect) => {
+                                self.rect_renderer.draw_rect(wgpu_render_pass, rect)
+                            }
+                            DisplayItem::ImageElement(image) => {
+                                self.image_renderer.draw_image(wgpu_render_pass, image)
+                            }
+                            DisplayItem::Shadow(shadow) => {
+                                self.shadow_renderer.draw_shadow(wgpu_render_pass, shadow)
+                            }
+                            DisplayItem::Path(path) => {
+                                self.path_renderer.draw_path(wgpu_render_pass, path)
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+            }
+        }
+        render_pass.apply_scissor(clip_rect);
+        let stencil_reference = render_pass.stencil_reference();
+        render_pass
+            .wgpu_render_pass()
+            .set_stencil_reference(stencil_reference);
+    }
+
+    /// Write `instances` into `buffer`, returning the buffer they are in and their range in it, or
+    /// `None` if there are none.
+    fn push_instances<T: Vertex>(
+        &self,
+        buffer: &mut GrowableVertexBuffer<T>,
+        instances: &[T],
+    ) -> Option<(VertexBuffer<T>, Range<u32>)> {
+        if instances.is_empty() {
+            return None;
+        }
+        let range = buffer.push(&self.device, &self.queue, instances);
+        Some((buffer.buffer().unwrap().clone(), range))
+    }
+
+    /// Draw the outlines of the views laid out since the last call, if the layout debug overlay is
+    /// enabled.
+    ///
+    /// Should be called after all the views are drawn, so the overlay is on top of them.
+    pub fn draw_layout_debug_overlay(&self, render_pass: &mut RenderPass) {
+        self.flush_display_list(render_pass);
+        self.layout_debug_overlay.draw(self, render_pass);
     }
 
     pub fn create_texture(&self, image: ImageRef) -> Texture2d {
@@ -218,75 +553,470 @@ impl<'cx> UiContext<'cx> {
         LayoutPass::new()
     }
 
+    /// Begin drawing onto `canvas`, clearing its attachments according to `clear_values` (or
+    /// just a clear color).
     pub fn begin_render_pass(
         &self,
         canvas: &CanvasRef,
-        clear_color: impl Into<Rgba>,
+        clear_values: impl Into<ClearValues>,
     ) -> RenderPass {
-        assert!(
-            canvas.depth_stencil_texture_view.is_none(),
-            "TODO: drawing with depth stencil buffer"
-        );
-        let clear_color = clear_color.into();
-        let wgpu_clear_color = wgpu::Color {
+        self.begin_render_pass_with_camera(canvas, clear_values.into(), &self.camera)
+    }
+
+    fn begin_render_pass_with_camera(
+        &self,
+        canvas: &CanvasRef,
+        clear_values: ClearValues,
+        camera: &Camera,
+    ) -> RenderPass {
+        let depth_stencil_attachment = canvas.depth_stencil_texture_view.as_ref().map(|view| {
+            let format = view.texture().format();
+            wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops: format.has_depth_aspect().then(|| wgpu::Operations {
+                    load: load_op(clear_values.depth),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: format.has_stencil_aspect().then(|| wgpu::Operations {
+                    load: load_op(clear_values.stencil),
+                    store: wgpu::StoreOp::Store,
+                }),
+            }
+        });
+        let wgpu_clear_color = clear_values.color.map(|clear_color| wgpu::Color {
             r: clear_color.r as f64,
             g: clear_color.g as f64,
             b: clear_color.b as f64,
             a: clear_color.a as f64,
-        };
+        });
         let mut encoder = self.device.create_command_encoder(&the_default());
         let mut render_pass = encoder
             .begin_render_pass(&wgpu::RenderPassDescriptor {
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                     view: &canvas.color_texture_view,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu_clear_color),
+                        load: load_op(wgpu_clear_color),
                         store: wgpu::StoreOp::Store,
                     },
                     depth_slice: None,
-                    resolve_target: None,
+                    resolve_target: canvas.resolve_texture_view.as_ref(),
                 })],
+                depth_stencil_attachment,
                 ..the_default()
             })
             .forget_lifetime();
-        self.camera_bind_group
+        camera
+            .bind_group
             .set_projection(&self.queue, canvas.projection);
-        self.camera_bind_group
+        camera
+            .bind_group
             .set_aaf(&self.queue, 1. / (0.5 * canvas.logical_size.as_vec().sum()));
-        render_pass.set_bind_group(0, &self.camera_bind_group_wgpu, &[]);
-        RenderPass::from_raw_parts(self.queue.clone(), render_pass, encoder)
+        render_pass.set_bind_group(0, &camera.bind_group_wgpu, &[]);
+        self.texture_bindings.lock().unwrap().begin_render_pass();
+        let mut render_pass =
+            RenderPass::from_raw_parts(self.queue.clone(), render_pass, encoder, canvas);
+        let instance_buffers = self.instance_buffer_pool.lock().unwrap().pop();
+        render_pass.instance_buffers = instance_buffers.unwrap_or_default();
+        render_pass.instance_buffer_pool = Some(self.instance_buffer_pool.clone());
+        render_pass
+    }
+
+    /// Lay out and draw `view` onto a new `TextureCanvas` of `physical_size`, and copy the result
+    /// back to the CPU.
+    ///
+    /// The canvas has the format of this `UiContext`, whose color format must be one of the 8-bit
+    /// RGBA or BGRA formats, and whose alpha blend should be `AlphaBlend::Over` for the alpha of
+    /// the result to be right. Use `image::RgbaImage::save` to export the result as PNG.
+    ///
+    /// This can be called while drawing onto another canvas (the projection of that render pass
+    /// is kept), but the views drawn here should not be drawn in that render pass too, as their
+    /// GPU resources are updated for this canvas.
+    pub fn render_to_image(
+        &self,
+        view: &mut dyn View<'cx>,
+        physical_size: RectSize<u32>,
+        scale_factor: f32,
+        clear_values: impl Into<ClearValues>,
+    ) -> Result<image::RgbaImage, ReadCanvasError> {
+        let logical_size = RectSize::new(
+            physical_size.width as f32 / scale_factor,
+            physical_size.height as f32 / scale_factor,
+        );
+        let canvas = TextureCanvas::create(
+            &self.device,
+            self.canvas_format,
+            physical_size,
+            logical_size,
+        );
+        let canvas_ref = canvas.create_ref().unwrap();
+        self.prepare_view_bounded(&canvas_ref, canvas_ref.bounds(), view);
+        let mut render_pass = self.begin_render_pass_with_camera(
+            &canvas_ref,
+            clear_values.into(),
+            &self.offscreen_camera,
+        );
+        self.draw_view(&mut render_pass, view);
+        drop(render_pass);
+        canvas.read_to_image(&self.device, &self.queue)
     }
 }
 
+/// Values the attachments of a canvas are cleared to at the beginning of a `RenderPass`.
+///
+/// `None` keeps the existing content of the attachment instead, e.g. for drawing on top of 3D
+/// content. Note that views are only visible where the stencil buffer equals the current stencil
+/// reference, which is 0 outside of stencil masks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClearValues {
+    pub color: Option<Rgba>,
+    pub depth: Option<f32>,
+    pub stencil: Option<u32>,
+}
+
+impl ClearValues {
+    /// Clear the color to `color`, the depth to 1 and the stencil to 0.
+    pub const fn new(color: Rgba) -> Self {
+        Self {
+            color: Some(color),
+            depth: Some(1.),
+            stencil: Some(0),
+        }
+    }
+
+    /// Keep the existing content of all the attachments.
+    pub const fn load() -> Self {
+        Self {
+            color: None,
+            depth: None,
+            stencil: None,
+        }
+    }
+
+    pub const fn with_color(self, color: Option<Rgba>) -> Self {
+        Self { color, ..self }
+    }
+
+    pub const fn with_depth(self, depth: Option<f32>) -> Self {
+        Self { depth, ..self }
+    }
+
+    pub const fn with_stencil(self, stencil: Option<u32>) -> Self {
+        Self { stencil, ..self }
+    }
+}
+
+impl From<Rgba> for ClearValues {
+    fn from(color: Rgba) -> Self {
+        Self::new(color)
+    }
+}
+
+impl From<Srgba> for ClearValues {
+    fn from(color: Srgba) -> Self {
+        Self::new(color.into())
+    }
+}
+
+impl From<Srgb> for ClearValues {
+    fn from(color: Srgb) -> Self {
+        Self::new(color.into())
+    }
+}
+
+/// The projection and the anti-aliasing factor of a render pass.
+struct Camera {
+    bind_group: CameraBindGroup,
+    bind_group_wgpu: wgpu::BindGroup,
+}
+
+impl Camera {
+    fn create(device: &wgpu::Device) -> Self {
+        let bind_group = CameraBindGroup {
+            projection: UniformBuffer::create_init(device, Matrix4::identity().into()),
+            aaf: UniformBuffer::create_init(device, 0.),
+        };
+        let bind_group_wgpu = bind_group
+            .create_bind_group(&CameraBindGroup::create_bind_group_layout(device), device);
+        Self {
+            bind_group,
+            bind_group_wgpu,
+        }
+    }
+}
+
+/// The instance buffers a render pass writes the instances of its batches into.
+#[derive(Debug, Default)]
+struct InstanceBuffers {
+    rects: GrowableVertexBuffer<RectInstance>,
+    glyphs: GrowableVertexBuffer<GlyphInstance>,
+    images: GrowableVertexBuffer<ImageInstance>,
+}
+
+impl InstanceBuffers {
+    fn clear(&mut self) {
+        self.rects.clear();
+        self.glyphs.clear();
+        self.images.clear();
+    }
+}
+
+/// Bind groups of the textures of image batches, by texture view, so that they are not created for
+/// every batch.
+#[derive(Debug, Default)]
+struct TextureBindings {
+    bindings: HashMap<wgpu::TextureView, (ImageTextureBinding, u64)>,
+    /// Number of render passes begun, which each binding is stored with the last one it was used
+    /// in.
+    render_pass_count: u64,
+}
+
+impl TextureBindings {
+    /// Bindings unused in this many render passes are evicted, so that their textures can be
+    /// freed.
+    const MAX_UNUSED_RENDER_PASSES: u64 = 8;
+
+    fn begin_render_pass(&mut self) {
+        self.render_pass_count += 1;
+        let render_pass_count = self.render_pass_count;
+        self.bindings.retain(|_, (_, last_used)| {
+            render_pass_count - *last_used <= Self::MAX_UNUSED_RENDER_PASSES
+        });
+    }
+
+    fn get_or_create(
+        &mut self,
+        texture: &Texture2d,
+        create: impl FnOnce() -> ImageTextureBinding,
+    ) -> ImageTextureBinding {
+        let (binding, last_used) = self
+            .bindings
+            .entry(texture.wgpu_texture_view().clone())
+            .or_insert_with(|| (create(), 0));
+        *last_used = self.render_pass_count;
+        binding.clone()
+    }
+}
+
+/// Clear to `value`, or load the existing content if `None`.
+fn load_op<V>(value: Option<V>) -> wgpu::LoadOp<V> {
+    match value {
+        Some(value) => wgpu::LoadOp::Clear(value),
+        None => wgpu::LoadOp::Load,
+    }
+}
+
+/// Append `new_instances` to `instances`, returning the range of the appended instances.
+fn extend_instances<T>(
+    instances: &mut Vec<T>,
+    new_instances: impl IntoIterator<Item = T>,
+) -> Range<u32> {
+    let start = instances.len() as u32;
+    instances.extend(new_instances);
+    start..instances.len() as u32
+}
+
+fn offset_range(range: Range<u32>, offset: u32) -> Range<u32> {
+    range.start + offset..range.end + offset
+}
+
 pub struct RenderPass {
     queue: wgpu::Queue,
     render_pass: ManuallyDrop<wgpu::RenderPass<'static>>,
     encoder: MaybeUninit<wgpu::CommandEncoder>,
+    logical_size: RectSize<f32>,
+    physical_size: RectSize<u32>,
+    /// Clip rects pushed by `push_clip_rect`, each already intersected with the ones below it.
+    clip_stack: Vec<Bounds<f32>>,
+    /// Stencil masks pushed by `push_stencil_mask`.
+    stencil_masks: Vec<(RectInstance, Bounds<f32>)>,
+    has_stencil: bool,
+    display_list: DisplayList,
+    instance_buffers: InstanceBuffers,
+    /// Where `instance_buffers` is put back once the render pass is submitted, `None` for render
+    /// passes not begun by a `UiContext`.
+    instance_buffer_pool: Option<Arc<Mutex<Vec<InstanceBuffers>>>>,
 }
 
 unsafe impl Send for RenderPass {}
 unsafe impl Sync for RenderPass {}
 
 impl RenderPass {
+    /// `canvas` is the canvas `render_pass` is drawing onto.
     pub fn from_raw_parts(
         queue: wgpu::Queue,
         render_pass: wgpu::RenderPass,
         encoder: wgpu::CommandEncoder,
+        canvas: &CanvasRef,
     ) -> Self {
+        let physical_size = canvas.color_texture_view.texture().size();
+        let has_stencil = canvas
+            .depth_stencil_texture_view
+            .as_ref()
+            .is_some_and(|view| view.texture().format().has_stencil_aspect());
         Self {
             queue,
             render_pass: ManuallyDrop::new(render_pass.forget_lifetime()),
             encoder: MaybeUninit::new(encoder),
+            logical_size: canvas.logical_size,
+            physical_size: RectSize::new(physical_size.width, physical_size.height),
+            clip_stack: Vec::new(),
+            stencil_masks: Vec::new(),
+            has_stencil,
+            display_list: DisplayList::default(),
+            instance_buffers: InstanceBuffers::default(),
+            instance_buffer_pool: None,
         }
     }
 
     pub fn wgpu_render_pass(&mut self) -> &mut wgpu::RenderPass<'static> {
         &mut self.render_pass
     }
+
+    /// The items drawn by views since the last `UiContext::flush_display_list`.
+    pub fn display_list(&self) -> &DisplayList {
+        &self.display_list
+    }
+
+    pub fn display_list_mut(&mut self) -> &mut DisplayList {
+        &mut self.display_list
+    }
+
+    /// The rect (in logical coordinates) that drawing is currently restricted to, `None` if
+    /// drawing is not restricted.
+    pub fn clip_rect(&self) -> Option<Bounds<f32>> {
+        self.clip_stack.last().copied()
+    }
+
+    /// Restrict drawing to `clip_rect` (in logical coordinates) intersected with the current clip
+    /// rect, until the matching `pop_clip_rect`.
+    ///
+    /// Parts of `clip_rect` outside of the canvas are ignored.
+    pub fn push_clip_rect(&mut self, clip_rect: Bounds<f32>) {
+        let clip_rect = match self.clip_rect() {
+            Some(previous_clip_rect) => previous_clip_rect.intersection(clip_rect),
+            None => Some(clip_rect),
+        };
+        // Zero-sized, so that nothing drawn inside is visible.
+        let clip_rect = clip_rect.unwrap_or(Bounds::from_scalars(0., 0., 0., 0.));
+        self.clip_stack.push(clip_rect);
+        self.update_clip_rect();
+    }
+
+    /// Restore the clip rect from before the last `push_clip_rect`.
+    ///
+    /// # Panics
+    ///
+    /// - if there is no clip rect pushed
+    pub fn pop_clip_rect(&mut self) {
+        self.clip_stack
+            .pop()
+            .expect("`pop_clip_rect` without a matching `push_clip_rect`");
+        self.update_clip_rect();
+    }
+
+    /// Number of stencil masks pushed, which is what the stencil buffer equals inside all of them.
+    pub fn stencil_reference(&self) -> u32 {
+        self.stencil_masks.len() as u32
+    }
+
+    /// Restrict drawing to the inside of a rounded rect of `bounds` (in logical coordinates),
+    /// intersected with the current stencil mask, until the matching `pop_stencil_mask`.
+    ///
+    /// Unlike clip rects, stencil masks need the canvas to have a stencil buffer, and do nothing
+    /// otherwise. Views that draw onto `wgpu_render_pass` directly should flush the display list
+    /// first, as the masks are drawn into the stencil buffer when the display list is flushed.
+    pub fn push_stencil_mask(
+        &mut self,
+        bounds: Bounds<f32>,
+        corner_radius: impl Into<CornerRadius>,
+    ) {
+        let mask = RectInstance::from_parameters(bounds, 0.).with_corner_radius(corner_radius);
+        if self.has_stencil {
+            self.display_list
+                .push(DisplayItem::StencilMask(mask, StencilMaskOp::Push), bounds);
+        }
+        self.stencil_masks.push((mask, bounds));
+        self.update_stencil_reference();
+    }
+
+    /// Restore the stencil mask from before the last `push_stencil_mask`.
+    ///
+    /// # Panics
+    ///
+    /// - if there is no stencil mask pushed
+    pub fn pop_stencil_mask(&mut self) {
+        let (mask, bounds) = self
+            .stencil_masks
+            .last()
+            .copied()
+            .expect("`pop_stencil_mask` without a matching `push_stencil_mask`");
+        if self.has_stencil {
+            self.display_list
+                .push(DisplayItem::StencilMask(mask, StencilMaskOp::Pop), bounds);
+        }
+        self.stencil_masks.pop();
+        self.update_stencil_reference();
+    }
+
+    fn update_stencil_reference(&mut self) {
+        let stencil_reference = self.stencil_reference();
+        self.display_list.set_stencil_reference(stencil_reference);
+        self.render_pass.set_stencil_reference(stencil_reference);
+    }
+
+    fn update_clip_rect(&mut self) {
+        let clip_rect = self.clip_rect();
+        self.display_list.set_clip_rect(clip_rect);
+        self.apply_scissor(clip_rect);
+    }
+
+    /// Set the scissor rect of the wgpu render pass to `clip_rect`, without changing the clip rect
+    /// of the display list.
+    pub(crate) fn apply_scissor(&mut self, clip_rect: Option<Bounds<f32>>) {
+        let physical_bounds = Bounds::new(
+            point2(0., 0.),
+            RectSize::new(
+                self.physical_size.width as f32,
+                self.physical_size.height as f32,
+            ),
+        );
+        let scale = vec2(
+            physical_bounds.width() / self.logical_size.width,
+            physical_bounds.height() / self.logical_size.height,
+        );
+        let scissor = clip_rect
+            .map(|clip_rect| {
+                Bounds::new(
+                    point2(clip_rect.x_min() * scale.x, clip_rect.y_min() * scale.y),
+                    clip_rect.size.scaled(scale.x, scale.y),
+                )
+            })
+            .map_or(Some(physical_bounds), |bounds| {
+                bounds.intersection(physical_bounds)
+            });
+        match scissor {
+            Some(scissor) => {
+                let x_min = scissor.x_min().floor() as u32;
+                let y_min = scissor.y_min().floor() as u32;
+                let x_max = (scissor.x_max().ceil() as u32).min(self.physical_size.width);
+                let y_max = (scissor.y_max().ceil() as u32).min(self.physical_size.height);
+                self.render_pass
+                    .set_scissor_rect(x_min, y_min, x_max - x_min, y_max - y_min);
+            }
+            // Nothing is visible.
+            None => self.render_pass.set_scissor_rect(0, 0, 0, 0),
+        }
+    }
 }
 
 impl Drop for RenderPass {
     fn drop(&mut self) {
+        if !self.display_list.is_empty() {
+            log::warn!(
+                "`RenderPass` is dropped with {} display items not flushed",
+                self.display_list.len()
+            );
+        }
         unsafe { drop_in_place::<wgpu::RenderPass>(&mut *self.render_pass) };
         let encoder = {
             let mut encoder: MaybeUninit<wgpu::CommandEncoder> = MaybeUninit::uninit();
@@ -294,5 +1024,11 @@ impl Drop for RenderPass {
             unsafe { encoder.assume_init() }
         };
         self.queue.submit([encoder.finish()]);
+        // Overwriting the instances from now on only affects the next submissions.
+        if let Some(instance_buffer_pool) = self.instance_buffer_pool.take() {
+            let mut instance_buffers = std::mem::take(&mut self.instance_buffers);
+            instance_buffers.clear();
+            instance_buffer_pool.lock().unwrap().push(instance_buffers);
+        }
     }
 }
@@ -1,4 +1,7 @@
-use std::{marker::PhantomData, ops::RangeBounds};
+use std::{
+    marker::PhantomData,
+    ops::{Range, RangeBounds},
+};
 
 use bytemuck::{Pod, Zeroable};
 use cgmath::*;
@@ -27,6 +30,20 @@ impl<T: Vertex> VertexBuffer<T> {
         }
     }
 
+    /// Uninitialized, with room for `capacity` elements, for `wgpu::Queue::write_buffer`.
+    pub fn create(device: &wgpu::Device, capacity: usize) -> Self {
+        let wgpu_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (capacity * size_of::<T>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            wgpu_buffer,
+            _marker: PhantomData,
+        }
+    }
+
     pub fn wgpu_buffer(&self) -> &wgpu::Buffer {
         &self.wgpu_buffer
     }
@@ -44,6 +61,78 @@ impl<T: Vertex> VertexBuffer<T> {
     }
 }
 
+/// A vertex buffer that is kept across frames, which elements are appended to with
+/// `wgpu::Queue::write_buffer` until it is cleared.
+///
+/// When the elements do not fit, a larger buffer is created, and the old one is kept alive by the
+/// commands using it.
+#[derive(Debug)]
+pub struct GrowableVertexBuffer<T: Vertex> {
+    buffer: Option<VertexBuffer<T>>,
+    capacity: usize,
+    len: usize,
+}
+
+impl<T: Vertex> Default for GrowableVertexBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Vertex> GrowableVertexBuffer<T> {
+    pub fn new() -> Self {
+        Self {
+            buffer: None,
+            capacity: 0,
+            len: 0,
+        }
+    }
+
+    /// The buffer written to by the last `push`.
+    pub fn buffer(&self) -> Option<&VertexBuffer<T>> {
+        self.buffer.as_ref()
+    }
+
+    /// Number of elements in `buffer`.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Let the next `push` overwrite the elements, which must not be used by commands that have
+    /// not been submitted yet, as writes only take effect on the next submission.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Write `elements` after the ones pushed since the last `clear`, and return their range of
+    /// indices in `buffer`.
+    pub fn push(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        elements: &[T],
+    ) -> Range<u32> {
+        if elements.is_empty() {
+            return self.len as u32..self.len as u32;
+        }
+        if self.buffer.is_none() || self.len + elements.len() > self.capacity {
+            self.capacity = (2 * self.capacity).max(elements.len());
+            self.buffer = Some(VertexBuffer::create(device, self.capacity));
+            self.len = 0;
+        }
+        let buffer = self.buffer.as_ref().unwrap();
+        let offset = (self.len * size_of::<T>()) as u64;
+        queue.write_buffer(buffer.wgpu_buffer(), offset, bytemuck::cast_slice(elements));
+        let start = self.len;
+        self.len += elements.len();
+        start as u32..self.len as u32
+    }
+}
+
 pub mod vertex_formats {
     use super::*;
 
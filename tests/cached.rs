@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+use muilib::{
+    AppResources, Bounds, CanvasRef, EventRouter, LayoutCache, Path, PathView, RectSize, RectView,
+    RenderPass, Rgba, ScrollState, Srgb, UiContext, View, cgmath::point2, snapshot,
+};
+
+fn create_ui_context(resources: &AppResources) -> UiContext<'_> {
+    UiContext::create_headless_software(resources, snapshot::Snapshots::CANVAS_FORMAT)
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn resources() -> AppResources {
+    AppResources::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("res"))
+}
+
+#[test]
+fn scroll_view_in_cached_draws_in_every_frame() {
+    let resources = resources();
+    let ui_context = create_ui_context(&resources);
+    let event_router = EventRouter::<()>::new();
+    let mut scroll_state = ScrollState::new(&event_router);
+    let mut cache = LayoutCache::new();
+    let mut content =
+        RectView::new(RectSize::new(64., 256.)).with_fill_color(Srgb::from_hex(0xFF0000));
+
+    let mut render_frame = || {
+        let layout = ui_context.begin_layout_pass();
+        let scroll_view = layout.scroll_view(&mut scroll_state, &mut content);
+        let cached = layout.cached(&mut cache, scroll_view);
+        ui_context
+            .render_to_image(cached, RectSize::new(64, 64), 1., Rgba::new(0., 0., 0., 1.))
+            .unwrap()
+    };
+    let first_frame = render_frame();
+    let second_frame = render_frame();
+
+    assert_eq!(first_frame.get_pixel(16, 32).0, [255, 0, 0, 255]);
+    assert!(
+        first_frame == second_frame,
+        "the cached subtree is drawn differently in the second frame"
+    );
+}
+
+fn circle() -> PathView {
+    let mut path = Path::new();
+    path.move_to(point2(60., 32.))
+        .arc(point2(32., 32.), 28., 0., 2. * std::f32::consts::PI)
+        .close();
+    PathView::new(RectSize::new(64., 64.), path).with_fill_color(Some(Rgba::new(1., 0., 0., 1.)))
+}
+
+#[test]
+fn cached_path_is_prepared_again_for_a_new_scale_factor() {
+    let resources = resources();
+    let ui_context = create_ui_context(&resources);
+    let mut cache = LayoutCache::new();
+    let mut path_view = circle();
+    let clear_color = Rgba::new(0., 0., 0., 1.);
+
+    let mut render_frame = |physical_size, scale_factor| {
+        let layout = ui_context.begin_layout_pass();
+        let cached = layout.cached(&mut cache, &mut path_view);
+        ui_context
+            .render_to_image(cached, physical_size, scale_factor, clear_color)
+            .unwrap()
+    };
+    // The same logical bounds in both frames.
+    render_frame(RectSize::new(64, 64), 1.);
+    let second_frame = render_frame(RectSize::new(128, 128), 2.);
+
+    let expected = ui_context
+        .render_to_image(&mut circle(), RectSize::new(128, 128), 2., clear_color)
+        .unwrap();
+    assert!(
+        second_frame == expected,
+        "the cached path is not tessellated for the new scale factor"
+    );
+}
+
+/// Turns dirty in `apply_bounds` if `dirty_on_apply_bounds` is set, even if its bounds are the
+/// same.
+struct DirtiedInApplyBounds {
+    dirty_on_apply_bounds: bool,
+    needs_update: bool,
+    prepare_count: usize,
+}
+
+impl<'cx> View<'cx> for DirtiedInApplyBounds {
+    fn preferred_size(&mut self) -> RectSize<f32> {
+        RectSize::new(16., 16.)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.needs_update
+    }
+
+    fn apply_bounds(&mut self, _bounds: Bounds<f32>) {
+        if self.dirty_on_apply_bounds {
+            self.dirty_on_apply_bounds = false;
+            self.needs_update = true;
+        }
+    }
+
+    fn prepare_for_drawing(&mut self, _ui_context: &UiContext<'cx>, _canvas: &CanvasRef) {
+        self.needs_update = false;
+        self.prepare_count += 1;
+    }
+
+    fn draw(&self, _ui_context: &UiContext<'cx>, _render_pass: &mut RenderPass) {}
+}
+
+#[test]
+fn subview_dirtied_in_apply_bounds_is_prepared() {
+    let resources = resources();
+    let ui_context = create_ui_context(&resources);
+    let mut cache = LayoutCache::new();
+    let mut view = DirtiedInApplyBounds {
+        dirty_on_apply_bounds: false,
+        needs_update: true,
+        prepare_count: 0,
+    };
+
+    let mut render_frame = |view: &mut DirtiedInApplyBounds| {
+        let layout = ui_context.begin_layout_pass();
+        let cached = layout.cached(&mut cache, view);
+        ui_context
+            .render_to_image(cached, RectSize::new(16, 16), 1., Rgba::new(0., 0., 0., 1.))
+            .unwrap();
+    };
+    render_frame(&mut view);
+    assert_eq!(view.prepare_count, 1);
+    render_frame(&mut view);
+    assert_eq!(view.prepare_count, 1, "the clean subview is prepared again");
+    view.dirty_on_apply_bounds = true;
+    render_frame(&mut view);
+    assert_eq!(view.prepare_count, 2, "the dirtied subview is not prepared");
+}
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use muilib::{
+    AppResources, CornerRadius, LineWidth, RectSize, RectView, Srgb, StackAlignmentHorizontal,
+    StackAlignmentVertical, TextView, snapshot::Snapshots,
+};
+
+fn manifest_directory() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn rects_and_text() {
+    let resources = AppResources::new(manifest_directory().join("res"));
+    let snapshots = Snapshots::new(&resources, manifest_directory().join("tests/snapshots"))
+        .unwrap_or_else(|e| panic!("{e}"));
+    let ui_context = snapshots.ui_context();
+    let mut rounded_rect = RectView::new(RectSize::new(48., 24.))
+        .with_fill_color(Srgb::from_hex(0x3070C0))
+        .with_line_color(Srgb::from_hex(0xFFFFFF))
+        .with_line_width(LineWidth::Uniform(2.))
+        .with_corner_radius(CornerRadius::Uniform(8.));
+    let mut square =
+        RectView::new(RectSize::new(16., 16.)).with_fill_color(Srgb::from_hex(0xE04040));
+    let mut text = TextView::new(ui_context)
+        .with_text("snap")
+        .with_fg_color(Srgb::from_hex(0x000000));
+
+    let layout = ui_context.begin_layout_pass();
+    let stack = layout.vstack(|vstack| {
+        vstack.set_fixed_padding(4.);
+        vstack.set_alignment_horizontal(StackAlignmentHorizontal::Left);
+        vstack.set_alignment_vertical(StackAlignmentVertical::Top);
+        vstack.subview(&mut rounded_rect);
+        vstack.subview(&mut square);
+        vstack.subview(&mut text);
+    });
+    snapshots.assert_snapshot("rects_and_text", stack, RectSize::new(64, 80));
+}